@@ -0,0 +1,188 @@
+use std::str::FromStr;
+
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+/// Kind of artwork GameTDB publishes for a game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArtKind {
+    /// Standard front cover (`cover`)
+    #[default]
+    Cover,
+    /// Medium front cover (`coverM`)
+    CoverM,
+    /// Small front cover (`coverS`)
+    CoverS,
+    /// High resolution front cover (`coverHQ`)
+    CoverHq,
+    /// 3D box render (`box`)
+    Box3d,
+    /// Cartridge label (`cart`)
+    Cart,
+    /// Full wrap-around cover, back + spine + front (`coverfull`)
+    Full,
+    /// High resolution full cover (`coverfullHQ`)
+    FullHq,
+}
+
+/// How a piece of art is fitted into the 106×96 cover area
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtFit {
+    /// Scale to fill the whole area (front covers are already close to the right shape)
+    Stretch,
+    /// Scale to fit inside the area keeping the aspect ratio, centred on black
+    Contain,
+}
+
+impl ArtKind {
+    /// All kinds, in the order they are presented to users
+    pub const ALL: [ArtKind; 8] = [
+        ArtKind::Cover,
+        ArtKind::CoverM,
+        ArtKind::CoverS,
+        ArtKind::CoverHq,
+        ArtKind::Box3d,
+        ArtKind::Cart,
+        ArtKind::Full,
+        ArtKind::FullHq,
+    ];
+
+    /// Get the identifier used on the command line and in JS options
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArtKind::Cover => "cover",
+            ArtKind::CoverM => "cover-m",
+            ArtKind::CoverS => "cover-s",
+            ArtKind::CoverHq => "cover-hq",
+            ArtKind::Box3d => "box3d",
+            ArtKind::Cart => "cart",
+            ArtKind::Full => "full",
+            ArtKind::FullHq => "full-hq",
+        }
+    }
+
+    /// Get a human readable name for the kind
+    pub fn label(&self) -> &'static str {
+        match self {
+            ArtKind::Cover => "Front cover",
+            ArtKind::CoverM => "Front cover (medium)",
+            ArtKind::CoverS => "Front cover (small)",
+            ArtKind::CoverHq => "Front cover (HQ)",
+            ArtKind::Box3d => "3D box",
+            ArtKind::Cart => "Cartridge",
+            ArtKind::Full => "Full cover",
+            ArtKind::FullHq => "Full cover (HQ)",
+        }
+    }
+
+    /// Get the GameTDB path segment substituted for `{kind}` in URL templates
+    pub fn gametdb_path(&self) -> &'static str {
+        match self {
+            ArtKind::Cover => "cover",
+            ArtKind::CoverM => "coverM",
+            ArtKind::CoverS => "coverS",
+            ArtKind::CoverHq => "coverHQ",
+            ArtKind::Box3d => "box",
+            ArtKind::Cart => "cart",
+            ArtKind::Full => "coverfull",
+            ArtKind::FullHq => "coverfullHQ",
+        }
+    }
+
+    /// Get how this kind is fitted into the cover area
+    pub fn fit(&self) -> ArtFit {
+        match self {
+            ArtKind::Cover | ArtKind::CoverM | ArtKind::CoverS | ArtKind::CoverHq => {
+                ArtFit::Stretch
+            }
+            // Full covers are cropped to the front panel first, which is cover-shaped
+            ArtKind::Full | ArtKind::FullHq => ArtFit::Stretch,
+            ArtKind::Box3d | ArtKind::Cart => ArtFit::Contain,
+        }
+    }
+
+    /// Crop the art down to the part that should be shown
+    ///
+    /// Full covers are laid out back, spine, front, so only the right half is kept.
+    pub fn crop(&self, img: &DynamicImage) -> DynamicImage {
+        match self {
+            ArtKind::Full | ArtKind::FullHq => {
+                let half = img.width() / 2;
+                img.crop_imm(half, 0, img.width() - half, img.height())
+            }
+            _ => img.clone(),
+        }
+    }
+
+    /// Expand a URL template for this kind
+    ///
+    /// Returns `None` when the template has no `{kind}` placeholder and this is
+    /// not the standard front cover, since the URL would point at the same file.
+    pub fn expand_template(&self, template: &str) -> Option<String> {
+        if template.contains("{kind}") {
+            Some(template.replace("{kind}", self.gametdb_path()))
+        } else if *self == ArtKind::Cover {
+            Some(template.to_string())
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for ArtKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        ArtKind::ALL
+            .into_iter()
+            .find(|kind| {
+                kind.as_str().eq_ignore_ascii_case(s) || kind.gametdb_path().eq_ignore_ascii_case(s)
+            })
+            .ok_or_else(|| {
+                format!(
+                    "Unknown art kind '{s}' (expected cover, cover-m, cover-s, cover-hq, box3d, cart, full or full-hq)"
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for ArtKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_template() {
+        let template = "https://art.gametdb.com/ds/{kind}/{region}/{id}.png";
+        assert_eq!(
+            ArtKind::Cart.expand_template(template).unwrap(),
+            "https://art.gametdb.com/ds/cart/{region}/{id}.png"
+        );
+
+        let legacy = "https://art.gametdb.com/ds/cover/{region}/{id}.png";
+        assert_eq!(ArtKind::Cover.expand_template(legacy).unwrap(), legacy);
+        assert!(ArtKind::Box3d.expand_template(legacy).is_none());
+    }
+
+    #[test]
+    fn test_full_cover_crops_front_half() {
+        let img = DynamicImage::new_rgba8(300, 100);
+        let front = ArtKind::FullHq.crop(&img);
+        assert_eq!((front.width(), front.height()), (150, 100));
+        assert_eq!(ArtKind::Cover.crop(&img).width(), 300);
+    }
+
+    #[test]
+    fn test_art_kind_from_str() {
+        assert_eq!("cart".parse(), Ok(ArtKind::Cart));
+        assert_eq!("coverHQ".parse(), Ok(ArtKind::CoverHq));
+        assert_eq!("box3d".parse(), Ok(ArtKind::Box3d));
+        assert!("poster".parse::<ArtKind>().is_err());
+    }
+}
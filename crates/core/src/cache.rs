@@ -0,0 +1,490 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::errors::Result;
+use crate::http::{HttpClient, HttpMethod, HttpRequest, HttpResponse};
+
+/// Default size limit of the download cache (512 MiB)
+pub const DEFAULT_MAX_BYTES: u64 = 512 * 1024 * 1024;
+/// Default age after which cached entries are revalidated (30 days)
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Validators and bookkeeping stored next to each cached body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheMeta {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_type: Option<String>,
+    /// Unix time the body was last confirmed by the server
+    pub fetched_at: u64,
+    /// SHA-256 of the body, hex encoded
+    pub sha256: String,
+}
+
+/// A cached response body with its metadata
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub meta: CacheMeta,
+    pub body: Vec<u8>,
+}
+
+impl CacheEntry {
+    /// Whether the entry was confirmed by the server within `max_age`
+    pub fn is_fresh(&self, max_age: Duration) -> bool {
+        unix_now().saturating_sub(self.meta.fetched_at) < max_age.as_secs()
+    }
+
+    fn to_response(&self) -> HttpResponse {
+        let mut headers = Vec::new();
+        if let Some(content_type) = &self.meta.content_type {
+            headers.push(("content-type".to_string(), content_type.clone()));
+        }
+        if let Some(etag) = &self.meta.etag {
+            headers.push(("etag".to_string(), etag.clone()));
+        }
+        if let Some(last_modified) = &self.meta.last_modified {
+            headers.push(("last-modified".to_string(), last_modified.clone()));
+        }
+        HttpResponse {
+            status: 200,
+            headers,
+            body: self.body.clone(),
+        }
+    }
+}
+
+/// Result of pruning the cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneStats {
+    pub removed: usize,
+    pub freed_bytes: u64,
+    pub remaining: usize,
+    pub remaining_bytes: u64,
+}
+
+/// On-disk store of downloaded originals
+///
+/// Each URL is stored as `<sha256(url)>.bin` plus a `<sha256(url)>.json`
+/// metadata file holding the ETag/Last-Modified validators.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    max_age: Duration,
+}
+
+impl DiskCache {
+    /// Open (and create) a cache in `dir` with the default limits
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_age: DEFAULT_MAX_AGE,
+        })
+    }
+
+    /// Set the size limit enforced by [`DiskCache::prune`]
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Set how long entries are served without asking the server
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Get the cache folder
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get how long entries are served without revalidation
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Look up the cached body for a URL
+    pub fn get(&self, url: &str) -> Option<CacheEntry> {
+        let key = cache_key(url);
+        let meta: CacheMeta = serde_json::from_slice(&fs::read(self.meta_path(&key)).ok()?).ok()?;
+        if meta.url != url {
+            return None;
+        }
+        let body = fs::read(self.body_path(&key)).ok()?;
+        if hex_digest(&body) != meta.sha256 {
+            return None;
+        }
+        Some(CacheEntry { meta, body })
+    }
+
+    /// Store a successful response for a URL
+    pub fn put(&self, url: &str, response: &HttpResponse) -> Result<CacheEntry> {
+        let key = cache_key(url);
+        let meta = CacheMeta {
+            url: url.to_string(),
+            etag: response.header("etag").map(str::to_string),
+            last_modified: response.header("last-modified").map(str::to_string),
+            content_type: response.header("content-type").map(str::to_string),
+            fetched_at: unix_now(),
+            sha256: hex_digest(&response.body),
+        };
+        write_atomic(&self.body_path(&key), &response.body)?;
+        self.write_meta(&key, &meta)?;
+        Ok(CacheEntry {
+            meta,
+            body: response.body.clone(),
+        })
+    }
+
+    /// Metadata of every complete entry
+    pub fn list(&self) -> Result<Vec<CacheMeta>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|(body, _, _, _)| body.is_file())
+            .filter_map(|(_, meta, _, _)| serde_json::from_slice(&fs::read(meta).ok()?).ok())
+            .collect())
+    }
+
+    /// Mark an entry as confirmed by the server (after a 304)
+    pub fn touch(&self, entry: &mut CacheEntry) -> Result<()> {
+        entry.meta.fetched_at = unix_now();
+        self.write_meta(&cache_key(&entry.meta.url), &entry.meta)
+    }
+
+    /// Remove all entries
+    pub fn clear(&self) -> Result<()> {
+        for (body, meta, _, _) in self.entries()? {
+            let _ = fs::remove_file(body);
+            let _ = fs::remove_file(meta);
+        }
+        Ok(())
+    }
+
+    /// Drop incomplete entries, then the least recently confirmed ones until
+    /// the cache fits within its size limit
+    pub fn prune(&self) -> Result<PruneStats> {
+        let mut stats = PruneStats::default();
+        let mut entries = self.entries()?;
+        // Oldest first
+        entries.sort_by_key(|(_, _, _, fetched_at)| *fetched_at);
+
+        let mut total: u64 = entries.iter().map(|(_, _, size, _)| size).sum();
+        for (body, meta, size, _) in entries {
+            let incomplete = !body.is_file() || !meta.is_file();
+            if incomplete || total > self.max_bytes {
+                let _ = fs::remove_file(&body);
+                let _ = fs::remove_file(&meta);
+                total -= size;
+                stats.removed += 1;
+                stats.freed_bytes += size;
+            } else {
+                stats.remaining += 1;
+            }
+        }
+
+        stats.remaining_bytes = total;
+        Ok(stats)
+    }
+
+    /// List `(body, meta, size, fetched_at)` for every key found on disk
+    fn entries(&self) -> Result<Vec<(PathBuf, PathBuf, u64, u64)>> {
+        let mut keys: Vec<String> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                let ext = path.extension()?.to_str()?;
+                if ext != "bin" && ext != "json" {
+                    return None;
+                }
+                let key = path.file_stem()?.to_str()?;
+                // Other files (such as the miss cache) may share the folder
+                let is_key = key.len() == 64 && key.bytes().all(|b| b.is_ascii_hexdigit());
+                is_key.then(|| key.to_string())
+            })
+            .collect();
+        keys.sort();
+        keys.dedup();
+
+        Ok(keys
+            .into_iter()
+            .map(|key| {
+                let body = self.body_path(&key);
+                let meta = self.meta_path(&key);
+                let size = [&body, &meta]
+                    .iter()
+                    .filter_map(|path| fs::metadata(path).ok())
+                    .map(|m| m.len())
+                    .sum();
+                let fetched_at = fs::read(&meta)
+                    .ok()
+                    .and_then(|bytes| serde_json::from_slice::<CacheMeta>(&bytes).ok())
+                    .map(|meta| meta.fetched_at)
+                    .unwrap_or(0);
+                (body, meta, size, fetched_at)
+            })
+            .collect())
+    }
+
+    fn write_meta(&self, key: &str, meta: &CacheMeta) -> Result<()> {
+        let json = serde_json::to_vec_pretty(meta)
+            .map_err(|e| crate::errors::Error::Other(e.to_string()))?;
+        write_atomic(&self.meta_path(key), &json)
+    }
+
+    fn body_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.bin"))
+    }
+
+    fn meta_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+
+/// HTTP client that serves originals from a [`DiskCache`]
+///
+/// Fresh entries are returned without touching the network. Stale entries
+/// are revalidated with `If-None-Match`/`If-Modified-Since`; a `304` or a
+/// failed revalidation keeps the cached body. Only `200` responses are
+/// stored: misses are left to [`MissCache`](crate::source::MissCache) so
+/// they follow the miss TTL and `--recheck-missing`.
+pub struct CachingClient {
+    inner: Arc<dyn HttpClient>,
+    cache: DiskCache,
+}
+
+impl CachingClient {
+    /// Wrap `inner` with the given cache
+    pub fn new(inner: Arc<dyn HttpClient>, cache: DiskCache) -> Self {
+        Self { inner, cache }
+    }
+
+    /// Get the underlying cache
+    pub fn cache(&self) -> &DiskCache {
+        &self.cache
+    }
+}
+
+#[async_trait]
+impl HttpClient for CachingClient {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        // Probes are answered from the cache when possible but never stored
+        if request.method == HttpMethod::Head {
+            return match self.cache.get(&request.url) {
+                Some(entry) if entry.is_fresh(self.cache.max_age) => Ok(HttpResponse {
+                    body: Vec::new(),
+                    ..entry.to_response()
+                }),
+                _ => self.inner.send(request).await,
+            };
+        }
+
+        let url = request.url.clone();
+        let Some(mut entry) = self.cache.get(&url) else {
+            let response = self.inner.send(request).await?;
+            if response.status == 200 {
+                // A failed cache write should not fail the download
+                let _ = self.cache.put(&url, &response);
+            }
+            return Ok(response);
+        };
+
+        if entry.is_fresh(self.cache.max_age) {
+            return Ok(entry.to_response());
+        }
+
+        let mut conditional = request;
+        if let Some(etag) = &entry.meta.etag {
+            conditional = conditional.header("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &entry.meta.last_modified {
+            conditional = conditional.header("If-Modified-Since", last_modified);
+        }
+
+        match self.inner.send(conditional).await {
+            Ok(response) if response.status == 200 => {
+                let _ = self.cache.put(&url, &response);
+                Ok(response)
+            }
+            Ok(response) if response.status == 304 => {
+                let _ = self.cache.touch(&mut entry);
+                Ok(entry.to_response())
+            }
+            // Server errors or offline: a stale original is better than no cover
+            Ok(_) | Err(_) => Ok(entry.to_response()),
+        }
+    }
+}
+
+/// Cache key for a URL (hex SHA-256)
+pub fn cache_key(url: &str) -> String {
+    hex_digest(url.as_bytes())
+}
+
+pub(crate) fn hex_digest(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::testing::MockClient;
+
+    fn response(body: &[u8]) -> HttpResponse {
+        HttpResponse {
+            status: 200,
+            headers: vec![("ETag".to_string(), "\"v1\"".to_string())],
+            body: body.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_fresh_entries_skip_network() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = Arc::new(MockClient::default().with_body("http://x/a.png", b"art".to_vec()));
+        let client = CachingClient::new(mock.clone(), DiskCache::open(dir.path()).unwrap());
+
+        for _ in 0..3 {
+            let res = futures::executor::block_on(client.send(HttpRequest::get("http://x/a.png")))
+                .unwrap();
+            assert_eq!(res.body, b"art");
+        }
+        assert_eq!(mock.requested().len(), 1);
+    }
+
+    #[test]
+    fn test_stale_entries_revalidate_with_etag() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::open(dir.path())
+            .unwrap()
+            .with_max_age(Duration::ZERO);
+        cache.put("http://x/a.png", &response(b"old")).unwrap();
+
+        let mock = Arc::new(MockClient::default().with_body("http://x/a.png", b"new".to_vec()));
+        let client = CachingClient::new(mock.clone(), cache);
+        let res =
+            futures::executor::block_on(client.send(HttpRequest::get("http://x/a.png"))).unwrap();
+        assert_eq!(res.body, b"new");
+        assert_eq!(client.cache().get("http://x/a.png").unwrap().body, b"new");
+    }
+
+    #[test]
+    fn test_misses_are_rechecked() {
+        use crate::game::{GameCode, Platform};
+        use crate::source::{ChainedSource, CoverRequest, CoverSource, ProxySource};
+        use crate::source::{MissCache, DEFAULT_MISS_TTL};
+
+        let dir = tempfile::tempdir().unwrap();
+        let request = CoverRequest::new(GameCode::from_bytes(b"AXVE").unwrap(), Platform::Gba);
+        for run in 0..2 {
+            let mock = Arc::new(MockClient::default());
+            let client = Arc::new(CachingClient::new(
+                mock.clone(),
+                DiskCache::open(dir.path()).unwrap(),
+            ));
+            let misses = MissCache::new(DEFAULT_MISS_TTL).with_recheck(run == 1);
+            let chain = ChainedSource::new()
+                .with(ProxySource::new(client, "https://proxy.test"))
+                .with_miss_cache(Arc::new(misses));
+
+            let result = futures::executor::block_on(chain.fetch(&request)).unwrap();
+            assert!(result.is_none());
+            // The 404 is not kept on disk, so a recheck asks the server again
+            assert_eq!(mock.requested(), vec!["https://proxy.test/gba/AXVE"]);
+        }
+    }
+
+    #[test]
+    fn test_revalidation_sends_validators() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::open(dir.path())
+            .unwrap()
+            .with_max_age(Duration::ZERO);
+        let mut original = response(b"old");
+        original.headers.push((
+            "Last-Modified".to_string(),
+            "Wed, 01 Jan 2025 00:00:00 GMT".to_string(),
+        ));
+        cache.put("http://x/a.png", &original).unwrap();
+
+        let mock = Arc::new(MockClient::default().with_status("http://x/a.png", 304));
+        let client = CachingClient::new(mock.clone(), cache);
+        let res =
+            futures::executor::block_on(client.send(HttpRequest::get("http://x/a.png"))).unwrap();
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body, b"old");
+
+        let sent = mock.sent();
+        let header = |name: &str| {
+            sent[0]
+                .headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+        };
+        assert_eq!(header("If-None-Match").as_deref(), Some("\"v1\""));
+        assert_eq!(
+            header("If-Modified-Since").as_deref(),
+            Some("Wed, 01 Jan 2025 00:00:00 GMT")
+        );
+    }
+
+    #[test]
+    fn test_failed_revalidation_keeps_stale_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::open(dir.path())
+            .unwrap()
+            .with_max_age(Duration::ZERO);
+        cache.put("http://x/a.png", &response(b"old")).unwrap();
+
+        let mock = Arc::new(MockClient::default().with_status("http://x/a.png", 503));
+        let client = CachingClient::new(mock, cache);
+        let res =
+            futures::executor::block_on(client.send(HttpRequest::get("http://x/a.png"))).unwrap();
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body, b"old");
+        assert_eq!(client.cache().get("http://x/a.png").unwrap().body, b"old");
+    }
+
+    #[test]
+    fn test_prune_enforces_size_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::open(dir.path()).unwrap().with_max_bytes(0);
+        cache.put("http://x/a.png", &response(&[0; 64])).unwrap();
+        cache.put("http://x/b.png", &response(&[1; 64])).unwrap();
+
+        let stats = cache.prune().unwrap();
+        assert_eq!(stats.removed, 2);
+        assert_eq!(stats.remaining, 0);
+        assert!(cache.get("http://x/a.png").is_none());
+    }
+}
@@ -0,0 +1,220 @@
+use std::str::FromStr;
+
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+/// Target screen the cover will be shown on
+///
+/// The original DS and DS Lite panels are washed out and only show 18-bit colour
+/// (6 bits per channel), so covers are tuned per screen before quantization.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisplayProfile {
+    /// Original Nintendo DS (dim, low-contrast panel)
+    DsPhat,
+    /// Nintendo DS Lite (brighter but still washed out)
+    DsLite,
+    /// Nintendo DSi / DSi XL
+    Dsi,
+    /// PC emulator or capture: no adjustment, full 8-bit colour
+    #[default]
+    Emulator,
+}
+
+impl DisplayProfile {
+    /// All profiles, in the order they are presented to users
+    pub const ALL: [DisplayProfile; 4] = [
+        DisplayProfile::DsPhat,
+        DisplayProfile::DsLite,
+        DisplayProfile::Dsi,
+        DisplayProfile::Emulator,
+    ];
+
+    /// Get the identifier used on the command line and in JS options
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DisplayProfile::DsPhat => "ds-phat",
+            DisplayProfile::DsLite => "ds-lite",
+            DisplayProfile::Dsi => "dsi",
+            DisplayProfile::Emulator => "emulator",
+        }
+    }
+
+    /// Get a human readable name for the profile
+    pub fn label(&self) -> &'static str {
+        match self {
+            DisplayProfile::DsPhat => "DS (original)",
+            DisplayProfile::DsLite => "DS Lite",
+            DisplayProfile::Dsi => "DSi",
+            DisplayProfile::Emulator => "Emulator / PC",
+        }
+    }
+
+    /// Get the colour adjustment applied for this profile
+    pub fn adjustment(&self) -> ColorAdjustment {
+        match self {
+            DisplayProfile::DsPhat => ColorAdjustment {
+                gamma: 1.25,
+                saturation: 1.35,
+                contrast: 1.15,
+                bits_per_channel: 6,
+            },
+            DisplayProfile::DsLite => ColorAdjustment {
+                gamma: 1.15,
+                saturation: 1.2,
+                contrast: 1.1,
+                bits_per_channel: 6,
+            },
+            DisplayProfile::Dsi => ColorAdjustment {
+                gamma: 1.05,
+                saturation: 1.1,
+                contrast: 1.05,
+                bits_per_channel: 6,
+            },
+            DisplayProfile::Emulator => ColorAdjustment::default(),
+        }
+    }
+}
+
+impl FromStr for DisplayProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        DisplayProfile::ALL
+            .into_iter()
+            .find(|profile| profile.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown display profile '{s}' (expected ds-phat, ds-lite, dsi or emulator)"
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for DisplayProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Gamma, saturation and contrast tuning plus the panel's colour depth
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorAdjustment {
+    /// Exponent applied to normalised channel values (>1 darkens midtones)
+    pub gamma: f32,
+    /// Saturation multiplier around the pixel's luma
+    pub saturation: f32,
+    /// Contrast multiplier around mid-grey
+    pub contrast: f32,
+    /// Bits per channel the panel can actually show
+    pub bits_per_channel: u8,
+}
+
+impl Default for ColorAdjustment {
+    fn default() -> Self {
+        Self {
+            gamma: 1.0,
+            saturation: 1.0,
+            contrast: 1.0,
+            bits_per_channel: 8,
+        }
+    }
+}
+
+impl ColorAdjustment {
+    /// Whether applying this adjustment would leave pixels untouched
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Apply gamma, saturation and contrast, then snap to the panel's bit depth
+    pub fn apply(&self, image: &mut RgbaImage) {
+        if self.is_identity() {
+            return;
+        }
+
+        for pixel in image.pixels_mut() {
+            let [r, g, b, a] = pixel.0;
+            let mut rgb = [r, g, b].map(|c| c as f32 / 255.0);
+
+            for c in rgb.iter_mut() {
+                *c = (*c - 0.5) * self.contrast + 0.5;
+            }
+
+            let luma = 0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2];
+            for c in rgb.iter_mut() {
+                *c = luma + (*c - luma) * self.saturation;
+                *c = c.clamp(0.0, 1.0).powf(self.gamma);
+            }
+
+            let [r, g, b] = rgb.map(|c| self.quantize_channel((c * 255.0).round() as u8));
+            pixel.0 = [r, g, b, a];
+        }
+    }
+
+    /// Snap a channel value to the nearest level the panel can display
+    pub fn quantize_channel(&self, value: u8) -> u8 {
+        let bits = self.bits_per_channel.clamp(1, 8);
+        if bits == 8 {
+            return value;
+        }
+
+        let max = (1u32 << bits) - 1;
+        let level = (value as u32 * max + 127) / 255;
+        ((level * 255 + max / 2) / max) as u8
+    }
+
+    /// Snap every entry of an RGB palette to the panel's bit depth
+    pub fn quantize_palette(&self, palette: &mut [u8]) {
+        if self.bits_per_channel >= 8 {
+            return;
+        }
+
+        for value in palette.iter_mut() {
+            *value = self.quantize_channel(*value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_emulator_profile_is_identity() {
+        let mut image = RgbaImage::from_pixel(2, 2, Rgba([12, 34, 56, 255]));
+        DisplayProfile::Emulator.adjustment().apply(&mut image);
+        assert!(image.pixels().all(|p| p.0 == [12, 34, 56, 255]));
+    }
+
+    #[test]
+    fn test_quantize_channel_six_bits() {
+        let adjustment = DisplayProfile::DsLite.adjustment();
+        assert_eq!(adjustment.quantize_channel(0), 0);
+        assert_eq!(adjustment.quantize_channel(255), 255);
+
+        // Every output must be one of the 64 levels a 6-bit panel can show
+        let levels: std::collections::HashSet<u8> = (0..=255u8)
+            .map(|v| adjustment.quantize_channel(v))
+            .collect();
+        assert_eq!(levels.len(), 64);
+    }
+
+    #[test]
+    fn test_ds_profile_boosts_saturation() {
+        let mut image = RgbaImage::from_pixel(1, 1, Rgba([160, 100, 100, 255]));
+        DisplayProfile::DsPhat.adjustment().apply(&mut image);
+        let [r, g, b, a] = image.get_pixel(0, 0).0;
+        assert!(r as i32 - g as i32 > 60);
+        assert_eq!(g, b);
+        assert_eq!(a, 255);
+    }
+
+    #[test]
+    fn test_display_profile_from_str() {
+        assert_eq!("ds-lite".parse(), Ok(DisplayProfile::DsLite));
+        assert_eq!("DSi".parse(), Ok(DisplayProfile::Dsi));
+        assert!("gba".parse::<DisplayProfile>().is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -11,9 +12,20 @@ pub enum Error {
     #[error("Invalid game code")]
     InvalidGameCode,
 
+    #[error("Invalid platform '{0}'. Must be 'nds' or 'gba'")]
+    InvalidPlatform(String),
+
     #[error("Download error: {0}")]
     Download(String),
 
+    #[error("{kind}: {message}")]
+    Fetch {
+        kind: FetchErrorKind,
+        message: String,
+        /// HTTP status, when the server answered
+        status: Option<u16>,
+    },
+
     #[error("Processing error: {0}")]
     Processing(String),
 
@@ -24,6 +36,101 @@ pub enum Error {
     Other(String),
 }
 
+impl Error {
+    /// Create a classified fetch error
+    pub fn fetch(kind: FetchErrorKind, message: impl Into<String>) -> Self {
+        Error::Fetch {
+            kind,
+            message: message.into(),
+            status: None,
+        }
+    }
+
+    /// Create a fetch error for an unexpected HTTP status
+    pub fn http_status(kind: FetchErrorKind, url: &str, status: u16) -> Self {
+        Error::Fetch {
+            kind,
+            message: format!("{}: HTTP {}", url, status),
+            status: Some(status),
+        }
+    }
+
+    /// HTTP status the error came from, if any
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Fetch { status, .. } => *status,
+            _ => None,
+        }
+    }
+
+    /// Classify the error as a fetch outcome, if it came from fetching a cover
+    pub fn fetch_kind(&self) -> Option<FetchErrorKind> {
+        match self {
+            Error::Fetch { kind, .. } => Some(*kind),
+            Error::Download(_) => Some(FetchErrorKind::Transient),
+            Error::Image(_) => Some(FetchErrorKind::InvalidImage),
+            _ => None,
+        }
+    }
+
+    /// Whether retrying the same request later may succeed
+    pub fn is_transient(&self) -> bool {
+        self.fetch_kind() == Some(FetchErrorKind::Transient)
+    }
+}
+
+/// Why a download did not produce a cover
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FetchErrorKind {
+    /// The server has no such file (404/410)
+    NotFound,
+    /// Timeouts, connection failures, 5xx and rate limiting; worth retrying
+    Transient,
+    /// Any other failure that will not go away by retrying (bad URL, 403, ...)
+    Permanent,
+    /// The file downloaded but is not a decodable image
+    InvalidImage,
+}
+
+impl FetchErrorKind {
+    /// Get the identifier used in reports and JS errors
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FetchErrorKind::NotFound => "not-found",
+            FetchErrorKind::Transient => "transient",
+            FetchErrorKind::Permanent => "permanent",
+            FetchErrorKind::InvalidImage => "invalid-image",
+        }
+    }
+
+    /// Get a human readable description
+    pub fn label(&self) -> &'static str {
+        match self {
+            FetchErrorKind::NotFound => "Not found",
+            FetchErrorKind::Transient => "Network failure",
+            FetchErrorKind::Permanent => "Download failed",
+            FetchErrorKind::InvalidImage => "Invalid image",
+        }
+    }
+
+    /// Classify an HTTP status; `None` for 2xx and 304
+    pub fn from_status(status: u16) -> Option<Self> {
+        match status {
+            200..=299 | 304 => None,
+            404 | 410 => Some(FetchErrorKind::NotFound),
+            408 | 425 | 429 | 500..=599 => Some(FetchErrorKind::Transient),
+            _ => Some(FetchErrorKind::Permanent),
+        }
+    }
+}
+
+impl std::fmt::Display for FetchErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
 impl From<anyhow::Error> for Error {
     fn from(err: anyhow::Error) -> Self {
         Error::Other(err.to_string())
@@ -31,3 +138,45 @@ impl From<anyhow::Error> for Error {
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_status() {
+        assert_eq!(FetchErrorKind::from_status(200), None);
+        assert_eq!(
+            FetchErrorKind::from_status(404),
+            Some(FetchErrorKind::NotFound)
+        );
+        assert_eq!(
+            FetchErrorKind::from_status(503),
+            Some(FetchErrorKind::Transient)
+        );
+        assert_eq!(
+            FetchErrorKind::from_status(429),
+            Some(FetchErrorKind::Transient)
+        );
+        assert_eq!(
+            FetchErrorKind::from_status(403),
+            Some(FetchErrorKind::Permanent)
+        );
+    }
+
+    #[test]
+    fn test_http_status_error() {
+        let err = Error::http_status(
+            FetchErrorKind::Transient,
+            "https://proxy.test/nds/ABCE",
+            503,
+        );
+        assert_eq!(err.status(), Some(503));
+        assert!(err.is_transient());
+        assert_eq!(
+            err.to_string(),
+            "Network failure: https://proxy.test/nds/ABCE: HTTP 503"
+        );
+        assert_eq!(Error::Download("reset".into()).status(), None);
+    }
+}
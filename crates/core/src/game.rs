@@ -1,5 +1,69 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
 use crate::errors::{Error, Result};
 
+/// Console a ROM belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    /// Nintendo DS (`.nds`)
+    Nds,
+    /// Game Boy Advance (`.gba`)
+    Gba,
+}
+
+impl Platform {
+    /// All supported platforms
+    pub const ALL: [Platform; 2] = [Platform::Nds, Platform::Gba];
+
+    /// Get the lowercase identifier used in URLs and output paths ("nds" or "gba")
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Nds => "nds",
+            Platform::Gba => "gba",
+        }
+    }
+
+    /// Detect the platform from a ROM file extension (case-insensitive)
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        Platform::ALL
+            .into_iter()
+            .find(|platform| platform.as_str().eq_ignore_ascii_case(ext))
+    }
+
+    /// Detect the platform from a ROM path
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_extension)
+    }
+
+    /// Get the Pico Launcher cover directory, relative to the card root
+    pub fn cover_dir(&self) -> &'static str {
+        match self {
+            Platform::Nds => "_pico/covers/nds",
+            Platform::Gba => "_pico/covers/gba",
+        }
+    }
+}
+
+impl FromStr for Platform {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_extension(s).ok_or_else(|| Error::InvalidPlatform(s.to_string()))
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 /// Game code extracted from NDS or GBA file header
 /// NDS: 4 bytes at offset 0x0C. Example: "NTRJ" for Japanese, "NTRE" for European, "NTRA" for American
 /// GBA: 4 bytes at offset 0xAC. Example: "AXVE" for Game Boy Advance games
@@ -37,6 +101,25 @@ impl GameCode {
         Self::from_bytes(&header[0xAC..0xB0])
     }
 
+    /// Create from the start of a ROM of the given platform
+    pub fn from_rom_header(platform: Platform, header: &[u8]) -> Result<Self> {
+        match platform {
+            Platform::Nds => header
+                .get(..16)
+                .and_then(|h| h.try_into().ok())
+                .ok_or(Error::InvalidGameCode)
+                .and_then(Self::from_nds_header),
+            Platform::Gba => Self::from_gba_header(header),
+        }
+    }
+
+    /// Whether the code is a known homebrew or SDK placeholder rather than a real game
+    pub fn is_placeholder(&self) -> bool {
+        let mut chars = self.0.chars();
+        let first = chars.next();
+        PLACEHOLDER_CODES.contains(&self.0.as_str()) || chars.all(|c| Some(c) == first)
+    }
+
     /// Get the code as a string
     pub fn as_str(&self) -> &str {
         &self.0
@@ -48,6 +131,37 @@ impl GameCode {
     }
 }
 
+/// Codes shared by homebrew, prototypes and SDK builds
+const PLACEHOLDER_CODES: [&str; 3] = ["NTRJ", "HNXX", "PASS"];
+
+/// Raw code field of a ROM header when it can be used as a file name
+///
+/// Unlike [`GameCode`] this keeps placeholders such as `####`, which is what
+/// Pico Launcher looks covers up by.
+pub fn header_code_field(platform: Platform, header: &[u8]) -> Option<String> {
+    let range = match platform {
+        Platform::Nds => 0x0C..0x10,
+        Platform::Gba => 0xAC..0xB0,
+    };
+    let field = header.get(range)?;
+    let usable = field
+        .iter()
+        .all(|&b| b.is_ascii_graphic() && !b"\\/:*?\"<>|".contains(&b));
+    usable.then(|| String::from_utf8_lossy(field).into_owned())
+}
+
+/// Decode the first line of an NDS banner title (UTF-16LE, NUL padded)
+pub fn nds_banner_title(bytes: &[u8]) -> Option<String> {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    let title = String::from_utf16_lossy(&units);
+    let first_line = title.lines().next()?.trim();
+    (!first_line.is_empty()).then(|| first_line.to_string())
+}
+
 impl AsRef<str> for GameCode {
     fn as_ref(&self) -> &str {
         &self.0
@@ -95,6 +209,21 @@ mod tests {
         assert_eq!(code.as_str(), "NTRJ");
     }
 
+    #[test]
+    fn test_platform_from_path() {
+        assert_eq!(
+            Platform::from_path(Path::new("roms/Mario Kart DS.NDS")),
+            Some(Platform::Nds)
+        );
+        assert_eq!(
+            Platform::from_path(Path::new("a/b.gba")),
+            Some(Platform::Gba)
+        );
+        assert_eq!(Platform::from_path(Path::new("readme.txt")), None);
+        assert!("GBA".parse::<Platform>().is_ok());
+        assert!("snes".parse::<Platform>().is_err());
+    }
+
     #[test]
     fn test_game_code_from_gba_header() {
         let mut header = [0u8; 0xB0];
@@ -106,4 +235,32 @@ mod tests {
         let code = GameCode::from_gba_header(&header).unwrap();
         assert_eq!(code.as_str(), "AXVE");
     }
+
+    #[test]
+    fn test_placeholder_codes() {
+        assert!(GameCode::from_bytes(b"HNXX").unwrap().is_placeholder());
+        assert!(GameCode::from_bytes(b"0000").unwrap().is_placeholder());
+        assert!(!GameCode::from_bytes(b"AMCE").unwrap().is_placeholder());
+
+        let mut header = [0u8; 16];
+        header[0x0C..0x10].copy_from_slice(b"####");
+        assert!(GameCode::from_nds_header(&header).is_err());
+        assert_eq!(
+            header_code_field(Platform::Nds, &header).as_deref(),
+            Some("####")
+        );
+        header[0x0C..0x10].copy_from_slice(b"AB/C");
+        assert_eq!(header_code_field(Platform::Nds, &header), None);
+    }
+
+    #[test]
+    fn test_nds_banner_title() {
+        let mut bytes: Vec<u8> = "My Homebrew\nby someone"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        bytes.resize(0x100, 0);
+        assert_eq!(nds_banner_title(&bytes).as_deref(), Some("My Homebrew"));
+        assert_eq!(nds_banner_title(&[0; 8]), None);
+    }
 }
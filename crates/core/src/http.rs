@@ -0,0 +1,586 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::errors::{FetchErrorKind, Result};
+
+/// HTTP method of a request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    /// Headers only, used to probe whether a file exists
+    Head,
+}
+
+impl HttpMethod {
+    /// Get the method name as sent on the wire
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Head => "HEAD",
+        }
+    }
+}
+
+/// A request issued by a cover source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl HttpRequest {
+    /// Create a plain GET request
+    pub fn get(url: impl Into<String>) -> Self {
+        Self {
+            method: HttpMethod::Get,
+            url: url.into(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Create a HEAD request
+    pub fn head(url: impl Into<String>) -> Self {
+        Self {
+            method: HttpMethod::Head,
+            ..Self::get(url)
+        }
+    }
+
+    /// Create a GET request for the first byte only, for servers without HEAD support
+    pub fn first_byte(url: impl Into<String>) -> Self {
+        Self::get(url).header("Range", "bytes=0-0")
+    }
+
+    /// Add a request header
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// A fully buffered HTTP response
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Whether the status is 2xx
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Get a response header by name (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Platform specific HTTP transport used by cover sources
+///
+/// Native builds use reqwest (behind the `native` feature); the wasm bindings
+/// provide a browser `fetch` implementation.
+#[async_trait]
+pub trait HttpClient: Send + Sync {
+    /// Perform a request; non-2xx statuses are returned, not treated as errors
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse>;
+}
+
+/// How often and how long to wait before retrying transient failures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 disables retrying)
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled for each further retry
+    pub base_delay: Duration,
+    /// Upper bound for a single delay; a longer `Retry-After` is not retried
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries
+    pub const NONE: RetryPolicy = RetryPolicy {
+        max_retries: 0,
+        base_delay: Duration::ZERO,
+        max_delay: Duration::ZERO,
+    };
+
+    /// Set the number of retries
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Delay before retry number `retry` (0-based), with "equal jitter"
+    ///
+    /// The exponential delay is capped at `max_delay`, then a random value in
+    /// its upper half is used so parallel workers do not retry in lockstep.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(1u32.checked_shl(retry).unwrap_or(u32::MAX))
+            .min(self.max_delay);
+        let half = exponential / 2;
+        half + half.mul_f64(fastrand::f64())
+    }
+
+    /// Whether a response should be retried
+    pub fn should_retry(response: &HttpResponse) -> bool {
+        FetchErrorKind::from_status(response.status) == Some(FetchErrorKind::Transient)
+    }
+
+    /// Delay requested by a `Retry-After: <seconds>` header
+    pub fn retry_after(response: &HttpResponse) -> Option<Duration> {
+        let seconds: u64 = response.header("retry-after")?.trim().parse().ok()?;
+        Some(Duration::from_secs(seconds))
+    }
+}
+
+/// How many downloads may run at once, and how fast
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConcurrencyLimits {
+    /// Requests in flight across all hosts
+    pub max_concurrent: usize,
+    /// Requests in flight to a single host
+    pub per_host: usize,
+    /// Optional cap on requests started per second to a single host
+    pub requests_per_second: Option<f64>,
+}
+
+impl Default for ConcurrencyLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 16,
+            per_host: 4,
+            requests_per_second: None,
+        }
+    }
+}
+
+/// Get the host (and port) part of a URL, as written
+///
+/// Hosts are case-insensitive, so compare or key on the lowercased result.
+pub fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let authority = &rest[..end];
+    authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host)
+}
+
+#[cfg(feature = "native")]
+pub use self::limited_client::LimitedClient;
+#[cfg(feature = "native")]
+pub use self::reqwest_client::{ReqwestClient, RetryingClient};
+
+#[cfg(feature = "native")]
+mod limited_client {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use tokio::sync::Semaphore;
+    use tokio::time::Instant;
+
+    use super::{url_host, ConcurrencyLimits, HttpClient, HttpRequest, HttpResponse};
+    use crate::errors::Result;
+
+    /// Caps concurrent requests globally and per host, with an optional
+    /// per-host requests-per-second limit
+    pub struct LimitedClient {
+        inner: Arc<dyn HttpClient>,
+        limits: ConcurrencyLimits,
+        global: Semaphore,
+        hosts: Mutex<HashMap<String, HostState>>,
+    }
+
+    struct HostState {
+        permits: Arc<Semaphore>,
+        /// Earliest time the next request to this host may start
+        next_start: Instant,
+    }
+
+    impl LimitedClient {
+        /// Wrap `inner` with the given limits (zero limits are treated as 1)
+        pub fn new(inner: Arc<dyn HttpClient>, limits: ConcurrencyLimits) -> Self {
+            Self {
+                inner,
+                global: Semaphore::new(limits.max_concurrent.max(1)),
+                limits,
+                hosts: Mutex::new(HashMap::new()),
+            }
+        }
+
+        /// Get the configured limits
+        pub fn limits(&self) -> ConcurrencyLimits {
+            self.limits
+        }
+
+        /// Get the host's semaphore and reserve a start slot under the rate cap
+        fn reserve(&self, host: &str) -> (Arc<Semaphore>, Option<Instant>) {
+            let mut hosts = self.hosts.lock().unwrap();
+            let now = Instant::now();
+            let state = hosts
+                .entry(host.to_ascii_lowercase())
+                .or_insert_with(|| HostState {
+                    permits: Arc::new(Semaphore::new(self.limits.per_host.max(1))),
+                    next_start: now,
+                });
+
+            let start = match self.limits.requests_per_second {
+                Some(rps) if rps > 0.0 => {
+                    let start = state.next_start.max(now);
+                    state.next_start = start + Duration::from_secs_f64(1.0 / rps);
+                    Some(start)
+                }
+                _ => None,
+            };
+            (Arc::clone(&state.permits), start)
+        }
+    }
+
+    #[async_trait]
+    impl HttpClient for LimitedClient {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+            let (host_permits, start) = self.reserve(url_host(&request.url));
+            if let Some(start) = start {
+                tokio::time::sleep_until(start).await;
+            }
+
+            // The semaphores are never closed, so acquiring cannot fail
+            let _host = host_permits.acquire().await.ok();
+            let _global = self.global.acquire().await.ok();
+            self.inner.send(request).await
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use super::*;
+
+        /// Client that takes a while and records the peak number of requests in flight
+        #[derive(Default)]
+        struct SlowClient {
+            in_flight: AtomicUsize,
+            peak: AtomicUsize,
+        }
+
+        #[async_trait]
+        impl HttpClient for SlowClient {
+            async fn send(&self, _request: HttpRequest) -> Result<HttpResponse> {
+                let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(HttpResponse::default())
+            }
+        }
+
+        #[tokio::test]
+        async fn test_per_host_limit() {
+            let slow = Arc::new(SlowClient::default());
+            let client = Arc::new(LimitedClient::new(
+                slow.clone(),
+                ConcurrencyLimits {
+                    max_concurrent: 8,
+                    per_host: 2,
+                    requests_per_second: None,
+                },
+            ));
+
+            let tasks: Vec<_> = (0..6)
+                .map(|i| {
+                    let client = Arc::clone(&client);
+                    tokio::spawn(async move {
+                        client
+                            .send(HttpRequest::get(format!("https://art.test/{i}.png")))
+                            .await
+                    })
+                })
+                .collect();
+            for task in tasks {
+                task.await.unwrap().unwrap();
+            }
+            assert_eq!(slow.peak.load(Ordering::SeqCst), 2);
+        }
+
+        #[tokio::test]
+        async fn test_requests_per_second() {
+            let client = LimitedClient::new(
+                Arc::new(SlowClient::default()),
+                ConcurrencyLimits {
+                    max_concurrent: 8,
+                    per_host: 8,
+                    requests_per_second: Some(50.0),
+                },
+            );
+
+            let started = Instant::now();
+            for i in 0..4 {
+                client
+                    .send(HttpRequest::get(format!("https://art.test/{i}.png")))
+                    .await
+                    .unwrap();
+            }
+            // Three 20ms gaps between four starts
+            assert!(started.elapsed() >= Duration::from_millis(60));
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+mod reqwest_client {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+
+    use super::{HttpClient, HttpMethod, HttpRequest, HttpResponse, RetryPolicy};
+    use crate::errors::{Error, FetchErrorKind, Result};
+
+    /// Largest response body accepted; cover scans are a few MB at most
+    const MAX_BODY_BYTES: usize = 32 * 1024 * 1024;
+
+    /// HTTP client backed by reqwest; bodies are streamed in chunks
+    #[derive(Clone)]
+    pub struct ReqwestClient {
+        client: reqwest::Client,
+    }
+
+    impl ReqwestClient {
+        /// Build a client with the PicoCover user agent and a request timeout
+        pub fn new(timeout: Duration) -> Result<Self> {
+            let client = reqwest::Client::builder()
+                .user_agent(concat!("pico-cover/", env!("CARGO_PKG_VERSION")))
+                .timeout(timeout)
+                .build()
+                .map_err(|e| Error::Download(format!("Building HTTP client: {}", e)))?;
+            Ok(Self { client })
+        }
+    }
+
+    #[async_trait]
+    impl HttpClient for ReqwestClient {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+            let method = match request.method {
+                HttpMethod::Get => reqwest::Method::GET,
+                HttpMethod::Head => reqwest::Method::HEAD,
+            };
+            let mut builder = self.client.request(method, &request.url);
+            for (name, value) in &request.headers {
+                builder = builder.header(name, value);
+            }
+
+            let response = builder
+                .send()
+                .await
+                .map_err(|e| classify(&request.url, e))?;
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect();
+            let mut response = response;
+            let mut body = Vec::with_capacity(
+                response
+                    .content_length()
+                    .map_or(0, |len| len.min(MAX_BODY_BYTES as u64) as usize),
+            );
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                .map_err(|e| classify(&request.url, e))?
+            {
+                if body.len() + chunk.len() > MAX_BODY_BYTES {
+                    return Err(Error::fetch(
+                        FetchErrorKind::Permanent,
+                        format!(
+                            "{}: response larger than {} bytes",
+                            request.url, MAX_BODY_BYTES
+                        ),
+                    ));
+                }
+                body.extend_from_slice(&chunk);
+            }
+
+            Ok(HttpResponse {
+                status,
+                headers,
+                body,
+            })
+        }
+    }
+
+    /// Sort reqwest errors into transient (network) and permanent failures
+    fn classify(url: &str, err: reqwest::Error) -> Error {
+        let kind = if err.is_timeout() || err.is_connect() || err.is_request() || err.is_body() {
+            FetchErrorKind::Transient
+        } else {
+            FetchErrorKind::Permanent
+        };
+        Error::fetch(kind, format!("{}: {}", url, err))
+    }
+
+    /// Retries transient failures with exponential backoff and jitter
+    ///
+    /// Network errors, timeouts, 408, 429 and 5xx responses are retried; the
+    /// last outcome is returned once the policy's retries are used up, or
+    /// straight away if the server asks to wait longer than `max_delay`.
+    pub struct RetryingClient {
+        inner: Arc<dyn HttpClient>,
+        policy: RetryPolicy,
+    }
+
+    impl RetryingClient {
+        /// Wrap `inner` with a retry policy
+        pub fn new(inner: Arc<dyn HttpClient>, policy: RetryPolicy) -> Self {
+            Self { inner, policy }
+        }
+    }
+
+    #[async_trait]
+    impl HttpClient for RetryingClient {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+            let mut retry = 0;
+            loop {
+                let outcome = self.inner.send(request.clone()).await;
+                if retry >= self.policy.max_retries {
+                    return outcome;
+                }
+
+                let delay = match &outcome {
+                    Ok(response) if RetryPolicy::should_retry(response) => {
+                        match RetryPolicy::retry_after(response) {
+                            Some(wait) if wait > self.policy.max_delay => return outcome,
+                            Some(wait) => wait,
+                            None => self.policy.delay(retry),
+                        }
+                    }
+                    Err(err) if err.is_transient() => self.policy.delay(retry),
+                    _ => return outcome,
+                };
+                tokio::time::sleep(delay).await;
+                retry += 1;
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::source::testing::MockClient;
+
+        #[tokio::test]
+        async fn test_retries_transient_failures_only() {
+            let policy = RetryPolicy {
+                max_retries: 2,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(2),
+            };
+            let mock = Arc::new(
+                MockClient::default()
+                    .with_failure("http://x/down")
+                    .with_status("http://x/busy", 503),
+            );
+            let client = RetryingClient::new(mock.clone(), policy);
+
+            assert!(client
+                .send(HttpRequest::get("http://x/down"))
+                .await
+                .is_err());
+            let busy = client
+                .send(HttpRequest::get("http://x/busy"))
+                .await
+                .unwrap();
+            assert_eq!(busy.status, 503);
+            let missing = client
+                .send(HttpRequest::get("http://x/none"))
+                .await
+                .unwrap();
+            assert_eq!(missing.status, 404);
+
+            // 3 attempts each for the transient URLs, 1 for the 404
+            assert_eq!(mock.requested().len(), 7);
+        }
+
+        #[tokio::test]
+        async fn test_long_retry_after_is_not_shortened() {
+            let mut mock = MockClient::default();
+            for (url, wait) in [("http://x/soon", "0"), ("http://x/later", "60")] {
+                mock.responses.insert(
+                    url.to_string(),
+                    HttpResponse {
+                        status: 429,
+                        headers: vec![("Retry-After".to_string(), wait.to_string())],
+                        body: Vec::new(),
+                    },
+                );
+            }
+            let mock = Arc::new(mock);
+            let client = RetryingClient::new(mock.clone(), RetryPolicy::default());
+
+            let soon = client.send(HttpRequest::get("http://x/soon")).await;
+            assert_eq!(soon.unwrap().status, 429);
+            assert_eq!(mock.requested().len(), 4);
+
+            // Waiting 60s is beyond the policy, so the 429 is returned at once
+            let later = client.send(HttpRequest::get("http://x/later")).await;
+            assert_eq!(later.unwrap().status, 429);
+            assert_eq!(mock.requested().len(), 5);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_host() {
+        assert_eq!(
+            url_host("https://art.gametdb.com/ds/cover/US/A.png"),
+            "art.gametdb.com"
+        );
+        assert_eq!(
+            url_host("http://user@localhost:8787/nds/A"),
+            "localhost:8787"
+        );
+        assert_eq!(url_host("example.org?q=1"), "example.org");
+        assert_eq!(url_host("https://Art.GameTDB.com/x"), "Art.GameTDB.com");
+    }
+
+    #[test]
+    fn test_retry_delay_grows_and_is_capped() {
+        let policy = RetryPolicy::default();
+        for retry in 0..8 {
+            let delay = policy.delay(retry);
+            let cap = (policy.base_delay * 2u32.pow(retry)).min(policy.max_delay);
+            assert!(delay >= cap / 2 && delay <= cap, "retry {retry}: {delay:?}");
+        }
+    }
+}
@@ -1,6 +1,43 @@
+use crate::art::{ArtFit, ArtKind};
+use crate::display::DisplayProfile;
 use crate::errors::{Error, Result};
+use crate::metrics::QualityMetrics;
+use crate::resample::{self, Sharpening};
 use color_quant::NeuQuant;
-use image::{DynamicImage, GenericImageView};
+use image::imageops::overlay;
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Width of the resized cover art inside the final BMP
+pub const COVER_ART_WIDTH: u32 = 106;
+/// Height of the resized cover art inside the final BMP
+pub const COVER_ART_HEIGHT: u32 = 96;
+/// Width of the final BMP (cover art plus right padding)
+pub const COVER_WIDTH: u32 = 128;
+/// Height of the final BMP
+pub const COVER_HEIGHT: u32 = 96;
+
+/// Options controlling how cover art is rendered
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RenderOptions {
+    /// Colour tuning for the screen the cover is shown on
+    pub display: DisplayProfile,
+    /// Resample in linear light instead of directly on sRGB values
+    pub linear_light: bool,
+    /// Unsharp mask applied after resizing
+    pub sharpen: Sharpening,
+    /// Compute PSNR/SSIM between the resized art and the paletted output
+    pub compute_metrics: bool,
+}
+
+/// A rendered BMP together with optional quality measurements
+#[derive(Debug, Clone)]
+pub struct RenderedCover {
+    pub bmp: Vec<u8>,
+    pub metrics: Option<QualityMetrics>,
+}
 
 /// Image processor for cover art
 pub struct ImageProcessor;
@@ -8,11 +45,58 @@ pub struct ImageProcessor;
 impl ImageProcessor {
     /// Process cover image: resize to 106×96 and add 22px right padding for 128×96 final size
     pub fn process_cover(image_data: &[u8], _width: u32, _height: u32) -> Result<Vec<u8>> {
+        Self::process_cover_with_options(image_data, &RenderOptions::default())
+    }
+
+    /// Process cover image with explicit render options
+    pub fn process_cover_with_options(
+        image_data: &[u8],
+        options: &RenderOptions,
+    ) -> Result<Vec<u8>> {
+        Self::process_cover_report(image_data, options).map(|rendered| rendered.bmp)
+    }
+
+    /// Process cover image and return the BMP along with any requested quality metrics
+    pub fn process_cover_report(
+        image_data: &[u8],
+        options: &RenderOptions,
+    ) -> Result<RenderedCover> {
         let img = image::load_from_memory(image_data)
             .map_err(|e| Error::Image(format!("Failed to load image: {}", e)))?;
 
-        // Always resize to 106×96 and add 22px padding to make 128×96
-        Self::convert_to_8bpp_bmp(&img, 106, 96, 128, 96)
+        Self::render_art_with_report(&img, ArtKind::Cover, options)
+    }
+
+    /// Render an already decoded front cover to a 128×96 8bpp BMP
+    pub fn render_cover(img: &DynamicImage, options: &RenderOptions) -> Result<Vec<u8>> {
+        Self::render_art(img, ArtKind::Cover, options)
+    }
+
+    /// Render any kind of art to a 128×96 8bpp BMP, cropping or fitting it as needed
+    pub fn render_art(
+        img: &DynamicImage,
+        kind: ArtKind,
+        options: &RenderOptions,
+    ) -> Result<Vec<u8>> {
+        Self::render_art_with_report(img, kind, options).map(|rendered| rendered.bmp)
+    }
+
+    /// Render art to a BMP, also computing quality metrics if `options.compute_metrics` is set
+    pub fn render_art_with_report(
+        img: &DynamicImage,
+        kind: ArtKind,
+        options: &RenderOptions,
+    ) -> Result<RenderedCover> {
+        let art = kind.crop(img);
+        Self::convert_to_8bpp_bmp(
+            &art,
+            COVER_ART_WIDTH,
+            COVER_ART_HEIGHT,
+            COVER_WIDTH,
+            COVER_HEIGHT,
+            kind.fit(),
+            options,
+        )
     }
 
     /// Convert an image buffer to 8-bit indexed BMP format with optional padding
@@ -22,72 +106,89 @@ impl ImageProcessor {
         resize_height: u32,
         final_width: u32,
         final_height: u32,
-    ) -> Result<Vec<u8>> {
-        // Resize image to 106×96
-        let resized = img.resize_exact(
-            resize_width,
-            resize_height,
-            image::imageops::FilterType::Lanczos3,
+        fit: ArtFit,
+        options: &RenderOptions,
+    ) -> Result<RenderedCover> {
+        let (art_width, art_height) = match fit {
+            ArtFit::Stretch => (resize_width, resize_height),
+            ArtFit::Contain => {
+                let scale = (resize_width as f32 / img.width().max(1) as f32)
+                    .min(resize_height as f32 / img.height().max(1) as f32);
+                (
+                    ((img.width() as f32 * scale).round() as u32).clamp(1, resize_width),
+                    ((img.height() as f32 * scale).round() as u32).clamp(1, resize_height),
+                )
+            }
+        };
+
+        // Resize image to fit the 106×96 art area
+        let mut resized = resample::resize_exact(img, art_width, art_height, options.linear_light);
+        if let Some(mask) = options.sharpen.mask() {
+            mask.apply(&mut resized);
+        }
+
+        let adjustment = options.display.adjustment();
+        adjustment.apply(&mut resized);
+
+        // Place the art on a black canvas so the padding takes part in quantization
+        let mut canvas = RgbaImage::from_pixel(
+            final_width.max(resize_width),
+            final_height.max(resize_height),
+            Rgba([0, 0, 0, 255]),
         );
-        let rgba = resized.to_rgba8();
+        let x = (resize_width - art_width) / 2;
+        let y = (resize_height - art_height) / 2;
+        overlay(&mut canvas, &resized, x as i64, y as i64);
 
         // Quantize colors to 256 colors
         // NeuQuant expects raw RGBA bytes
-        let rgba_bytes = rgba.as_raw();
+        let rgba_bytes = canvas.as_raw();
         let quantizer = NeuQuant::new(10, 256, rgba_bytes);
-        let palette = quantizer.color_map_rgb();
+        let mut palette = quantizer.color_map_rgb();
+        adjustment.quantize_palette(&mut palette);
+        // Snapping can land several entries on the same colour
+        let remap = Self::dedup_palette(&mut palette);
 
         // Create indexed image
-        let mut indexed_data = Vec::new();
-        for chunk in rgba_bytes.chunks_exact(4) {
-            let idx = quantizer.index_of(&[chunk[0], chunk[1], chunk[2], chunk[3]]);
-            indexed_data.push(idx as u8);
-        }
+        let indexed_data: Vec<u8> = rgba_bytes
+            .chunks_exact(4)
+            .map(|chunk| remap[quantizer.index_of(chunk)])
+            .collect();
 
-        // Add padding if final size differs from resized size
-        let padded_data = if final_width > resize_width || final_height > resize_height {
-            Self::add_padding(
-                &indexed_data,
-                resize_width,
-                resize_height,
-                final_width,
-                final_height,
-            )
-        } else {
-            indexed_data
-        };
+        // Measure the art area only, so the black padding doesn't inflate the scores
+        let metrics = options.compute_metrics.then(|| {
+            let reference =
+                image::imageops::crop_imm(&canvas, 0, 0, resize_width, resize_height).to_image();
+            let art_indices: Vec<u8> = indexed_data
+                .chunks_exact(canvas.width() as usize)
+                .take(resize_height as usize)
+                .flat_map(|row| row[..resize_width as usize].iter().copied())
+                .collect();
+            QualityMetrics::compute(&reference, &palette, &art_indices)
+        });
 
         // Create BMP file with final dimensions
-        Self::create_8bpp_bmp(final_width, final_height, &padded_data, &palette)
+        let bmp = Self::create_8bpp_bmp(canvas.width(), canvas.height(), &indexed_data, &palette)?;
+        Ok(RenderedCover { bmp, metrics })
     }
 
-    /// Add black padding to image (right and/or bottom)
-    fn add_padding(
-        data: &[u8],
-        orig_width: u32,
-        orig_height: u32,
-        final_width: u32,
-        final_height: u32,
-    ) -> Vec<u8> {
-        let mut padded = Vec::new();
-        let orig_width = orig_width as usize;
-        let orig_height = orig_height as usize;
-        let final_width = final_width as usize;
-        let final_height = final_height as usize;
-
-        for y in 0..final_height {
-            for x in 0..final_width {
-                if x < orig_width && y < orig_height {
-                    // Original image data
-                    padded.push(data[y * orig_width + x]);
-                } else {
-                    // Black padding (index 0)
-                    padded.push(0);
-                }
-            }
-        }
-
-        padded
+    /// Drop repeated palette entries, keeping the first of each colour
+    ///
+    /// Returns the new index for every old one.
+    fn dedup_palette(palette: &mut Vec<u8>) -> Vec<u8> {
+        let mut seen: HashMap<[u8; 3], u8> = HashMap::new();
+        let mut unique = Vec::with_capacity(palette.len());
+        let remap = palette
+            .chunks_exact(3)
+            .map(|rgb| {
+                *seen.entry([rgb[0], rgb[1], rgb[2]]).or_insert_with(|| {
+                    unique.extend_from_slice(rgb);
+                    (unique.len() / 3 - 1) as u8
+                })
+            })
+            .collect();
+        *palette = unique;
+        remap
     }
 
     /// Create an 8-bit BMP file
@@ -155,4 +256,73 @@ mod tests {
         // Just verify the processor can be instantiated
         let _processor = ImageProcessor;
     }
+
+    #[test]
+    fn test_dedup_palette() {
+        let mut palette = vec![8, 8, 8, 0, 0, 0, 8, 8, 8, 4, 4, 4];
+        let remap = ImageProcessor::dedup_palette(&mut palette);
+        assert_eq!(palette, vec![8, 8, 8, 0, 0, 0, 4, 4, 4]);
+        assert_eq!(remap, vec![0, 1, 0, 2]);
+    }
+
+    #[test]
+    fn test_render_cover_dimensions() {
+        let img =
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(300, 270, Rgba([200, 40, 40, 255])));
+        let options = RenderOptions {
+            display: DisplayProfile::DsLite,
+            linear_light: true,
+            sharpen: Sharpening::CrispText,
+            compute_metrics: false,
+        };
+        let bmp = ImageProcessor::render_cover(&img, &options).unwrap();
+
+        assert_eq!(&bmp[0..2], b"BM");
+        assert_eq!(
+            bmp.len(),
+            14 + 40 + 1024 + (COVER_WIDTH * COVER_HEIGHT) as usize
+        );
+        let width = i32::from_le_bytes(bmp[18..22].try_into().unwrap());
+        let height = i32::from_le_bytes(bmp[22..26].try_into().unwrap());
+        assert_eq!((width, height), (COVER_WIDTH as i32, COVER_HEIGHT as i32));
+    }
+
+    #[test]
+    fn test_render_cart_is_letterboxed() {
+        // A wide white cartridge label should leave black bars above and below
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(200, 100, Rgba([255; 4])));
+        let bmp =
+            ImageProcessor::render_art(&img, ArtKind::Cart, &RenderOptions::default()).unwrap();
+
+        let palette = &bmp[54..54 + 1024];
+        let pixels = &bmp[54 + 1024..];
+        let color_at = |x: usize, y: usize| {
+            // Rows are stored bottom-up
+            let idx = pixels[(COVER_HEIGHT as usize - 1 - y) * COVER_WIDTH as usize + x] as usize;
+            [palette[idx * 4 + 2], palette[idx * 4 + 1], palette[idx * 4]]
+        };
+        assert!(color_at(53, 2).iter().all(|&c| c < 16));
+        assert!(color_at(53, 48).iter().all(|&c| c > 240));
+    }
+
+    #[test]
+    fn test_render_report_metrics() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |x, y| {
+            Rgba([(x * 4) as u8, (y * 4) as u8, 90, 255])
+        }));
+        let options = RenderOptions {
+            compute_metrics: true,
+            ..RenderOptions::default()
+        };
+        let rendered =
+            ImageProcessor::render_art_with_report(&img, ArtKind::Cover, &options).unwrap();
+        let metrics = rendered.metrics.unwrap();
+        assert!(metrics.psnr > 25.0);
+        assert!(metrics.palette_entries > 1 && metrics.palette_entries <= 256);
+
+        let plain =
+            ImageProcessor::render_art_with_report(&img, ArtKind::Cover, &RenderOptions::default())
+                .unwrap();
+        assert!(plain.metrics.is_none());
+    }
 }
@@ -1,14 +1,37 @@
 // Core types and error handling
+pub mod art;
+#[cfg(feature = "native")]
+pub mod cache;
+pub mod display;
 pub mod errors;
 pub mod game;
+pub mod http;
 pub mod image_processing;
+pub mod metrics;
+#[cfg(feature = "native")]
+pub mod pack;
+#[cfg(feature = "native")]
+pub mod pipeline;
+#[cfg(feature = "native")]
+pub mod provenance;
+pub mod resample;
+pub mod selection;
+pub mod source;
+pub mod titles;
 
 pub use errors::{Error, Result};
-pub use game::GameCode;
+pub use game::{GameCode, Platform};
 
 /// Re-export commonly used types
 pub mod prelude {
+    pub use crate::art::ArtKind;
+    pub use crate::display::DisplayProfile;
     pub use crate::errors::{Error, Result};
-    pub use crate::game::GameCode;
-    pub use crate::image_processing::ImageProcessor;
+    pub use crate::game::{GameCode, Platform};
+    pub use crate::http::{HttpClient, HttpRequest, HttpResponse};
+    pub use crate::image_processing::{ImageProcessor, RenderOptions, RenderedCover};
+    pub use crate::metrics::QualityMetrics;
+    pub use crate::resample::Sharpening;
+    pub use crate::selection::SelectionStrategy;
+    pub use crate::source::{ChainedSource, CoverRequest, CoverSource, FetchedCover};
 }
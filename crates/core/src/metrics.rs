@@ -0,0 +1,174 @@
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+/// How closely a rendered BMP matches the art it was quantized from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QualityMetrics {
+    /// Peak signal-to-noise ratio over RGB in dB (higher is better)
+    pub psnr: f64,
+    /// Mean structural similarity of the luma channel (1.0 = identical)
+    pub ssim: f64,
+    /// Number of distinct palette entries actually used by the pixels
+    pub palette_entries: usize,
+}
+
+impl QualityMetrics {
+    /// Covers below this PSNR usually show visible banding on hardware
+    pub const PSNR_REVIEW_THRESHOLD: f64 = 30.0;
+    /// Covers below this SSIM usually lost detail such as small text
+    pub const SSIM_REVIEW_THRESHOLD: f64 = 0.9;
+
+    /// Compare the pre-quantization art with its paletted rendering
+    ///
+    /// `palette` holds RGB triplets and `indices` one palette index per pixel of
+    /// `reference`, in the same row order.
+    pub fn compute(reference: &RgbaImage, palette: &[u8], indices: &[u8]) -> Self {
+        let rendered = RgbaImage::from_fn(reference.width(), reference.height(), |x, y| {
+            let idx = indices[(y * reference.width() + x) as usize] as usize * 3;
+            let rgb = palette.get(idx..idx + 3).unwrap_or(&[0, 0, 0]);
+            image::Rgba([rgb[0], rgb[1], rgb[2], 255])
+        });
+
+        let mut used = [false; 256];
+        for &idx in indices {
+            used[idx as usize] = true;
+        }
+
+        Self {
+            psnr: psnr(reference, &rendered),
+            ssim: ssim(reference, &rendered),
+            palette_entries: used.iter().filter(|&&u| u).count(),
+        }
+    }
+
+    /// Whether the cover is worth re-rendering with different settings
+    pub fn needs_review(&self) -> bool {
+        self.psnr < Self::PSNR_REVIEW_THRESHOLD || self.ssim < Self::SSIM_REVIEW_THRESHOLD
+    }
+}
+
+impl std::fmt::Display for QualityMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PSNR {:.1} dB, SSIM {:.3}, {} colours",
+            self.psnr, self.ssim, self.palette_entries
+        )
+    }
+}
+
+/// Peak signal-to-noise ratio over the RGB channels, in dB
+///
+/// Identical images return `f64::INFINITY`.
+pub fn psnr(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    let mut squared_error = 0.0;
+    let mut samples = 0.0;
+    for (pa, pb) in a.pixels().zip(b.pixels()) {
+        for c in 0..3 {
+            let diff = pa[c] as f64 - pb[c] as f64;
+            squared_error += diff * diff;
+            samples += 1.0;
+        }
+    }
+
+    if samples == 0.0 || squared_error == 0.0 {
+        return f64::INFINITY;
+    }
+    let mse = squared_error / samples;
+    10.0 * (255.0 * 255.0 / mse).log10()
+}
+
+/// Mean SSIM of the luma channel over 8×8 windows with a stride of 4
+pub fn ssim(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    const WINDOW: u32 = 8;
+    const STRIDE: u32 = 4;
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let width = a.width().min(b.width());
+    let height = a.height().min(b.height());
+    if width < WINDOW || height < WINDOW {
+        return if psnr(a, b).is_infinite() { 1.0 } else { 0.0 };
+    }
+
+    let luma = |img: &RgbaImage, x: u32, y: u32| {
+        let p = img.get_pixel(x, y);
+        0.299 * p[0] as f64 + 0.587 * p[1] as f64 + 0.114 * p[2] as f64
+    };
+
+    let mut total = 0.0;
+    let mut windows = 0.0;
+    let n = (WINDOW * WINDOW) as f64;
+    for wy in (0..=height - WINDOW).step_by(STRIDE as usize) {
+        for wx in (0..=width - WINDOW).step_by(STRIDE as usize) {
+            let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) =
+                (0.0, 0.0, 0.0, 0.0, 0.0);
+            for y in wy..wy + WINDOW {
+                for x in wx..wx + WINDOW {
+                    let la = luma(a, x, y);
+                    let lb = luma(b, x, y);
+                    sum_a += la;
+                    sum_b += lb;
+                    sum_aa += la * la;
+                    sum_bb += lb * lb;
+                    sum_ab += la * lb;
+                }
+            }
+
+            let mean_a = sum_a / n;
+            let mean_b = sum_b / n;
+            let var_a = sum_aa / n - mean_a * mean_a;
+            let var_b = sum_bb / n - mean_b * mean_b;
+            let covariance = sum_ab / n - mean_a * mean_b;
+
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+            windows += 1.0;
+        }
+    }
+
+    total / windows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn gradient() -> RgbaImage {
+        RgbaImage::from_fn(32, 32, |x, y| {
+            Rgba([(x * 8) as u8, (y * 8) as u8, 128, 255])
+        })
+    }
+
+    #[test]
+    fn test_identical_images() {
+        let img = gradient();
+        assert!(psnr(&img, &img).is_infinite());
+        assert!((ssim(&img, &img) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_noisy_image_scores_lower() {
+        let img = gradient();
+        let mut noisy = img.clone();
+        for (i, p) in noisy.pixels_mut().enumerate() {
+            if i % 3 == 0 {
+                p[0] = p[0].wrapping_add(60);
+            }
+        }
+        assert!(psnr(&img, &noisy) < 30.0);
+        assert!(ssim(&img, &noisy) < 0.99);
+    }
+
+    #[test]
+    fn test_compute_counts_palette_entries() {
+        let reference = RgbaImage::from_pixel(2, 2, Rgba([10, 20, 30, 255]));
+        let palette = [10, 20, 30, 200, 200, 200];
+        let metrics = QualityMetrics::compute(&reference, &palette, &[0, 0, 0, 1]);
+        assert_eq!(metrics.palette_entries, 2);
+        assert!(metrics.psnr.is_finite());
+        assert!(metrics.needs_review());
+    }
+}
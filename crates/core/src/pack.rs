@@ -0,0 +1,762 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use zip::read::ZipFile;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, DateTime, ZipArchive, ZipWriter};
+
+use crate::art::ArtKind;
+use crate::cache::{hex_digest, unix_now, DiskCache};
+use crate::errors::{Error, Result};
+use crate::game::Platform;
+use crate::image_processing::{ImageProcessor, RenderOptions};
+use crate::provenance::{Provenance, TOOL_VERSION};
+
+/// Manifest file name at the root of a pack
+pub const PACK_MANIFEST: &str = "pico-cover-pack.json";
+
+/// Current manifest format version
+const PACK_VERSION: u32 = 1;
+
+/// Largest cover accepted from a pack; a 128x96 BMP is about 37 KiB
+const MAX_COVER_BYTES: u64 = 1024 * 1024;
+/// Largest manifest accepted from a pack
+const MAX_MANIFEST_BYTES: u64 = 16 * 1024 * 1024;
+
+/// What to do when an imported cover already exists on the card
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    /// Leave the card's cover alone
+    #[default]
+    Keep,
+    /// Always take the pack's cover
+    Replace,
+    /// Take whichever cover was modified last
+    ///
+    /// Packs without a manifest only carry zip timestamps, which have no time
+    /// zone and are read as UTC, so covers changed within the writer's UTC
+    /// offset of each other may be ordered wrongly.
+    Newest,
+}
+
+impl ConflictPolicy {
+    /// All policies, in the order they are presented to users
+    pub const ALL: [ConflictPolicy; 3] = [
+        ConflictPolicy::Keep,
+        ConflictPolicy::Replace,
+        ConflictPolicy::Newest,
+    ];
+
+    /// Get the identifier used on the command line
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictPolicy::Keep => "keep",
+            ConflictPolicy::Replace => "replace",
+            ConflictPolicy::Newest => "newest",
+        }
+    }
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        ConflictPolicy::ALL
+            .into_iter()
+            .find(|policy| policy.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!("Unknown conflict policy '{s}' (expected keep, replace or newest)")
+            })
+    }
+}
+
+impl std::fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// One cover listed in a pack manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackEntry {
+    pub platform: Platform,
+    /// Cover name, normally the ROM's header code
+    pub code: String,
+    /// Where the original art came from (source name or URL), when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// SHA-256 of the BMP, hex encoded
+    pub sha256: String,
+    /// Unix time the cover was last modified
+    pub modified: u64,
+}
+
+impl PackEntry {
+    /// Path of the cover inside the zip and below the card root
+    pub fn path(&self) -> String {
+        format!("{}/{}.bmp", self.platform.cover_dir(), self.code)
+    }
+}
+
+/// Contents of [`PACK_MANIFEST`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackManifest {
+    pub version: u32,
+    /// Program that wrote the pack
+    pub tool: String,
+    /// Unix time the pack was written
+    pub created: u64,
+    pub covers: Vec<PackEntry>,
+}
+
+/// Totals of a pack import
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportStats {
+    pub added: usize,
+    pub replaced: usize,
+    pub kept: usize,
+    /// Kept covers that are locked or were edited by hand (also counted in `kept`)
+    pub locked: usize,
+}
+
+/// A set of rendered covers with their manifest entries
+///
+/// Packs use the web app's ZIP layout (`_pico/covers/nds/`, `_pico/covers/gba/`)
+/// plus a [`PACK_MANIFEST`] at the root; packs without one import too.
+#[derive(Debug, Clone, Default)]
+pub struct CoverPack {
+    covers: BTreeMap<(Platform, String), (PackEntry, Vec<u8>)>,
+}
+
+impl CoverPack {
+    /// Create an empty pack
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a cover, replacing any with the same platform and name
+    pub fn insert(&mut self, mut entry: PackEntry, bmp: Vec<u8>) {
+        entry.sha256 = hex_digest(&bmp);
+        self.covers
+            .insert((entry.platform, entry.code.clone()), (entry, bmp));
+    }
+
+    /// Add every cover on a card (`<root>/_pico/covers/{nds,gba}/*.bmp`)
+    ///
+    /// Covers still matching their [`Provenance`] record take its source,
+    /// region and creation time.
+    pub fn add_card(&mut self, root: &Path) -> Result<usize> {
+        let provenance = Provenance::load(root)?;
+        let mut added = 0;
+        for platform in Platform::ALL {
+            let Ok(entries) = fs::read_dir(root.join(platform.cover_dir())) else {
+                continue;
+            };
+            for path in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+                let Some(code) = cover_name(&path.to_string_lossy()) else {
+                    continue;
+                };
+                let bmp = fs::read(&path)?;
+                if !is_bmp(&bmp) {
+                    continue;
+                }
+                let mut entry = PackEntry {
+                    platform,
+                    code,
+                    source: None,
+                    region: None,
+                    sha256: String::new(),
+                    modified: modified_time(&path),
+                };
+                let record = provenance
+                    .get(platform, &entry.code)
+                    .filter(|record| record.matches(&bmp));
+                if let Some(record) = record {
+                    // Local paths mean nothing on another machine
+                    let remote = record.origin.starts_with("http://")
+                        || record.origin.starts_with("https://");
+                    entry.source = Some(if remote {
+                        record.origin.clone()
+                    } else {
+                        record.source.clone()
+                    });
+                    entry.region = record.region.clone();
+                    entry.modified = record.created;
+                }
+                self.insert(entry, bmp);
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    /// Render every cover original held in a download or proxy cache
+    ///
+    /// Entries are recognised by GameTDB and proxy URLs and by the proxy
+    /// server's `platform:CODE` keys. When a game has several originals, front
+    /// covers win over other art, then the most recently fetched.
+    pub fn add_cache(&mut self, cache: &DiskCache, options: &RenderOptions) -> Result<usize> {
+        let mut originals: BTreeMap<(Platform, String), CachedOriginal> = BTreeMap::new();
+        for meta in cache.list()? {
+            let Some(original) = CachedOriginal::parse(&meta.url, meta.fetched_at) else {
+                continue;
+            };
+            let key = (original.platform, original.code.clone());
+            let better = originals
+                .get(&key)
+                .is_none_or(|current| original.rank() > current.rank());
+            if better {
+                originals.insert(key, original);
+            }
+        }
+
+        let mut added = 0;
+        for ((platform, code), original) in originals {
+            let Some(entry) = cache.get(&original.url) else {
+                continue;
+            };
+            let Ok(image) = image::load_from_memory(&entry.body) else {
+                continue;
+            };
+            let rendered = ImageProcessor::render_art_with_report(&image, original.kind, options)?;
+            let entry = PackEntry {
+                platform,
+                code,
+                source: Some(original.url),
+                region: original.region,
+                sha256: String::new(),
+                modified: entry.meta.fetched_at,
+            };
+            self.insert(entry, rendered.bmp);
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    /// Manifest entries, sorted by platform and name
+    pub fn entries(&self) -> impl Iterator<Item = &PackEntry> {
+        self.covers.values().map(|(entry, _)| entry)
+    }
+
+    /// Number of covers
+    pub fn len(&self) -> usize {
+        self.covers.len()
+    }
+
+    /// Whether the pack has no covers
+    pub fn is_empty(&self) -> bool {
+        self.covers.is_empty()
+    }
+
+    /// Write the pack as a zip
+    pub fn write(&self, path: &Path) -> Result<PackManifest> {
+        let manifest = PackManifest {
+            version: PACK_VERSION,
+            tool: TOOL_VERSION.to_string(),
+            created: unix_now(),
+            covers: self.entries().cloned().collect(),
+        };
+        let json = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| Error::Processing(format!("pack manifest: {}", e)))?;
+
+        let mut zip = ZipWriter::new(fs::File::create(path)?);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        zip.start_file(PACK_MANIFEST, options).map_err(zip_error)?;
+        zip.write_all(&json)?;
+        for (entry, bmp) in self.covers.values() {
+            let options = options.last_modified_time(zip_time(entry.modified));
+            zip.start_file(entry.path(), options).map_err(zip_error)?;
+            zip.write_all(bmp)?;
+        }
+        zip.finish().map_err(zip_error)?;
+        Ok(manifest)
+    }
+
+    /// Read a pack written by [`CoverPack::write`] or the web app's ZIP export
+    ///
+    /// Covers whose bytes don't match the manifest hash are listed as invalid.
+    pub fn read(path: &Path) -> Result<(Self, Vec<String>)> {
+        let mut archive = ZipArchive::new(fs::File::open(path)?).map_err(zip_error)?;
+        let mut manifest: BTreeMap<(Platform, String), PackEntry> = BTreeMap::new();
+        if let Ok(mut file) = archive.by_name(PACK_MANIFEST) {
+            let json = read_limited(&mut file, MAX_MANIFEST_BYTES)?.ok_or_else(|| {
+                Error::Processing(format!("{}: manifest is too large", PACK_MANIFEST))
+            })?;
+            let parsed: PackManifest = serde_json::from_slice(&json)
+                .map_err(|e| Error::Processing(format!("{}: {}", PACK_MANIFEST, e)))?;
+            for entry in parsed.covers {
+                manifest.insert((entry.platform, entry.code.clone()), entry);
+            }
+        }
+
+        let mut pack = Self::new();
+        let mut invalid = Vec::new();
+        for index in 0..archive.len() {
+            let mut file = archive.by_index(index).map_err(zip_error)?;
+            let name = file.name().map_err(zip_error)?.to_string();
+            if file.is_dir() || name == PACK_MANIFEST {
+                continue;
+            }
+            let (Some(platform), Some(code)) = (pack_platform(&name), cover_name(&name)) else {
+                invalid.push(name);
+                continue;
+            };
+            let Some(bmp) = read_limited(&mut file, MAX_COVER_BYTES)?.filter(|bmp| is_bmp(bmp))
+            else {
+                invalid.push(name);
+                continue;
+            };
+
+            let zip_modified = file.last_modified().and_then(unix_time).unwrap_or(0);
+            let entry = match manifest.remove(&(platform, code.clone())) {
+                Some(entry) if entry.sha256 != hex_digest(&bmp) => {
+                    invalid.push(name);
+                    continue;
+                }
+                Some(entry) => entry,
+                None => PackEntry {
+                    platform,
+                    code,
+                    source: None,
+                    region: None,
+                    sha256: String::new(),
+                    modified: zip_modified,
+                },
+            };
+            pack.insert(entry, bmp);
+        }
+        Ok((pack, invalid))
+    }
+
+    /// Copy the covers onto a card, resolving conflicts with `policy`
+    ///
+    /// Covers the card's [`Provenance`] protects are kept whatever the policy.
+    /// Written covers lose their provenance record, which described the art
+    /// they replaced.
+    pub fn import(&self, root: &Path, policy: ConflictPolicy) -> Result<ImportStats> {
+        let mut provenance = Provenance::load(root)?;
+        let mut stats = ImportStats::default();
+        for (entry, bmp) in self.covers.values() {
+            let target = root.join(entry.path());
+            let exists = target.is_file();
+            if exists {
+                let take = match policy {
+                    ConflictPolicy::Keep => false,
+                    ConflictPolicy::Replace => true,
+                    ConflictPolicy::Newest => entry.modified > modified_time(&target),
+                };
+                let current = fs::read(&target)?;
+                if !take || current == *bmp {
+                    stats.kept += 1;
+                    continue;
+                }
+                if provenance
+                    .cover_lock(entry.platform, &entry.code, &current)
+                    .is_some()
+                {
+                    stats.kept += 1;
+                    stats.locked += 1;
+                    continue;
+                }
+            }
+
+            if let Some(dir) = target.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&target, bmp)?;
+            if entry.modified > 0 {
+                let file = fs::File::options().write(true).open(&target)?;
+                file.set_modified(UNIX_EPOCH + Duration::from_secs(entry.modified))?;
+            }
+            provenance.remove(entry.platform, &entry.code);
+            if exists {
+                stats.replaced += 1;
+            } else {
+                stats.added += 1;
+            }
+        }
+        provenance.save(root)?;
+        Ok(stats)
+    }
+}
+
+/// A cover original recognised in a cache
+struct CachedOriginal {
+    url: String,
+    platform: Platform,
+    code: String,
+    region: Option<String>,
+    kind: ArtKind,
+    fetched_at: u64,
+}
+
+impl CachedOriginal {
+    /// Recognise `platform:CODE`, `.../{platform}/{CODE}` and GameTDB `.../ds/{kind}/{region}/{CODE}.ext`
+    fn parse(url: &str, fetched_at: u64) -> Option<Self> {
+        let path = url.split(['?', '#']).next()?;
+        let (platform, code, region, kind) = match path.split_once(':') {
+            Some((platform, code)) if !code.starts_with("//") => (
+                platform.parse().ok()?,
+                code.to_string(),
+                None,
+                ArtKind::Cover,
+            ),
+            _ => {
+                let segments: Vec<&str> = path.rsplit('/').take(4).collect();
+                match segments.as_slice() {
+                    [file, region, kind, "ds", ..] => {
+                        let stem = file.rsplit_once('.')?.0;
+                        let kind = ArtKind::ALL
+                            .into_iter()
+                            .find(|k| k.gametdb_path() == *kind)?;
+                        (
+                            Platform::Nds,
+                            stem.to_string(),
+                            Some(region.to_string()),
+                            kind,
+                        )
+                    }
+                    [code, platform, ..] => (
+                        platform.parse().ok()?,
+                        code.to_string(),
+                        None,
+                        ArtKind::Cover,
+                    ),
+                    _ => return None,
+                }
+            }
+        };
+        let valid = code.len() == 4 && code.bytes().all(|b| b.is_ascii_alphanumeric());
+        valid.then(|| Self {
+            url: url.to_string(),
+            platform,
+            code: code.to_ascii_uppercase(),
+            region,
+            kind,
+            fetched_at,
+        })
+    }
+
+    fn rank(&self) -> (bool, u64) {
+        (self.kind == ArtKind::Cover, self.fetched_at)
+    }
+}
+
+/// Platform of a zip entry under `_pico/covers/<platform>/` or `<platform>/`
+fn pack_platform(name: &str) -> Option<Platform> {
+    let name = name.trim_start_matches('/');
+    let dir = name.strip_prefix("_pico/covers/").unwrap_or(name);
+    let (platform, file) = dir.split_once('/')?;
+    if file.contains('/') {
+        return None;
+    }
+    platform.parse().ok()
+}
+
+/// Cover name from a path ending in `<name>.bmp`
+fn cover_name(path: &str) -> Option<String> {
+    let file = path.rsplit(['/', '\\']).next()?;
+    let (stem, ext) = file.rsplit_once('.')?;
+    let valid = ext.eq_ignore_ascii_case("bmp")
+        && !stem.is_empty()
+        && !stem.starts_with('.')
+        && stem.len() <= 16;
+    valid.then(|| stem.to_string())
+}
+
+/// Read a zip entry, or `None` if it is larger than `limit`
+///
+/// The declared size is checked first, and the read itself is bounded in
+/// case the header lies.
+fn read_limited(file: &mut ZipFile<'_, fs::File>, limit: u64) -> Result<Option<Vec<u8>>> {
+    if file.size() > limit {
+        return Ok(None);
+    }
+    let mut bytes = Vec::new();
+    file.take(limit + 1).read_to_end(&mut bytes)?;
+    Ok((bytes.len() as u64 <= limit).then_some(bytes))
+}
+
+fn is_bmp(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"BM")
+}
+
+fn modified_time(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+fn zip_error(err: zip::result::ZipError) -> Error {
+    Error::Processing(format!("zip: {}", err))
+}
+
+/// Zip timestamp (two second resolution) for a unix time
+///
+/// Zip times have no time zone and are usually local time; ours are UTC, and
+/// readers get the exact time from the manifest instead.
+fn zip_time(unix: u64) -> DateTime {
+    let days = (unix / 86_400) as i64;
+    let secs = unix % 86_400;
+    // Civil-from-days (Howard Hinnant)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = (yoe + era * 400 + i64::from(month <= 2)) as u16;
+    DateTime::from_date_and_time(
+        year,
+        month,
+        day,
+        (secs / 3600) as u8,
+        (secs / 60 % 60) as u8,
+        (secs % 60) as u8,
+    )
+    .unwrap_or_default()
+}
+
+/// Unix time for a zip timestamp, taking it to be UTC
+///
+/// Other tools write local time, which makes the result off by their UTC offset.
+fn unix_time(time: DateTime) -> Option<u64> {
+    let (year, month, day) = (
+        i64::from(time.year()),
+        i64::from(time.month()),
+        i64::from(time.day()),
+    );
+    // Days-from-civil (Howard Hinnant)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    let secs = days * 86_400
+        + i64::from(time.hour()) * 3600
+        + i64::from(time.minute()) * 60
+        + i64::from(time.second());
+    u64::try_from(secs).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpResponse;
+    use crate::provenance::CoverRecord;
+    use crate::source::testing::png;
+
+    fn bmp(fill: u8) -> Vec<u8> {
+        let mut bytes = b"BM".to_vec();
+        bytes.extend([fill; 30]);
+        bytes
+    }
+
+    fn write_cover(root: &Path, platform: Platform, code: &str, bytes: &[u8]) {
+        let dir = root.join(platform.cover_dir());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(format!("{}.bmp", code)), bytes).unwrap();
+    }
+
+    fn record(code: &str, bmp: &[u8]) -> CoverRecord {
+        CoverRecord {
+            platform: Platform::Nds,
+            cover_name: code.to_string(),
+            game_code: None,
+            game_name: code.to_string(),
+            source: "gametdb".to_string(),
+            origin: format!("https://art.gametdb.com/ds/cover/US/{}.png", code),
+            region: Some("US".to_string()),
+            art_kind: ArtKind::Cover,
+            source_sha256: hex_digest(b"png"),
+            etag: None,
+            last_modified: None,
+            cover_sha256: hex_digest(bmp),
+            render: RenderOptions::default(),
+            tool: TOOL_VERSION.to_string(),
+            created: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_zip_time_round_trip() {
+        let unix = 1_700_000_000;
+        assert_eq!(unix_time(zip_time(unix)), Some(unix));
+    }
+
+    #[test]
+    fn test_export_and_import_card() {
+        let card = tempfile::tempdir().unwrap();
+        write_cover(card.path(), Platform::Nds, "AMCE", &bmp(1));
+        write_cover(card.path(), Platform::Gba, "BPEE", &bmp(2));
+        fs::write(card.path().join("_pico/covers/nds/notes.txt"), "hi").unwrap();
+
+        let mut provenance = Provenance::new();
+        provenance.record(record("AMCE", &bmp(1)));
+        provenance.save(card.path()).unwrap();
+
+        let mut pack = CoverPack::new();
+        assert_eq!(pack.add_card(card.path()).unwrap(), 2);
+        let zip_path = card.path().join("pack.zip");
+        let manifest = pack.write(&zip_path).unwrap();
+        assert_eq!(manifest.covers.len(), 2);
+        let amce = manifest.covers.iter().find(|c| c.code == "AMCE").unwrap();
+        assert_eq!(
+            amce.source.as_deref(),
+            Some("https://art.gametdb.com/ds/cover/US/AMCE.png")
+        );
+        assert_eq!(amce.region.as_deref(), Some("US"));
+        assert_eq!(amce.modified, 1_700_000_000);
+        let bpee = manifest.covers.iter().find(|c| c.code == "BPEE").unwrap();
+        assert_eq!(bpee.source, None);
+
+        let other = tempfile::tempdir().unwrap();
+        write_cover(other.path(), Platform::Nds, "AMCE", &bmp(9));
+        let (read, invalid) = CoverPack::read(&zip_path).unwrap();
+        assert!(invalid.is_empty());
+
+        let stats = read.import(other.path(), ConflictPolicy::Keep).unwrap();
+        assert_eq!((stats.added, stats.kept, stats.replaced), (1, 1, 0));
+        assert_eq!(
+            fs::read(other.path().join("_pico/covers/nds/AMCE.bmp")).unwrap(),
+            bmp(9)
+        );
+
+        let stats = read.import(other.path(), ConflictPolicy::Replace).unwrap();
+        assert_eq!(stats.replaced, 1);
+        assert_eq!(
+            fs::read(other.path().join("_pico/covers/nds/AMCE.bmp")).unwrap(),
+            bmp(1)
+        );
+    }
+
+    #[test]
+    fn test_import_web_export_with_newest_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("covers.zip");
+        let mut zip = ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        let old = SimpleFileOptions::default().last_modified_time(zip_time(1_600_000_000));
+        zip.start_file("_pico/covers/nds/AMCE.bmp", old).unwrap();
+        zip.write_all(&bmp(3)).unwrap();
+        zip.start_file("_pico/covers/gba/../../evil.bmp", old)
+            .unwrap();
+        zip.write_all(&bmp(4)).unwrap();
+        zip.start_file("_pico/covers/gba/BPRE.bmp", old).unwrap();
+        zip.write_all(b"BM").unwrap();
+        zip.write_all(&vec![0; MAX_COVER_BYTES as usize]).unwrap();
+        zip.finish().unwrap();
+
+        let card = tempfile::tempdir().unwrap();
+        write_cover(card.path(), Platform::Nds, "AMCE", &bmp(5));
+        let (pack, invalid) = CoverPack::read(&zip_path).unwrap();
+        assert_eq!(
+            invalid,
+            vec![
+                "_pico/covers/gba/../../evil.bmp".to_string(),
+                "_pico/covers/gba/BPRE.bmp".to_string()
+            ]
+        );
+
+        // The card's cover is newer than the 2020 one in the zip
+        let stats = pack.import(card.path(), ConflictPolicy::Newest).unwrap();
+        assert_eq!(stats.kept, 1);
+        assert_eq!(
+            fs::read(card.path().join("_pico/covers/nds/AMCE.bmp")).unwrap(),
+            bmp(5)
+        );
+    }
+
+    #[test]
+    fn test_import_keeps_protected_covers() {
+        let card = tempfile::tempdir().unwrap();
+        let mut provenance = Provenance::new();
+        for code in ["AMCE", "ABCE", "BBCE"] {
+            write_cover(card.path(), Platform::Nds, code, &bmp(1));
+            provenance.record(record(code, &bmp(1)));
+        }
+        // Edited by hand, and locked
+        write_cover(card.path(), Platform::Nds, "ABCE", &bmp(2));
+        provenance.set_lock(Platform::Nds, "BBCE", Some(true));
+        provenance.save(card.path()).unwrap();
+
+        let mut pack = CoverPack::new();
+        for code in ["AMCE", "ABCE", "BBCE"] {
+            let entry = PackEntry {
+                platform: Platform::Nds,
+                code: code.to_string(),
+                source: None,
+                region: None,
+                sha256: String::new(),
+                modified: 0,
+            };
+            pack.insert(entry, bmp(7));
+        }
+        let stats = pack.import(card.path(), ConflictPolicy::Replace).unwrap();
+        assert_eq!((stats.replaced, stats.kept, stats.locked), (1, 2, 2));
+
+        let covers = card.path().join("_pico/covers/nds");
+        assert_eq!(fs::read(covers.join("AMCE.bmp")).unwrap(), bmp(7));
+        assert_eq!(fs::read(covers.join("ABCE.bmp")).unwrap(), bmp(2));
+        assert_eq!(fs::read(covers.join("BBCE.bmp")).unwrap(), bmp(1));
+        let provenance = Provenance::load(card.path()).unwrap();
+        assert!(provenance.get(Platform::Nds, "AMCE").is_none());
+        assert!(provenance.get(Platform::Nds, "ABCE").is_some());
+    }
+
+    #[test]
+    fn test_add_cache_renders_originals() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::open(dir.path()).unwrap();
+        let response = |body: Vec<u8>| HttpResponse {
+            status: 200,
+            headers: Vec::new(),
+            body,
+        };
+        cache
+            .put(
+                "https://art.gametdb.com/ds/cover/US/AMCE.png",
+                &response(png(160, 144)),
+            )
+            .unwrap();
+        cache
+            .put(
+                "https://art.gametdb.com/ds/cart/US/AMCE.png",
+                &response(png(100, 100)),
+            )
+            .unwrap();
+        cache.put("gba:BPEE", &response(png(120, 120))).unwrap();
+        cache
+            .put("https://example.org/logo.png", &response(png(10, 10)))
+            .unwrap();
+
+        let mut pack = CoverPack::new();
+        assert_eq!(
+            pack.add_cache(&cache, &RenderOptions::default()).unwrap(),
+            2
+        );
+        let entries: Vec<&PackEntry> = pack.entries().collect();
+        assert_eq!(entries[0].code, "AMCE");
+        assert_eq!(entries[0].region.as_deref(), Some("US"));
+        assert_eq!(
+            entries[0].source.as_deref(),
+            Some("https://art.gametdb.com/ds/cover/US/AMCE.png")
+        );
+        assert_eq!(entries[1].platform, Platform::Gba);
+    }
+}
@@ -0,0 +1,1367 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinSet;
+use walkdir::WalkDir;
+
+use crate::art::ArtKind;
+use crate::cache::{hex_digest, unix_now};
+use crate::errors::{Error, FetchErrorKind, Result};
+use crate::game::{header_code_field, nds_banner_title, GameCode, Platform};
+use crate::http::{HttpClient, HttpRequest};
+use crate::image_processing::{ImageProcessor, RenderOptions, RenderedCover};
+use crate::metrics::QualityMetrics;
+use crate::provenance::{CoverLock, CoverRecord, Provenance, TOOL_VERSION};
+use crate::source::{CoverRequest, CoverSource, FetchedCover, Logger, Override, Overrides};
+use crate::titles::{TitleDb, TitleMatch, DEFAULT_MATCH_THRESHOLD};
+
+/// Bytes read from the start of each ROM; enough for NDS and GBA headers
+const HEADER_LEN: usize = 0xB0;
+
+/// Offset of the banner offset field in an NDS header
+const NDS_BANNER_OFFSET_FIELD: usize = 0x68;
+
+/// Offset of the English title inside an NDS banner
+const NDS_BANNER_TITLE_OFFSET: u64 = 0x340;
+
+/// Length of one title in an NDS banner (128 UTF-16 units)
+const NDS_BANNER_TITLE_LEN: usize = 0x100;
+
+/// Totals and per-game results of a pipeline run
+#[derive(Debug, Default, Clone)]
+pub struct ProcessStats {
+    pub processed: u32,
+    pub saved: u32,
+    pub skipped: u32,
+    pub errors: u32,
+    pub skipped_games: Vec<SkippedGame>,
+    pub failed_games: Vec<FailedGame>,
+    /// Covers written; after [`Pipeline::refresh`], the ones upgraded
+    pub saved_games: Vec<SavedCover>,
+    /// ROMs without a usable code that were identified by title
+    pub title_matches: Vec<MatchedTitle>,
+    /// Existing covers kept despite `overwrite` (also counted as skipped)
+    pub locked_games: Vec<LockedCover>,
+}
+
+/// A game that was left alone
+#[derive(Debug, Clone)]
+pub struct SkippedGame {
+    pub game_name: String,
+    pub platform: Platform,
+    /// Name of the game's cover file without `.bmp`, when one is on the card
+    pub cover_name: Option<String>,
+}
+
+impl SkippedGame {
+    /// A skipped game without a cover on the card
+    fn new(game_name: impl Into<String>, platform: Platform) -> Self {
+        Self {
+            game_name: game_name.into(),
+            platform,
+            cover_name: None,
+        }
+    }
+
+    /// A skipped game whose cover `cover_name` is on the card
+    fn with_cover(game_name: impl Into<String>, platform: Platform, cover_name: String) -> Self {
+        Self {
+            cover_name: Some(cover_name),
+            ..Self::new(game_name, platform)
+        }
+    }
+}
+
+impl std::fmt::Display for SkippedGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.game_name)
+    }
+}
+
+/// A game whose cover could not be checked or written, and why
+#[derive(Debug, Clone)]
+pub struct FailedGame {
+    pub game_name: String,
+    /// Fetch classification, when the failure came from downloading
+    pub kind: Option<FetchErrorKind>,
+    pub reason: String,
+}
+
+impl FailedGame {
+    /// Describe a failure for a game
+    pub fn from_error(game_name: impl Into<String>, err: &Error) -> Self {
+        Self {
+            game_name: game_name.into(),
+            kind: err.fetch_kind(),
+            reason: err.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for FailedGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} - {}", self.game_name, self.reason)
+    }
+}
+
+/// Details of a cover that was written to the card
+#[derive(Debug, Clone)]
+pub struct SavedCover {
+    pub game_name: String,
+    pub game_code: String,
+    pub platform: Platform,
+    /// Cover file name without `.bmp`
+    pub cover_name: String,
+    pub region: String,
+    pub art_kind: ArtKind,
+    pub metrics: Option<QualityMetrics>,
+}
+
+/// An existing cover that was protected from being overwritten
+#[derive(Debug, Clone)]
+pub struct LockedCover {
+    pub game_name: String,
+    pub platform: Platform,
+    /// Cover file name without `.bmp`
+    pub cover_name: String,
+    pub lock: CoverLock,
+}
+
+impl std::fmt::Display for LockedCover {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}] - {}",
+            self.game_name, self.cover_name, self.lock
+        )
+    }
+}
+
+/// A ROM identified by comparing its title against the title database
+#[derive(Debug, Clone)]
+pub struct MatchedTitle {
+    /// ROM file stem
+    pub game_name: String,
+    /// File name or banner title that matched
+    pub matched_from: String,
+    /// Header code the cover is saved under (what Pico Launcher looks up)
+    pub cover_name: String,
+    pub found: TitleMatch,
+}
+
+impl std::fmt::Display for MatchedTitle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} -> {} from \"{}\", saved as {}.bmp",
+            self.game_name, self.found, self.matched_from, self.cover_name
+        )
+    }
+}
+
+/// Settings for a pipeline run
+#[derive(Debug, Clone)]
+pub struct PipelineOptions {
+    /// ROM platforms to scan for
+    pub platforms: Vec<Platform>,
+    /// Replace covers that already exist on the card
+    pub overwrite: bool,
+    /// Overwrite locked and hand-edited covers as well
+    pub force: bool,
+    pub render: RenderOptions,
+    /// Games being looked up at once (HTTP limits apply on top of this)
+    pub fetch_workers: usize,
+    /// Covers being rendered at once
+    pub render_workers: usize,
+    /// Capacity of the channels between stages
+    pub channel_capacity: usize,
+    /// Minimum similarity (0-1) for identifying ROMs without a code by title
+    pub title_match_threshold: f32,
+}
+
+impl Default for PipelineOptions {
+    fn default() -> Self {
+        Self {
+            platforms: vec![Platform::Nds],
+            overwrite: false,
+            force: false,
+            render: RenderOptions::default(),
+            fetch_workers: 32,
+            render_workers: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4),
+            channel_capacity: 64,
+            title_match_threshold: DEFAULT_MATCH_THRESHOLD,
+        }
+    }
+}
+
+/// A ROM whose cover still has to be fetched and written
+struct Job {
+    path: PathBuf,
+    game_name: String,
+    /// Canonical title from the title database, when one is loaded
+    title: Option<String>,
+    code: GameCode,
+    /// Code an override looks the game up by instead
+    forced_code: Option<GameCode>,
+    platform: Platform,
+    /// Cover file name without `.bmp`
+    cover_name: String,
+    target: PathBuf,
+    /// Code unknown to the title database: only local art is looked for
+    local_only: bool,
+}
+
+/// What to do with one ROM
+enum Prepared {
+    Fetch(Job),
+    /// Leave the game alone; the name of its cover, if one is on the card
+    Skip(Option<String>),
+}
+
+/// A rendered cover waiting to be written
+struct Rendered {
+    job: Job,
+    region: String,
+    cover: RenderedCover,
+    record: CoverRecord,
+}
+
+/// Collects results from every stage
+struct Recorder {
+    stats: Mutex<ProcessStats>,
+    log: Option<Logger>,
+}
+
+impl Recorder {
+    fn log(&self, message: String) {
+        if let Some(log) = &self.log {
+            log(message);
+        }
+    }
+
+    fn processed(&self) {
+        self.stats.lock().unwrap().processed += 1;
+    }
+
+    fn saved(&self, saved: SavedCover) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.saved += 1;
+        stats.saved_games.push(saved);
+    }
+
+    fn locked(&self, locked: LockedCover) {
+        self.stats.lock().unwrap().locked_games.push(locked);
+    }
+
+    fn matched(&self, matched: MatchedTitle) {
+        self.stats.lock().unwrap().title_matches.push(matched);
+    }
+
+    fn skipped(&self, game: SkippedGame) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.skipped += 1;
+        stats.skipped_games.push(game);
+    }
+
+    fn failed(&self, game_name: String, err: &Error) {
+        let failure = FailedGame::from_error(game_name, err);
+        self.log(format!("❌ Error {}", failure));
+        let mut stats = self.stats.lock().unwrap();
+        stats.errors += 1;
+        stats.failed_games.push(failure);
+    }
+}
+
+/// Finds ROMs under a card root and writes a cover for each
+///
+/// Scan, fetch, render and write stages are connected by bounded channels,
+/// so a slow stage applies backpressure instead of buffering the whole card.
+/// Downloads run as async tasks, rendering runs on tokio's blocking pool.
+pub struct Pipeline {
+    sources: Arc<dyn CoverSource>,
+    options: PipelineOptions,
+    titles: HashMap<Platform, Arc<TitleDb>>,
+    overrides: Arc<Overrides>,
+    log: Option<Logger>,
+}
+
+impl Pipeline {
+    /// Create a pipeline fetching from `sources` with default options
+    pub fn new(sources: Arc<dyn CoverSource>) -> Self {
+        Self {
+            sources,
+            options: PipelineOptions::default(),
+            titles: HashMap::new(),
+            overrides: Arc::new(Overrides::new()),
+            log: None,
+        }
+    }
+
+    /// Set the run options
+    pub fn with_options(mut self, options: PipelineOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Name games after their GameTDB title and keep codes the database doesn't know
+    /// off the network; they are still looked up in local sources
+    pub fn with_titles(mut self, titles: Arc<TitleDb>) -> Self {
+        self.titles.insert(titles.platform(), titles);
+        self
+    }
+
+    /// Skip games marked `skip` and trust overridden codes
+    ///
+    /// Wrap the sources in an [`OverrideSource`](crate::source::OverrideSource)
+    /// as well so the forced art is fetched.
+    pub fn with_overrides(mut self, overrides: Arc<Overrides>) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Receive progress lines
+    pub fn with_logger(mut self, log: Logger) -> Self {
+        self.log = Some(log);
+        self
+    }
+
+    /// Process every ROM under `root`, writing covers to the Pico Launcher folders
+    ///
+    /// Every cover written is recorded in the card's [`Provenance`] manifest,
+    /// which is also consulted so locked and hand-edited covers survive
+    /// `overwrite` unless `force` is set. A manifest that cannot be read is an
+    /// error rather than a reason to start over.
+    pub async fn run(&self, root: &Path) -> Result<ProcessStats> {
+        for platform in &self.options.platforms {
+            tokio::fs::create_dir_all(root.join(platform.cover_dir())).await?;
+        }
+        // An unreadable manifest would drop the lock list, so it stops the run
+        let provenance = Arc::new(Mutex::new(Provenance::load(root)?));
+
+        let capacity = self.options.channel_capacity.max(1);
+        let recorder = Arc::new(Recorder {
+            stats: Mutex::new(ProcessStats::default()),
+            log: self.log.clone(),
+        });
+        let (scan_tx, scan_rx) = mpsc::channel(capacity);
+        let (fetch_tx, fetch_rx) = mpsc::channel(capacity);
+        let (write_tx, write_rx) = mpsc::channel(capacity);
+
+        let scanner = tokio::task::spawn_blocking({
+            let root = root.to_path_buf();
+            let platforms = self.options.platforms.clone();
+            move || scan(&root, &platforms, scan_tx)
+        });
+        let fetcher = tokio::spawn(fetch_stage(
+            scan_rx,
+            fetch_tx,
+            Arc::clone(&self.sources),
+            self.options.clone(),
+            Identify {
+                titles: self.titles.clone(),
+                overrides: Arc::clone(&self.overrides),
+                provenance: Arc::clone(&provenance),
+            },
+            root.to_path_buf(),
+            Arc::clone(&recorder),
+        ));
+        let renderer = tokio::spawn(render_stage(
+            fetch_rx,
+            write_tx,
+            self.options.clone(),
+            Arc::clone(&recorder),
+        ));
+        let writer = tokio::spawn(write_stage(
+            write_rx,
+            Arc::clone(&provenance),
+            Arc::clone(&recorder),
+        ));
+
+        join(scanner).await?;
+        join(fetcher).await?;
+        join(renderer).await?;
+        join(writer).await?;
+        provenance.lock().unwrap().save(root)?;
+
+        let recorder = Arc::try_unwrap(recorder)
+            .map_err(|_| Error::Processing("pipeline stage still running".to_string()))?;
+        Ok(recorder.stats.into_inner().unwrap())
+    }
+
+    /// Re-render only the covers whose source image changed upstream
+    ///
+    /// Every cover in the card's [`Provenance`] manifest is revalidated with
+    /// its recorded `ETag`/`Last-Modified`, then by comparing image hashes.
+    /// Upgraded covers are reported as saved and unchanged ones as skipped;
+    /// locks are honoured as in [`Pipeline::run`]. `client` should bypass any
+    /// download cache, or cached copies will hide the changes.
+    pub async fn refresh(&self, root: &Path, client: Arc<dyn HttpClient>) -> Result<ProcessStats> {
+        let mut provenance = Provenance::load(root)?;
+        let recorder = Recorder {
+            stats: Mutex::new(ProcessStats::default()),
+            log: self.log.clone(),
+        };
+        let permits = Arc::new(Semaphore::new(self.options.fetch_workers.max(1)));
+        let mut tasks = JoinSet::new();
+
+        let records: Vec<CoverRecord> = provenance
+            .records()
+            .filter(|record| self.options.platforms.contains(&record.platform))
+            .cloned()
+            .collect();
+        for record in records {
+            recorder.processed();
+            let target = root
+                .join(record.platform.cover_dir())
+                .join(format!("{}.bmp", record.cover_name));
+            let Ok(bmp) = tokio::fs::read(&target).await else {
+                recorder.log(format!(
+                    "⏭ Skipped {} [{}] - no longer on the card",
+                    record.game_name, record.cover_name
+                ));
+                recorder.skipped(SkippedGame::new(record.game_name, record.platform));
+                continue;
+            };
+            let lock = provenance.cover_lock(record.platform, &record.cover_name, &bmp);
+            if let Some(lock) = lock.filter(|_| !self.options.force) {
+                recorder.log(format!(
+                    "🔒 Skipped {} [{}] - {}",
+                    record.game_name, record.cover_name, lock
+                ));
+                recorder.locked(LockedCover {
+                    game_name: record.game_name.clone(),
+                    platform: record.platform,
+                    cover_name: record.cover_name.clone(),
+                    lock,
+                });
+                recorder.skipped(SkippedGame::with_cover(
+                    record.game_name,
+                    record.platform,
+                    record.cover_name,
+                ));
+                continue;
+            }
+
+            let Ok(permit) = Arc::clone(&permits).acquire_owned().await else {
+                break;
+            };
+            let client = Arc::clone(&client);
+            tasks.spawn(async move {
+                let refreshed = refresh_cover(client.as_ref(), record.clone()).await;
+                drop(permit);
+                (record, target, refreshed)
+            });
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            let (old, target, refreshed) =
+                joined.map_err(|e| Error::Processing(format!("refresh task failed: {}", e)))?;
+            match refreshed {
+                Ok(Refreshed::Unchanged(record)) => {
+                    if record != old {
+                        provenance.record(record);
+                    }
+                    recorder.skipped(SkippedGame::with_cover(
+                        old.game_name,
+                        old.platform,
+                        old.cover_name,
+                    ));
+                }
+                Ok(Refreshed::Unsupported) => {
+                    recorder.log(format!(
+                        "⏭ Skipped {} [{}] - {} can't be checked again",
+                        old.game_name, old.cover_name, old.source
+                    ));
+                    recorder.skipped(SkippedGame::with_cover(
+                        old.game_name,
+                        old.platform,
+                        old.cover_name,
+                    ));
+                }
+                Ok(Refreshed::Upgraded(record, cover)) => {
+                    if let Err(err) = tokio::fs::write(&target, &cover.bmp).await {
+                        recorder.failed(old.game_name, &Error::Io(err));
+                        continue;
+                    }
+                    recorder.log(format!(
+                        "⬆ Upgraded {} [{}] - {}",
+                        record.game_name, record.cover_name, record.origin
+                    ));
+                    recorder.saved(SavedCover {
+                        game_name: record.game_name.clone(),
+                        game_code: record
+                            .game_code
+                            .clone()
+                            .unwrap_or_else(|| record.cover_name.clone()),
+                        platform: record.platform,
+                        cover_name: record.cover_name.clone(),
+                        region: record.region.clone().unwrap_or(record.source.clone()),
+                        art_kind: record.art_kind,
+                        metrics: cover.metrics,
+                    });
+                    provenance.record(record);
+                }
+                Err(err) => recorder.failed(old.game_name, &err),
+            }
+        }
+
+        provenance.save(root)?;
+        Ok(recorder.stats.into_inner().unwrap())
+    }
+}
+
+/// What the fetch stage consults to name ROMs and decide which need covers
+struct Identify {
+    titles: HashMap<Platform, Arc<TitleDb>>,
+    overrides: Arc<Overrides>,
+    provenance: Arc<Mutex<Provenance>>,
+}
+
+async fn join<T>(handle: tokio::task::JoinHandle<T>) -> Result<T> {
+    handle
+        .await
+        .map_err(|e| Error::Processing(format!("pipeline stage failed: {}", e)))
+}
+
+/// Walk the card and send every ROM of the wanted platforms
+fn scan(root: &Path, platforms: &[Platform], tx: mpsc::Sender<(PathBuf, Platform)>) {
+    for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Some(platform) = Platform::from_path(entry.path()) else {
+            continue;
+        };
+        if !platforms.contains(&platform) {
+            continue;
+        }
+        if tx
+            .blocking_send((entry.path().to_path_buf(), platform))
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+async fn fetch_stage(
+    mut scan_rx: mpsc::Receiver<(PathBuf, Platform)>,
+    fetch_tx: mpsc::Sender<(Job, Result<Option<FetchedCover>>)>,
+    sources: Arc<dyn CoverSource>,
+    options: PipelineOptions,
+    identify: Identify,
+    root: PathBuf,
+    recorder: Arc<Recorder>,
+) {
+    let permits = Arc::new(Semaphore::new(options.fetch_workers.max(1)));
+    let mut tasks = JoinSet::new();
+
+    while let Some((path, platform)) = scan_rx.recv().await {
+        recorder.processed();
+        let game_name = file_name(&path);
+        let job = match prepare(&root, path, platform, &options, &identify, &recorder).await {
+            Ok(Prepared::Fetch(job)) => job,
+            Ok(Prepared::Skip(cover_name)) => {
+                recorder.skipped(SkippedGame {
+                    game_name,
+                    platform,
+                    cover_name,
+                });
+                continue;
+            }
+            Err(err) => {
+                recorder.failed(game_name, &err);
+                continue;
+            }
+        };
+
+        // The semaphore is never closed, so acquiring cannot fail
+        let Ok(permit) = Arc::clone(&permits).acquire_owned().await else {
+            break;
+        };
+        let sources = Arc::clone(&sources);
+        let log = recorder.log.clone();
+        let fetch_tx = fetch_tx.clone();
+        tasks.spawn(async move {
+            let mut request = CoverRequest::new(job.code.clone(), job.platform)
+                .with_game_name(job.game_name.clone())
+                .with_rom_path(job.path.clone());
+            if let Some(title) = &job.title {
+                request = request.with_title(title.as_str());
+            }
+            if job.local_only {
+                request = request.with_local_only();
+            }
+            if let Some(log) = log {
+                request = request.with_logger(log);
+            }
+            let fetched = sources.fetch(&request).await;
+            drop(permit);
+            let _ = fetch_tx.send((job, fetched)).await;
+        });
+    }
+
+    while tasks.join_next().await.is_some() {}
+}
+
+async fn render_stage(
+    mut fetch_rx: mpsc::Receiver<(Job, Result<Option<FetchedCover>>)>,
+    write_tx: mpsc::Sender<Rendered>,
+    options: PipelineOptions,
+    recorder: Arc<Recorder>,
+) {
+    let permits = Arc::new(Semaphore::new(options.render_workers.max(1)));
+    let render_options = Arc::new(options.render);
+    let mut tasks = JoinSet::new();
+
+    while let Some((job, fetched)) = fetch_rx.recv().await {
+        let found = match fetched {
+            Ok(Some(found)) => found,
+            Ok(None) => {
+                recorder.log(format!(
+                    "❌ Not found {} [{}] - no covers available",
+                    job.game_name, job.code
+                ));
+                let skipped = if tokio::fs::try_exists(&job.target).await.unwrap_or(false) {
+                    SkippedGame::with_cover(job.game_name, job.platform, job.cover_name)
+                } else {
+                    SkippedGame::new(job.game_name, job.platform)
+                };
+                recorder.skipped(skipped);
+                continue;
+            }
+            Err(err) => {
+                recorder.failed(job.game_name, &err);
+                continue;
+            }
+        };
+
+        let Ok(permit) = Arc::clone(&permits).acquire_owned().await else {
+            break;
+        };
+        let render_options = Arc::clone(&render_options);
+        let recorder = Arc::clone(&recorder);
+        let write_tx = write_tx.clone();
+        tasks.spawn_blocking(move || {
+            let rendered = ImageProcessor::render_art_with_report(
+                &found.image,
+                found.art_kind,
+                &render_options,
+            );
+            drop(permit);
+            match rendered {
+                Ok(cover) => {
+                    let looked_up = job.forced_code.as_ref().unwrap_or(&job.code);
+                    let record = CoverRecord {
+                        platform: job.platform,
+                        cover_name: job.cover_name.clone(),
+                        game_code: (looked_up.as_str() != job.cover_name)
+                            .then(|| looked_up.to_string()),
+                        game_name: job.game_name.clone(),
+                        source: found.source.clone(),
+                        origin: found.origin,
+                        region: found.region.clone(),
+                        art_kind: found.art_kind,
+                        source_sha256: hex_digest(&found.bytes),
+                        etag: found.etag,
+                        last_modified: found.last_modified,
+                        cover_sha256: hex_digest(&cover.bmp),
+                        render: (*render_options).clone(),
+                        tool: TOOL_VERSION.to_string(),
+                        created: unix_now(),
+                    };
+                    let rendered = Rendered {
+                        region: found.region.unwrap_or(found.source),
+                        job,
+                        cover,
+                        record,
+                    };
+                    let _ = write_tx.blocking_send(rendered);
+                }
+                Err(err) => recorder.failed(job.game_name, &err),
+            }
+        });
+    }
+
+    while tasks.join_next().await.is_some() {}
+}
+
+async fn write_stage(
+    mut write_rx: mpsc::Receiver<Rendered>,
+    provenance: Arc<Mutex<Provenance>>,
+    recorder: Arc<Recorder>,
+) {
+    while let Some(Rendered {
+        job,
+        region,
+        cover,
+        record,
+    }) = write_rx.recv().await
+    {
+        if let Err(err) = tokio::fs::write(&job.target, &cover.bmp).await {
+            recorder.failed(job.game_name, &Error::Io(err));
+            continue;
+        }
+        let art_kind = record.art_kind;
+        provenance.lock().unwrap().record(record);
+
+        match &cover.metrics {
+            Some(metrics) => recorder.log(format!(
+                "💾 Stored {} [{}] - {}",
+                job.game_name, job.code, metrics
+            )),
+            None => recorder.log(format!("💾 Stored {} [{}]", job.game_name, job.code)),
+        }
+        recorder.saved(SavedCover {
+            game_name: job.game_name,
+            game_code: job.code.to_string(),
+            platform: job.platform,
+            cover_name: job.cover_name,
+            region,
+            art_kind,
+            metrics: cover.metrics,
+        });
+    }
+}
+
+/// Outcome of checking a recorded cover's source again
+enum Refreshed {
+    /// Same image; the record may carry new validators
+    Unchanged(CoverRecord),
+    /// The origin is neither a URL nor a file that still exists
+    Unsupported,
+    /// New image, rendered, with its updated record
+    Upgraded(CoverRecord, RenderedCover),
+}
+
+/// Fetch a cover's origin again and render it if the image changed
+///
+/// The cover is rendered with its recorded settings, so only the source
+/// image changes.
+async fn refresh_cover(client: &dyn HttpClient, mut record: CoverRecord) -> Result<Refreshed> {
+    let origin = record.origin.clone();
+    let bytes = if origin.starts_with("http://") || origin.starts_with("https://") {
+        let mut request = HttpRequest::get(&origin);
+        if let Some(etag) = &record.etag {
+            request = request.header("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &record.last_modified {
+            request = request.header("If-Modified-Since", last_modified);
+        }
+        let response = client.send(request).await?;
+        if response.status == 304 {
+            return Ok(Refreshed::Unchanged(record));
+        }
+        if let Some(kind) = FetchErrorKind::from_status(response.status) {
+            return Err(Error::http_status(kind, &origin, response.status));
+        }
+        record.etag = response.header("etag").map(str::to_string);
+        record.last_modified = response.header("last-modified").map(str::to_string);
+        response.body
+    } else if Path::new(&origin).is_file() {
+        tokio::fs::read(&origin).await?
+    } else {
+        return Ok(Refreshed::Unsupported);
+    };
+
+    let source_sha256 = hex_digest(&bytes);
+    if source_sha256 == record.source_sha256 {
+        return Ok(Refreshed::Unchanged(record));
+    }
+    let found = FetchedCover::decode(bytes, record.source.as_str(), origin)?;
+    let art_kind = record.art_kind;
+    let render = record.render.clone();
+    let cover = tokio::task::spawn_blocking(move || {
+        ImageProcessor::render_art_with_report(&found.image, art_kind, &render)
+    })
+    .await
+    .map_err(|e| Error::Processing(format!("render task failed: {}", e)))??;
+
+    record.source_sha256 = source_sha256;
+    record.cover_sha256 = hex_digest(&cover.bmp);
+    record.tool = TOOL_VERSION.to_string();
+    record.created = unix_now();
+    Ok(Refreshed::Upgraded(record, cover))
+}
+
+/// Read the game code and decide whether the cover needs fetching
+async fn prepare(
+    root: &Path,
+    path: PathBuf,
+    platform: Platform,
+    options: &PipelineOptions,
+    identify: &Identify,
+    recorder: &Recorder,
+) -> Result<Prepared> {
+    let header = read_header(&path).await?;
+    let file_name = file_name(&path);
+    let header_code = GameCode::from_rom_header(platform, &header).ok();
+    let titles = identify.titles.get(&platform).map(Arc::as_ref);
+
+    let entry = identify.overrides.find(header_code.as_ref(), Some(&path));
+    if entry.is_some_and(|entry| entry.skip) {
+        recorder.log(format!("⏭ Skipped {} - override", file_name));
+        return Ok(Prepared::Skip(None));
+    }
+    let forced_code = entry.and_then(Override::code);
+    // An override names the game itself, so the title database isn't consulted
+    let identified = entry.and(header_code.clone().or_else(|| forced_code.clone()));
+
+    let mut local_only = false;
+    let (code, game_name, cover_name) = match (identified, header_code, titles) {
+        (Some(code), _, titles) => {
+            let Some(cover_name) = header_code_field(platform, &header) else {
+                recorder.log(format!(
+                    "⏭ Skipped {} - no game code to name the cover after",
+                    file_name
+                ));
+                return Ok(Prepared::Skip(None));
+            };
+            let game_name = titles
+                .and_then(|titles| titles.title(forced_code.as_ref().unwrap_or(&code)))
+                .map(str::to_string)
+                .unwrap_or(file_name);
+            (code, game_name, cover_name)
+        }
+        (None, Some(code), None) => (code.clone(), file_name, code.to_string()),
+        (None, None, None) => return Ok(Prepared::Skip(None)),
+        (None, Some(code), Some(titles)) if !code.is_placeholder() && titles.contains(&code) => {
+            let title = titles.title(&code).unwrap_or(&file_name).to_string();
+            (code.clone(), title, code.to_string())
+        }
+        // Blank, placeholder or unknown code: look the game up by its title instead
+        (None, header_code, Some(titles)) => {
+            let reason = match &header_code {
+                Some(code) => format!("unknown game code {}", code),
+                None => "no game code".to_string(),
+            };
+            let Some(cover_name) = header_code_field(platform, &header) else {
+                recorder.log(format!(
+                    "⏭ Skipped {} - {} to name the cover after",
+                    file_name, reason
+                ));
+                return Ok(Prepared::Skip(None));
+            };
+
+            let mut candidates = vec![file_name.clone()];
+            if platform == Platform::Nds {
+                candidates.extend(read_banner_title(&path, &header).await);
+            }
+            let best = candidates
+                .into_iter()
+                .filter_map(|text| Some((titles.best_match(&text)?, text)))
+                .max_by(|a, b| a.0.score.total_cmp(&b.0.score));
+            match best {
+                Some((found, matched_from)) if found.score >= options.title_match_threshold => {
+                    recorder.log(format!(
+                        "🔎 Matched {} ({}) to {}",
+                        file_name, reason, found
+                    ));
+                    let code = found.code.clone();
+                    let title = found.title.clone();
+                    recorder.matched(MatchedTitle {
+                        game_name: file_name,
+                        matched_from,
+                        cover_name: cover_name.clone(),
+                        found,
+                    });
+                    (code, title, cover_name)
+                }
+                best => {
+                    let closest = best
+                        .map(|(found, _)| format!(", closest {}", found))
+                        .unwrap_or_default();
+                    match header_code {
+                        // Homebrew or a new dump: the user may still have art for it
+                        Some(code) if !code.is_placeholder() => {
+                            recorder.log(format!(
+                                "🔎 {} - {} and no confident title match{}, checking local art only",
+                                file_name, reason, closest
+                            ));
+                            local_only = true;
+                            (code, file_name, cover_name)
+                        }
+                        _ => {
+                            recorder.log(format!(
+                                "⏭ Skipped {} - {} and no confident title match{}",
+                                file_name, reason, closest
+                            ));
+                            return Ok(Prepared::Skip(None));
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = titles
+        .and_then(|titles| titles.title(forced_code.as_ref().unwrap_or(&code)))
+        .map(str::to_string);
+    let target = root
+        .join(platform.cover_dir())
+        .join(format!("{cover_name}.bmp"));
+    if tokio::fs::try_exists(&target).await.unwrap_or(false) {
+        if !options.overwrite {
+            recorder.log(format!(
+                "⏭ Skipped {} [{}] - already exists",
+                game_name, code
+            ));
+            return Ok(Prepared::Skip(Some(cover_name)));
+        }
+        if !options.force {
+            let bmp = tokio::fs::read(&target).await?;
+            let lock = identify
+                .provenance
+                .lock()
+                .unwrap()
+                .cover_lock(platform, &cover_name, &bmp);
+            if let Some(lock) = lock {
+                recorder.log(format!("🔒 Skipped {} [{}] - {}", game_name, code, lock));
+                recorder.locked(LockedCover {
+                    game_name,
+                    platform,
+                    cover_name: cover_name.clone(),
+                    lock,
+                });
+                return Ok(Prepared::Skip(Some(cover_name)));
+            }
+        }
+    }
+
+    Ok(Prepared::Fetch(Job {
+        path,
+        game_name,
+        title,
+        code,
+        forced_code,
+        platform,
+        cover_name,
+        target,
+        local_only,
+    }))
+}
+
+/// Read the game code from a ROM header; `None` for files too short or without a code
+pub async fn read_game_code(path: &Path, platform: Platform) -> Result<Option<GameCode>> {
+    let header = read_header(path).await?;
+    Ok(GameCode::from_rom_header(platform, &header).ok())
+}
+
+async fn read_header(path: &Path) -> Result<Vec<u8>> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    (&mut file)
+        .take(HEADER_LEN as u64)
+        .read_to_end(&mut header)
+        .await?;
+    Ok(header)
+}
+
+/// Read the English title from an NDS banner, if the ROM has one
+async fn read_banner_title(path: &Path, header: &[u8]) -> Option<String> {
+    let field = header.get(NDS_BANNER_OFFSET_FIELD..NDS_BANNER_OFFSET_FIELD + 4)?;
+    let banner = u32::from_le_bytes(field.try_into().ok()?) as u64;
+    if banner == 0 {
+        return None;
+    }
+
+    let mut file = tokio::fs::File::open(path).await.ok()?;
+    file.seek(std::io::SeekFrom::Start(banner + NDS_BANNER_TITLE_OFFSET))
+        .await
+        .ok()?;
+    let mut title = Vec::with_capacity(NDS_BANNER_TITLE_LEN);
+    file.take(NDS_BANNER_TITLE_LEN as u64)
+        .read_to_end(&mut title)
+        .await
+        .ok()?;
+    nds_banner_title(&title)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|n| n.to_str())
+        .unwrap_or("Unknown")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::testing::png;
+    use crate::source::{ChainedSource, HttpTemplateSource, LocalFolderSource, OverrideSource};
+
+    fn write_rom(dir: &Path, name: &str, code: &[u8; 4]) {
+        let mut rom = vec![0u8; 0x200];
+        rom[0x0C..0x10].copy_from_slice(code);
+        std::fs::write(dir.join(name), rom).unwrap();
+    }
+
+    fn skipped_names(stats: &ProcessStats) -> Vec<&str> {
+        stats
+            .skipped_games
+            .iter()
+            .map(|game| game.game_name.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_writes_found_covers() {
+        let card = tempfile::tempdir().unwrap();
+        let library = tempfile::tempdir().unwrap();
+        write_rom(card.path(), "Found.nds", b"ABCE");
+        write_rom(card.path(), "Missing.nds", b"ZZZE");
+        std::fs::write(card.path().join("notes.txt"), "not a rom").unwrap();
+        std::fs::write(library.path().join("ABCE.png"), png(40, 36)).unwrap();
+
+        let pipeline = Pipeline::new(Arc::new(LocalFolderSource::new(library.path())));
+        let stats = pipeline.run(card.path()).await.unwrap();
+
+        assert_eq!(stats.processed, 2);
+        assert_eq!(stats.saved, 1);
+        assert_eq!(skipped_names(&stats), vec!["Missing"]);
+        assert_eq!(stats.skipped_games[0].cover_name, None);
+        assert_eq!(stats.saved_games[0].game_code, "ABCE");
+        assert!(card.path().join("_pico/covers/nds/ABCE.bmp").is_file());
+
+        let provenance = Provenance::load(card.path()).unwrap();
+        let record = provenance.get(Platform::Nds, "ABCE").unwrap();
+        assert_eq!(record.source, "local");
+        assert_eq!(
+            record.origin,
+            library.path().join("ABCE.png").display().to_string()
+        );
+        assert_eq!(record.source_sha256, hex_digest(&png(40, 36)));
+        let bmp = std::fs::read(card.path().join("_pico/covers/nds/ABCE.bmp")).unwrap();
+        assert!(record.matches(&bmp));
+
+        // Existing covers are skipped on the next run
+        let again = pipeline.run(card.path()).await.unwrap();
+        assert_eq!(again.saved, 0);
+        assert_eq!(again.skipped, 2);
+        let mut covers: Vec<_> = again
+            .skipped_games
+            .iter()
+            .map(|game| (game.platform, game.cover_name.as_deref()))
+            .collect();
+        covers.sort();
+        assert_eq!(
+            covers,
+            vec![(Platform::Nds, None), (Platform::Nds, Some("ABCE"))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_keeps_locked_and_edited_covers() {
+        let card = tempfile::tempdir().unwrap();
+        let library = tempfile::tempdir().unwrap();
+        for (name, code) in [("a.nds", b"ABCE"), ("b.nds", b"BBCE"), ("c.nds", b"CBCE")] {
+            write_rom(card.path(), name, code);
+            let cover = library
+                .path()
+                .join(format!("{}.png", String::from_utf8_lossy(code)));
+            std::fs::write(cover, png(40, 36)).unwrap();
+        }
+        let sources: Arc<dyn CoverSource> = Arc::new(LocalFolderSource::new(library.path()));
+        Pipeline::new(Arc::clone(&sources))
+            .run(card.path())
+            .await
+            .unwrap();
+
+        // Edit one cover by hand and lock another
+        let covers = card.path().join("_pico/covers/nds");
+        std::fs::write(covers.join("ABCE.bmp"), b"BMhand made").unwrap();
+        let mut provenance = Provenance::load(card.path()).unwrap();
+        provenance.set_lock(Platform::Nds, "BBCE", Some(true));
+        provenance.save(card.path()).unwrap();
+
+        let overwrite = PipelineOptions {
+            overwrite: true,
+            ..PipelineOptions::default()
+        };
+        let stats = Pipeline::new(Arc::clone(&sources))
+            .with_options(overwrite.clone())
+            .run(card.path())
+            .await
+            .unwrap();
+        assert_eq!(stats.saved, 1);
+        assert_eq!(stats.skipped, 2);
+        let mut locks: Vec<_> = stats
+            .locked_games
+            .iter()
+            .map(|locked| (locked.cover_name.as_str(), locked.lock))
+            .collect();
+        locks.sort_by_key(|(name, _)| *name);
+        assert_eq!(
+            locks,
+            vec![("ABCE", CoverLock::Customised), ("BBCE", CoverLock::Locked)]
+        );
+        assert_eq!(
+            std::fs::read(covers.join("ABCE.bmp")).unwrap(),
+            b"BMhand made"
+        );
+
+        let stats = Pipeline::new(sources)
+            .with_options(PipelineOptions {
+                force: true,
+                ..overwrite
+            })
+            .run(card.path())
+            .await
+            .unwrap();
+        assert_eq!(stats.saved, 3);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_stops_on_corrupt_manifest() {
+        let card = tempfile::tempdir().unwrap();
+        let library = tempfile::tempdir().unwrap();
+        write_rom(card.path(), "a.nds", b"ABCE");
+        std::fs::write(library.path().join("ABCE.png"), png(40, 36)).unwrap();
+        let covers = card.path().join("_pico/covers/nds");
+        std::fs::create_dir_all(&covers).unwrap();
+        std::fs::write(covers.join("ABCE.bmp"), b"BMhand made").unwrap();
+        std::fs::write(Provenance::path(card.path()), "{\"version\": 1, \"locks\"").unwrap();
+
+        let result = Pipeline::new(Arc::new(LocalFolderSource::new(library.path())))
+            .with_options(PipelineOptions {
+                overwrite: true,
+                ..PipelineOptions::default()
+            })
+            .run(card.path())
+            .await;
+        assert!(result.is_err());
+        assert_eq!(
+            std::fs::read(covers.join("ABCE.bmp")).unwrap(),
+            b"BMhand made"
+        );
+        assert_eq!(
+            std::fs::read_to_string(Provenance::path(card.path())).unwrap(),
+            "{\"version\": 1, \"locks\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_refresh_upgrades_changed_covers() {
+        let card = tempfile::tempdir().unwrap();
+        let library = tempfile::tempdir().unwrap();
+        write_rom(card.path(), "a.nds", b"ABCE");
+        write_rom(card.path(), "b.nds", b"BBCE");
+        std::fs::write(library.path().join("ABCE.png"), png(40, 36)).unwrap();
+        std::fs::write(library.path().join("BBCE.png"), png(40, 36)).unwrap();
+        let pipeline = Pipeline::new(Arc::new(LocalFolderSource::new(library.path())));
+        pipeline.run(card.path()).await.unwrap();
+
+        // A better scan replaces one original
+        std::fs::write(library.path().join("ABCE.png"), png(400, 360)).unwrap();
+        let client = Arc::new(crate::source::testing::MockClient::default());
+        let stats = pipeline.refresh(card.path(), client).await.unwrap();
+
+        assert_eq!(stats.processed, 2);
+        assert_eq!(stats.saved, 1);
+        assert_eq!(stats.saved_games[0].cover_name, "ABCE");
+        assert_eq!(skipped_names(&stats), vec!["b"]);
+        let provenance = Provenance::load(card.path()).unwrap();
+        let record = provenance.get(Platform::Nds, "ABCE").unwrap();
+        assert_eq!(record.source_sha256, hex_digest(&png(400, 360)));
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_refresh_keeps_render_options() {
+        let card = tempfile::tempdir().unwrap();
+        let library = tempfile::tempdir().unwrap();
+        write_rom(card.path(), "a.nds", b"ABCE");
+        std::fs::write(library.path().join("ABCE.png"), png(40, 36)).unwrap();
+        let sources: Arc<dyn CoverSource> = Arc::new(LocalFolderSource::new(library.path()));
+        let render = RenderOptions {
+            linear_light: true,
+            ..RenderOptions::default()
+        };
+        Pipeline::new(Arc::clone(&sources))
+            .with_options(PipelineOptions {
+                render: render.clone(),
+                ..PipelineOptions::default()
+            })
+            .run(card.path())
+            .await
+            .unwrap();
+
+        // Refreshing with default settings swaps the image, not the rendering
+        std::fs::write(library.path().join("ABCE.png"), png(400, 360)).unwrap();
+        let client = Arc::new(crate::source::testing::MockClient::default());
+        let stats = Pipeline::new(sources)
+            .refresh(card.path(), client)
+            .await
+            .unwrap();
+        assert_eq!(stats.saved, 1);
+
+        let provenance = Provenance::load(card.path()).unwrap();
+        let record = provenance.get(Platform::Nds, "ABCE").unwrap();
+        assert_eq!(record.render, render);
+        assert_eq!(record.source_sha256, hex_digest(&png(400, 360)));
+        let expected = ImageProcessor::render_art_with_report(
+            &image::load_from_memory(&png(400, 360)).unwrap(),
+            ArtKind::Cover,
+            &render,
+        )
+        .unwrap();
+        let bmp = std::fs::read(card.path().join("_pico/covers/nds/ABCE.bmp")).unwrap();
+        assert_eq!(bmp, expected.bmp);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_revalidates_urls() {
+        let url = "https://art.test/ds/cover/US/ABCE.png";
+        let record = CoverRecord {
+            platform: Platform::Nds,
+            cover_name: "ABCE".to_string(),
+            game_code: None,
+            game_name: "Example".to_string(),
+            source: "gametdb".to_string(),
+            origin: url.to_string(),
+            region: Some("US".to_string()),
+            art_kind: ArtKind::Cover,
+            source_sha256: hex_digest(&png(40, 36)),
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            cover_sha256: String::new(),
+            render: RenderOptions::default(),
+            tool: TOOL_VERSION.to_string(),
+            created: 0,
+        };
+
+        let client = crate::source::testing::MockClient::default().with_status(url, 304);
+        let refreshed = refresh_cover(&client, record.clone()).await;
+        assert!(matches!(refreshed, Ok(Refreshed::Unchanged(_))));
+
+        let client = crate::source::testing::MockClient::default().with_body(url, png(40, 36));
+        let refreshed = refresh_cover(&client, record.clone()).await;
+        assert!(matches!(refreshed, Ok(Refreshed::Unchanged(_))));
+
+        let client = crate::source::testing::MockClient::default().with_body(url, png(80, 72));
+        let refreshed = refresh_cover(&client, record).await;
+        let Ok(Refreshed::Upgraded(updated, cover)) = refreshed else {
+            panic!("expected an upgrade");
+        };
+        assert!(updated.matches(&cover.bmp));
+        assert_eq!(updated.etag, None);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_uses_titles() {
+        let card = tempfile::tempdir().unwrap();
+        let library = tempfile::tempdir().unwrap();
+        write_rom(card.path(), "mk.nds", b"ABCE");
+        write_rom(card.path(), "homebrew.nds", b"ZZZE");
+        write_rom(card.path(), "newdump.nds", b"YYYE");
+        std::fs::write(library.path().join("ABCE.png"), png(40, 36)).unwrap();
+        std::fs::write(library.path().join("ZZZE.png"), png(40, 36)).unwrap();
+        let titles = TitleDb::parse(Platform::Nds, "ABCE = Example Kart DS\n").unwrap();
+
+        let client = Arc::new(crate::source::testing::MockClient::default());
+        let sources = ChainedSource::new()
+            .with(LocalFolderSource::new(library.path()))
+            .with(HttpTemplateSource::new(
+                client.clone(),
+                "remote",
+                "https://art.test/{platform}/{id}.png",
+            ));
+
+        let stats = Pipeline::new(Arc::new(sources))
+            .with_titles(Arc::new(titles))
+            .run(card.path())
+            .await
+            .unwrap();
+
+        assert_eq!(stats.saved, 2);
+        let mut names: Vec<_> = stats
+            .saved_games
+            .iter()
+            .map(|saved| saved.game_name.as_str())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["Example Kart DS", "homebrew"]);
+        // Codes missing from the database only get local art
+        assert!(card.path().join("_pico/covers/nds/ZZZE.bmp").is_file());
+        assert!(!card.path().join("_pico/covers/nds/YYYE.bmp").exists());
+        assert!(client.requested().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_matches_titles_for_missing_codes() {
+        let card = tempfile::tempdir().unwrap();
+        let library = tempfile::tempdir().unwrap();
+        std::fs::write(library.path().join("ABCE.png"), png(40, 36)).unwrap();
+        let titles =
+            TitleDb::parse(Platform::Nds, "ABCE = Example Kart DS\nXYZE = Other Game\n").unwrap();
+
+        // Placeholder code, real title only in the banner
+        let mut rom = vec![0u8; 0x800];
+        rom[0x0C..0x10].copy_from_slice(b"####");
+        rom[0x68..0x6C].copy_from_slice(&0x400u32.to_le_bytes());
+        let banner_title: Vec<u8> = "Example Kart DS\nHomebrew Team"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let start = 0x400 + NDS_BANNER_TITLE_OFFSET as usize;
+        rom[start..start + banner_title.len()].copy_from_slice(&banner_title);
+        std::fs::write(card.path().join("kart_hack_v2.nds"), rom).unwrap();
+        write_rom(card.path(), "Unrelated.nds", b"HNXX");
+
+        let stats = Pipeline::new(Arc::new(LocalFolderSource::new(library.path())))
+            .with_titles(Arc::new(titles))
+            .run(card.path())
+            .await
+            .unwrap();
+
+        assert_eq!(stats.title_matches.len(), 1);
+        let matched = &stats.title_matches[0];
+        assert_eq!(matched.found.code.as_str(), "ABCE");
+        assert_eq!(matched.matched_from, "Example Kart DS");
+        // Saved under the header code, which is what the launcher looks up
+        assert!(card.path().join("_pico/covers/nds/####.bmp").is_file());
+        assert_eq!(skipped_names(&stats), vec!["Unrelated"]);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_honours_overrides() {
+        let card = tempfile::tempdir().unwrap();
+        let library = tempfile::tempdir().unwrap();
+        write_rom(card.path(), "mk.nds", b"ABCE");
+        write_rom(card.path(), "kart_hack.nds", b"ZZZE");
+        std::fs::write(library.path().join("ABCE.png"), png(40, 36)).unwrap();
+        let titles = TitleDb::parse(Platform::Nds, "ABCE = Example Kart DS\n").unwrap();
+        let overrides = Arc::new(
+            Overrides::parse(
+                r#"{"games": {"ABCE": "skip"}, "paths": {"kart_hack.nds": {"code": "ABCE"}}}"#,
+            )
+            .unwrap(),
+        );
+        let client = Arc::new(crate::source::testing::MockClient::default());
+        let sources = OverrideSource::new(
+            Arc::new(LocalFolderSource::new(library.path())),
+            Arc::clone(&overrides),
+            client,
+        );
+
+        let stats = Pipeline::new(Arc::new(sources))
+            .with_titles(Arc::new(titles))
+            .with_overrides(overrides)
+            .run(card.path())
+            .await
+            .unwrap();
+
+        assert_eq!(skipped_names(&stats), vec!["mk"]);
+        // The hack keeps its own header code but is looked up as the original game
+        assert_eq!(stats.saved_games[0].game_name, "Example Kart DS");
+        assert!(card.path().join("_pico/covers/nds/ZZZE.bmp").is_file());
+        assert!(!card.path().join("_pico/covers/nds/ABCE.bmp").exists());
+        let provenance = Provenance::load(card.path()).unwrap();
+        let record = provenance.get(Platform::Nds, "ZZZE").unwrap();
+        assert_eq!(record.game_code.as_deref(), Some("ABCE"));
+    }
+}
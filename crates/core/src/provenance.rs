@@ -0,0 +1,333 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::art::ArtKind;
+use crate::cache::{hex_digest, write_atomic};
+use crate::errors::{Error, Result};
+use crate::game::Platform;
+use crate::image_processing::RenderOptions;
+
+/// Manifest location below the card root
+pub const PROVENANCE_FILE: &str = "_pico/covers/picocover.json";
+
+/// Program and version recorded with every cover
+pub const TOOL_VERSION: &str = concat!("pico-cover ", env!("CARGO_PKG_VERSION"));
+
+/// Current manifest format version
+const PROVENANCE_VERSION: u32 = 1;
+
+/// How one cover on the card was made
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverRecord {
+    pub platform: Platform,
+    /// Cover file name without `.bmp`, normally the ROM's header code
+    pub cover_name: String,
+    /// Code the art was looked up by, when it differs from the cover name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_code: Option<String>,
+    pub game_name: String,
+    /// Name of the source that found the art
+    pub source: String,
+    /// URL or local file the art came from
+    pub origin: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    pub art_kind: ArtKind,
+    /// SHA-256 of the downloaded or local image, hex encoded
+    pub source_sha256: String,
+    /// Validators of the download, sent when checking for a newer image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// SHA-256 of the BMP written to the card, hex encoded
+    pub cover_sha256: String,
+    pub render: RenderOptions,
+    pub tool: String,
+    /// Unix time the cover was written
+    pub created: u64,
+}
+
+impl CoverRecord {
+    /// Key of the record in the manifest, e.g. `nds/AMCE`
+    pub fn key(&self) -> String {
+        record_key(self.platform, &self.cover_name)
+    }
+
+    /// Whether the BMP bytes are the ones this record describes
+    pub fn matches(&self, bmp: &[u8]) -> bool {
+        self.cover_sha256 == hex_digest(bmp)
+    }
+
+    /// Whether the cover was rendered by this version with these settings
+    pub fn is_current(&self, render: &RenderOptions) -> bool {
+        self.tool == TOOL_VERSION && self.render == *render
+    }
+}
+
+/// Why an existing cover is kept instead of being overwritten
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverLock {
+    /// On the user's lock list
+    Locked,
+    /// Changed since PicoCover wrote it, most likely edited by hand
+    Customised,
+}
+
+impl std::fmt::Display for CoverLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoverLock::Locked => write!(f, "locked"),
+            CoverLock::Customised => write!(f, "changed outside PicoCover"),
+        }
+    }
+}
+
+/// On-disk form of the manifest
+#[derive(Debug, Serialize, Deserialize)]
+struct ProvenanceFile<C, L> {
+    version: u32,
+    covers: C,
+    #[serde(default)]
+    locks: L,
+}
+
+/// Provenance manifest of a card
+///
+/// Holds one [`CoverRecord`] per generated cover, so later runs can tell
+/// stale covers from current ones, and the user's lock list that protects
+/// covers from being overwritten.
+#[derive(Debug, Default)]
+pub struct Provenance {
+    covers: BTreeMap<String, CoverRecord>,
+    /// Key → locked (`true`) or explicitly unlocked (`false`)
+    locks: BTreeMap<String, bool>,
+    dirty: bool,
+}
+
+impl Provenance {
+    /// Create an empty manifest
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Manifest path for a card root
+    pub fn path(root: &Path) -> PathBuf {
+        root.join(PROVENANCE_FILE)
+    }
+
+    /// Load the manifest of a card; a card without one starts empty
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = Self::path(root);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let file: ProvenanceFile<BTreeMap<String, CoverRecord>, BTreeMap<String, bool>> =
+            serde_json::from_slice(&bytes)
+                .map_err(|e| Error::Processing(format!("{}: {}", path.display(), e)))?;
+        Ok(Self {
+            covers: file.covers,
+            locks: file.locks,
+            dirty: false,
+        })
+    }
+
+    /// Look up the record of a cover
+    pub fn get(&self, platform: Platform, cover_name: &str) -> Option<&CoverRecord> {
+        self.covers.get(&record_key(platform, cover_name))
+    }
+
+    /// Add or replace the record of a cover
+    pub fn record(&mut self, record: CoverRecord) {
+        self.covers.insert(record.key(), record);
+        self.dirty = true;
+    }
+
+    /// Forget the record of a cover
+    pub fn remove(&mut self, platform: Platform, cover_name: &str) -> Option<CoverRecord> {
+        let removed = self.covers.remove(&record_key(platform, cover_name));
+        self.dirty |= removed.is_some();
+        removed
+    }
+
+    /// Lock (`Some(true)`), unlock (`Some(false)`) or reset (`None`) a cover
+    ///
+    /// Unlocked covers may be overwritten even after being edited by hand;
+    /// reset covers are protected only when they no longer match their record.
+    pub fn set_lock(&mut self, platform: Platform, cover_name: &str, locked: Option<bool>) {
+        let key = record_key(platform, cover_name);
+        let changed = match locked {
+            Some(locked) => self.locks.insert(key, locked) != Some(locked),
+            None => self.locks.remove(&key).is_some(),
+        };
+        self.dirty |= changed;
+    }
+
+    /// Get the explicit lock state of a cover, if the user set one
+    pub fn lock_state(&self, platform: Platform, cover_name: &str) -> Option<bool> {
+        self.locks.get(&record_key(platform, cover_name)).copied()
+    }
+
+    /// Why the existing cover `bmp` must not be overwritten, if it must not
+    ///
+    /// Covers without a record predate the manifest or came from elsewhere, so
+    /// they are only protected by an explicit lock.
+    pub fn cover_lock(
+        &self,
+        platform: Platform,
+        cover_name: &str,
+        bmp: &[u8],
+    ) -> Option<CoverLock> {
+        match self.lock_state(platform, cover_name) {
+            Some(true) => Some(CoverLock::Locked),
+            Some(false) => None,
+            None => self
+                .get(platform, cover_name)
+                .filter(|record| !record.matches(bmp))
+                .map(|_| CoverLock::Customised),
+        }
+    }
+
+    /// All records, sorted by platform and cover name
+    pub fn records(&self) -> impl Iterator<Item = &CoverRecord> {
+        self.covers.values()
+    }
+
+    /// Number of records
+    pub fn len(&self) -> usize {
+        self.covers.len()
+    }
+
+    /// Whether no covers are recorded
+    pub fn is_empty(&self) -> bool {
+        self.covers.is_empty()
+    }
+
+    /// Write the manifest back to the card if anything changed
+    pub fn save(&mut self, root: &Path) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let file = ProvenanceFile {
+            version: PROVENANCE_VERSION,
+            covers: &self.covers,
+            locks: &self.locks,
+        };
+        let json = serde_json::to_vec_pretty(&file)
+            .map_err(|e| Error::Processing(format!("provenance: {}", e)))?;
+
+        let path = Self::path(root);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write_atomic(&path, &json)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+fn record_key(platform: Platform, cover_name: &str) -> String {
+    format!("{}/{}", platform.as_str(), cover_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::unix_now;
+
+    #[test]
+    fn test_round_trip() {
+        let card = tempfile::tempdir().unwrap();
+        let mut provenance = Provenance::load(card.path()).unwrap();
+        assert!(provenance.is_empty());
+
+        let record = CoverRecord {
+            platform: Platform::Nds,
+            cover_name: "AMCE".to_string(),
+            game_code: None,
+            game_name: "Mario Kart DS".to_string(),
+            source: "gametdb".to_string(),
+            origin: "https://art.gametdb.com/ds/cover/US/AMCE.png".to_string(),
+            region: Some("US".to_string()),
+            art_kind: ArtKind::Cover,
+            source_sha256: hex_digest(b"png"),
+            etag: None,
+            last_modified: None,
+            cover_sha256: hex_digest(b"BMbmp"),
+            render: RenderOptions::default(),
+            tool: TOOL_VERSION.to_string(),
+            created: unix_now(),
+        };
+        provenance.record(record.clone());
+        provenance.save(card.path()).unwrap();
+
+        let loaded = Provenance::load(card.path()).unwrap();
+        let found = loaded.get(Platform::Nds, "AMCE").unwrap();
+        assert_eq!(found, &record);
+        assert!(found.matches(b"BMbmp"));
+        assert!(!found.matches(b"BMedited"));
+        assert!(found.is_current(&RenderOptions::default()));
+        assert!(!found.is_current(&RenderOptions {
+            linear_light: true,
+            ..RenderOptions::default()
+        }));
+    }
+
+    #[test]
+    fn test_cover_locks() {
+        let card = tempfile::tempdir().unwrap();
+        let mut provenance = Provenance::new();
+        provenance.record(CoverRecord {
+            platform: Platform::Gba,
+            cover_name: "BPEE".to_string(),
+            game_code: None,
+            game_name: "Pokemon Emerald".to_string(),
+            source: "proxy".to_string(),
+            origin: "https://proxy.test/gba/BPEE".to_string(),
+            region: None,
+            art_kind: ArtKind::Cover,
+            source_sha256: hex_digest(b"png"),
+            etag: None,
+            last_modified: None,
+            cover_sha256: hex_digest(b"BMours"),
+            render: RenderOptions::default(),
+            tool: TOOL_VERSION.to_string(),
+            created: 0,
+        });
+
+        assert_eq!(
+            provenance.cover_lock(Platform::Gba, "BPEE", b"BMours"),
+            None
+        );
+        assert_eq!(
+            provenance.cover_lock(Platform::Gba, "BPEE", b"BMedited"),
+            Some(CoverLock::Customised)
+        );
+        // No record: nothing to compare against
+        assert_eq!(provenance.cover_lock(Platform::Gba, "AXVE", b"BM"), None);
+
+        provenance.set_lock(Platform::Gba, "BPEE", Some(false));
+        provenance.set_lock(Platform::Gba, "AXVE", Some(true));
+        provenance.save(card.path()).unwrap();
+        let loaded = Provenance::load(card.path()).unwrap();
+        assert_eq!(loaded.cover_lock(Platform::Gba, "BPEE", b"BMedited"), None);
+        assert_eq!(
+            loaded.cover_lock(Platform::Gba, "AXVE", b"BM"),
+            Some(CoverLock::Locked)
+        );
+    }
+
+    #[test]
+    fn test_corrupt_manifest_is_an_error() {
+        let card = tempfile::tempdir().unwrap();
+        fs::create_dir_all(card.path().join("_pico/covers")).unwrap();
+        fs::write(Provenance::path(card.path()), "{not json").unwrap();
+        assert!(Provenance::load(card.path()).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+use crate::image_processing::{COVER_ART_HEIGHT, COVER_ART_WIDTH};
+
+/// How to choose between cover candidates found for the same game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SelectionStrategy {
+    /// Stop at the first region and template that decodes (fastest)
+    #[default]
+    FirstFound,
+    /// Probe every candidate and keep the highest resolution, then largest file
+    BestQuality,
+    /// Probe every candidate and keep the highest resolution, then sharpest image
+    BestSharpness,
+}
+
+impl SelectionStrategy {
+    /// All strategies, in the order they are presented to users
+    pub const ALL: [SelectionStrategy; 3] = [
+        SelectionStrategy::FirstFound,
+        SelectionStrategy::BestQuality,
+        SelectionStrategy::BestSharpness,
+    ];
+
+    /// Get the identifier used on the command line and in JS options
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SelectionStrategy::FirstFound => "first-found",
+            SelectionStrategy::BestQuality => "best-quality",
+            SelectionStrategy::BestSharpness => "best-sharpness",
+        }
+    }
+
+    /// Whether every candidate has to be downloaded before choosing
+    pub fn probes_all(&self) -> bool {
+        *self != SelectionStrategy::FirstFound
+    }
+
+    /// Pick the best candidate, returning its index
+    ///
+    /// Candidates must be given in preference order (region, then template);
+    /// ties keep the earlier candidate so region preference still applies.
+    pub fn pick(&self, candidates: &[Candidate]) -> Option<usize> {
+        if *self == SelectionStrategy::FirstFound {
+            return if candidates.is_empty() { None } else { Some(0) };
+        }
+
+        let mut best: Option<usize> = None;
+        for (idx, candidate) in candidates.iter().enumerate() {
+            let better = match best {
+                None => true,
+                Some(current) => self.compare(candidate, &candidates[current]) == Ordering::Greater,
+            };
+            if better {
+                best = Some(idx);
+            }
+        }
+        best
+    }
+
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        let by_pixels = a.pixels().cmp(&b.pixels());
+        match self {
+            SelectionStrategy::FirstFound => Ordering::Equal,
+            SelectionStrategy::BestQuality => by_pixels.then(a.byte_len.cmp(&b.byte_len)),
+            SelectionStrategy::BestSharpness => by_pixels.then(
+                a.sharpness
+                    .unwrap_or(0.0)
+                    .partial_cmp(&b.sharpness.unwrap_or(0.0))
+                    .unwrap_or(Ordering::Equal),
+            ),
+        }
+    }
+}
+
+impl FromStr for SelectionStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        SelectionStrategy::ALL
+            .into_iter()
+            .find(|strategy| strategy.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown selection strategy '{s}' (expected first-found, best-quality or best-sharpness)"
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for SelectionStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Measurements of a downloaded cover used to rank it against others
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate {
+    pub width: u32,
+    pub height: u32,
+    /// Size of the encoded file in bytes
+    pub byte_len: usize,
+    /// Variance of the Laplacian at output size, if measured
+    pub sharpness: Option<f64>,
+}
+
+impl Candidate {
+    /// Measure a decoded image, computing sharpness only when the strategy needs it
+    pub fn measure(img: &DynamicImage, byte_len: usize, strategy: SelectionStrategy) -> Self {
+        Self {
+            width: img.width(),
+            height: img.height(),
+            byte_len,
+            sharpness: (strategy == SelectionStrategy::BestSharpness).then(|| sharpness(img)),
+        }
+    }
+
+    fn pixels(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+}
+
+/// Estimate how sharp an image will look once rendered
+///
+/// Computes the variance of a 4-neighbour Laplacian over the luma of the image
+/// scaled to twice the cover size, so scans of different resolutions compare fairly.
+pub fn sharpness(img: &DynamicImage) -> f64 {
+    let luma = img
+        .resize_exact(
+            COVER_ART_WIDTH * 2,
+            COVER_ART_HEIGHT * 2,
+            FilterType::Triangle,
+        )
+        .to_luma8();
+    let (width, height) = luma.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let at = |x: u32, y: u32| luma.get_pixel(x, y)[0] as f64;
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+    let mut count = 0.0;
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let laplacian =
+                at(x - 1, y) + at(x + 1, y) + at(x, y - 1) + at(x, y + 1) - 4.0 * at(x, y);
+            sum += laplacian;
+            sum_sq += laplacian * laplacian;
+            count += 1.0;
+        }
+    }
+
+    let mean = sum / count;
+    sum_sq / count - mean * mean
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    fn candidate(width: u32, height: u32, byte_len: usize) -> Candidate {
+        Candidate {
+            width,
+            height,
+            byte_len,
+            sharpness: None,
+        }
+    }
+
+    #[test]
+    fn test_first_found_picks_first() {
+        let candidates = [candidate(160, 144, 10), candidate(1000, 900, 500)];
+        assert_eq!(SelectionStrategy::FirstFound.pick(&candidates), Some(0));
+        assert_eq!(SelectionStrategy::FirstFound.pick(&[]), None);
+    }
+
+    #[test]
+    fn test_best_quality_prefers_resolution_then_size() {
+        let candidates = [
+            candidate(160, 144, 900),
+            candidate(1000, 900, 500),
+            candidate(1000, 900, 700),
+        ];
+        assert_eq!(SelectionStrategy::BestQuality.pick(&candidates), Some(2));
+    }
+
+    #[test]
+    fn test_ties_keep_region_preference() {
+        let candidates = [candidate(500, 450, 100), candidate(500, 450, 100)];
+        assert_eq!(SelectionStrategy::BestQuality.pick(&candidates), Some(0));
+        assert_eq!(SelectionStrategy::BestSharpness.pick(&candidates), Some(0));
+    }
+
+    #[test]
+    fn test_sharpness_detects_detail() {
+        let flat = DynamicImage::ImageRgba8(RgbaImage::from_pixel(64, 64, Rgba([90, 90, 90, 255])));
+        let checker = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |x, y| {
+            let v = if (x / 2 + y / 2) % 2 == 0 { 0 } else { 255 };
+            Rgba([v, v, v, 255])
+        }));
+        assert!(sharpness(&checker) > sharpness(&flat) + 100.0);
+    }
+}
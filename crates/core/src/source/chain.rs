@@ -0,0 +1,226 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use super::{CoverRequest, CoverSource, FetchedCover, MissCache};
+use crate::errors::{Error, Result};
+use crate::game::Platform;
+
+/// Tries a list of sources in priority order and returns the first hit
+#[derive(Default)]
+pub struct ChainedSource {
+    sources: Vec<Box<dyn CoverSource>>,
+    misses: Option<Arc<MissCache>>,
+}
+
+impl ChainedSource {
+    /// Create an empty chain
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a source with lower priority than the ones already added
+    pub fn with(mut self, source: impl CoverSource + 'static) -> Self {
+        self.push(Box::new(source));
+        self
+    }
+
+    /// Append an already boxed source
+    pub fn push(&mut self, source: Box<dyn CoverSource>) {
+        self.sources.push(source);
+    }
+
+    /// Skip sources that recently had no cover for a game
+    pub fn with_miss_cache(mut self, misses: Arc<MissCache>) -> Self {
+        self.misses = Some(misses);
+        self
+    }
+
+    /// Get the miss cache, if any
+    pub fn miss_cache(&self) -> Option<&Arc<MissCache>> {
+        self.misses.as_ref()
+    }
+
+    /// Number of sources in the chain
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Whether the chain has no sources
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+}
+
+#[async_trait]
+impl CoverSource for ChainedSource {
+    fn name(&self) -> &str {
+        "chain"
+    }
+
+    fn supports(&self, platform: Platform) -> bool {
+        self.sources.iter().any(|source| source.supports(platform))
+    }
+
+    async fn fetch(&self, request: &CoverRequest) -> Result<Option<FetchedCover>> {
+        let mut last_error = None;
+        let mut any_missed = false;
+
+        for source in &self.sources {
+            if !source.accepts(request) || (request.local_only && !source.is_local()) {
+                continue;
+            }
+
+            // A forced region or art kind may exist where the plain lookup missed
+            let misses = self
+                .misses
+                .as_ref()
+                .filter(|_| source.cache_misses() && !request.wants_specific_art());
+            if let Some(misses) = misses {
+                if misses.is_known_missing(source.name(), request.platform, &request.code) {
+                    any_missed = true;
+                    continue;
+                }
+            }
+
+            match source.fetch(request).await {
+                Ok(Some(cover)) => {
+                    if let Some(misses) = misses {
+                        misses.record_hit(source.name(), request.platform, &request.code);
+                    }
+                    return Ok(Some(cover));
+                }
+                Ok(None) => {
+                    if let Some(misses) = misses {
+                        misses.record_miss(source.name(), request.platform, &request.code);
+                    }
+                    any_missed = true;
+                }
+                Err(err) => {
+                    request.log(format!(
+                        "⚠ {} [{}] - {} failed: {}",
+                        request.game_name,
+                        request.code,
+                        source.name(),
+                        err
+                    ));
+                    if last_error
+                        .as_ref()
+                        .is_none_or(|last: &Error| !last.is_transient())
+                    {
+                        last_error = Some(err);
+                    }
+                }
+            }
+        }
+
+        // A network failure makes any miss inconclusive; other errors are only
+        // reported when no source could give a definite answer
+        match last_error {
+            Some(err) if err.is_transient() || !any_missed => Err(err),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameCode;
+    use crate::source::testing::{png, MockClient};
+    use crate::source::{HttpTemplateSource, ProxySource};
+
+    fn request(platform: Platform) -> CoverRequest {
+        CoverRequest::new(GameCode::from_bytes(b"AXVE").unwrap(), platform)
+    }
+
+    #[test]
+    fn test_chain_falls_through_in_order() {
+        let client = Arc::new(
+            MockClient::default()
+                .with_body("https://mirror.test/gba/AXVE.png", png(8, 8))
+                .with_body("https://proxy.test/gba/AXVE", png(8, 8)),
+        );
+        let chain = ChainedSource::new()
+            .with(HttpTemplateSource::new(
+                client.clone(),
+                "first",
+                "https://first.test/{id}.png",
+            ))
+            .with(HttpTemplateSource::new(
+                client.clone(),
+                "mirror",
+                "https://mirror.test/{platform}/{id}.png",
+            ))
+            .with(ProxySource::new(client.clone(), "https://proxy.test"));
+
+        let cover = futures::executor::block_on(chain.fetch(&request(Platform::Gba)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.source, "mirror");
+        assert_eq!(client.requested().len(), 2);
+    }
+
+    #[test]
+    fn test_chain_skips_unsupported_platforms() {
+        let client =
+            Arc::new(MockClient::default().with_body("https://proxy.test/nds/AXVE", png(8, 8)));
+        let chain = ChainedSource::new().with(
+            ProxySource::new(client.clone(), "https://proxy.test/").for_platforms(&[Platform::Gba]),
+        );
+
+        assert!(chain.supports(Platform::Gba));
+        assert!(!chain.supports(Platform::Nds));
+        let result = futures::executor::block_on(chain.fetch(&request(Platform::Nds))).unwrap();
+        assert!(result.is_none());
+        assert!(client.requested().is_empty());
+    }
+
+    #[test]
+    fn test_chain_reports_error_without_definite_answer() {
+        let client = Arc::new(MockClient::default().with_failure("https://proxy.test/gba/AXVE"));
+        let failing =
+            ChainedSource::new().with(ProxySource::new(client.clone(), "https://proxy.test"));
+        assert!(futures::executor::block_on(failing.fetch(&request(Platform::Gba))).is_err());
+
+        // A network failure is not hidden by a plain miss from another source
+        let mixed = ChainedSource::new()
+            .with(ProxySource::new(client.clone(), "https://proxy.test"))
+            .with(HttpTemplateSource::new(
+                client.clone(),
+                "other",
+                "https://other.test/{id}",
+            ));
+        let err = futures::executor::block_on(mixed.fetch(&request(Platform::Gba))).unwrap_err();
+        assert!(err.is_transient());
+
+        // ...but a permanent failure is
+        let forbidden =
+            Arc::new(MockClient::default().with_status("https://proxy.test/gba/AXVE", 403));
+        let mixed = ChainedSource::new()
+            .with(ProxySource::new(forbidden.clone(), "https://proxy.test"))
+            .with(HttpTemplateSource::new(
+                forbidden,
+                "other",
+                "https://other.test/{id}",
+            ));
+        let result = futures::executor::block_on(mixed.fetch(&request(Platform::Gba))).unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_chain_skips_known_misses() {
+        let client = Arc::new(MockClient::default());
+        let misses = Arc::new(MissCache::new(crate::source::DEFAULT_MISS_TTL));
+        let chain = ChainedSource::new()
+            .with(ProxySource::new(client.clone(), "https://proxy.test"))
+            .with_miss_cache(misses.clone());
+
+        for _ in 0..3 {
+            let result = futures::executor::block_on(chain.fetch(&request(Platform::Gba))).unwrap();
+            assert!(result.is_none());
+        }
+        assert_eq!(client.requested().len(), 1);
+        assert_eq!(misses.len(), 1);
+    }
+}
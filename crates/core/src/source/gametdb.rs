@@ -0,0 +1,455 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use super::{CoverRequest, CoverSource, FetchedCover};
+use crate::art::ArtKind;
+use crate::errors::{Error, FetchErrorKind, Result};
+use crate::game::Platform;
+use crate::http::{HttpClient, HttpRequest};
+use crate::selection::{Candidate, SelectionStrategy};
+
+/// Region codes tried by default, in order
+pub const DEFAULT_REGIONS: [&str; 10] =
+    ["EN", "US", "JA", "EU", "FR", "DE", "IT", "ES", "NL", "PT"];
+
+/// GameTDB URL templates tried by default; `{kind}`, `{region}` and `{id}` are substituted
+pub const DEFAULT_TEMPLATES: [&str; 2] = [
+    "https://art.gametdb.com/ds/{kind}/{region}/{id}.png",
+    "https://art.gametdb.com/ds/{kind}/{region}/{id}.jpg",
+];
+
+/// Walks GameTDB-style URL templates over art kinds and regions
+pub struct GameTdbSource {
+    client: Arc<dyn HttpClient>,
+    regions: Vec<String>,
+    templates: Vec<String>,
+    art_kinds: Vec<ArtKind>,
+    selection: SelectionStrategy,
+    parallel_probes: bool,
+    platforms: Vec<Platform>,
+}
+
+impl GameTdbSource {
+    /// Create a source with the default regions and templates (NDS only)
+    pub fn new(client: Arc<dyn HttpClient>) -> Self {
+        Self {
+            client,
+            regions: DEFAULT_REGIONS.iter().map(|r| r.to_string()).collect(),
+            templates: DEFAULT_TEMPLATES.iter().map(|t| t.to_string()).collect(),
+            art_kinds: vec![ArtKind::Cover],
+            selection: SelectionStrategy::FirstFound,
+            parallel_probes: false,
+            platforms: vec![Platform::Nds],
+        }
+    }
+
+    /// Set the region codes to try (order matters)
+    pub fn with_regions(mut self, regions: Vec<String>) -> Self {
+        self.regions = regions;
+        self
+    }
+
+    /// Set the URL templates to try for each region
+    pub fn with_templates(mut self, templates: Vec<String>) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    /// Set the art kinds to try (order matters)
+    pub fn with_art_kinds(mut self, art_kinds: Vec<ArtKind>) -> Self {
+        self.art_kinds = art_kinds;
+        self
+    }
+
+    /// Set how to choose between candidates found in several regions
+    pub fn with_selection(mut self, selection: SelectionStrategy) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Probe all candidates concurrently with HEAD requests before downloading
+    ///
+    /// Only candidates that exist are downloaded, in preference order. With
+    /// first-found selection the remaining probes are dropped as soon as the
+    /// most preferred existing candidate is known.
+    pub fn with_parallel_probes(mut self, parallel_probes: bool) -> Self {
+        self.parallel_probes = parallel_probes;
+        self
+    }
+
+    /// Restrict the source to the given platforms
+    pub fn for_platforms(mut self, platforms: &[Platform]) -> Self {
+        self.platforms = platforms.to_vec();
+        self
+    }
+
+    /// Candidate URLs for one art kind, in preference order (region, then template)
+    fn candidate_urls(&self, art_kind: ArtKind, request: &CoverRequest) -> Vec<(String, String)> {
+        let mut urls = Vec::new();
+        let regions = match &request.region {
+            Some(region) => std::slice::from_ref(region),
+            None => self.regions.as_slice(),
+        };
+        for region in regions {
+            for template in &self.templates {
+                let Some(template) = art_kind.expand_template(template) else {
+                    continue;
+                };
+                let url =
+                    super::expand_placeholders(&template, request).replace("{region}", region);
+                urls.push((region.clone(), url));
+            }
+        }
+        urls
+    }
+
+    /// Download and decode one candidate
+    ///
+    /// `Ok(None)` is a plain 404; every other failure is returned classified so
+    /// the caller can tell "no art" apart from "could not check".
+    async fn download(&self, url: &str) -> Result<Option<FetchedCover>> {
+        let response = self.client.send(HttpRequest::get(url)).await?;
+        match FetchErrorKind::from_status(response.status) {
+            None => FetchedCover::from_response(response, self.name(), url).map(Some),
+            Some(FetchErrorKind::NotFound) => Ok(None),
+            Some(kind) => Err(Error::http_status(kind, url, response.status)),
+        }
+    }
+}
+
+impl GameTdbSource {
+    /// Check whether a candidate exists without downloading it
+    ///
+    /// Falls back to a one-byte ranged GET when the server rejects HEAD.
+    async fn probe(&self, url: &str) -> Result<bool> {
+        let mut response = self.client.send(HttpRequest::head(url)).await?;
+        if matches!(response.status, 405 | 501) {
+            response = self.client.send(HttpRequest::first_byte(url)).await?;
+        }
+        match FetchErrorKind::from_status(response.status) {
+            None => Ok(true),
+            Some(FetchErrorKind::NotFound) => Ok(false),
+            Some(kind) => Err(Error::http_status(kind, url, response.status)),
+        }
+    }
+
+    /// Probe every URL concurrently and return the outcome for each
+    ///
+    /// With `first_only`, returns as soon as every URL before the first hit
+    /// has been ruled out; outstanding probes are cancelled by dropping them
+    /// and stay [`Probe::Pending`].
+    async fn probe_candidates(
+        &self,
+        urls: &[(String, String)],
+        first_only: bool,
+        network_error: &mut Option<Error>,
+    ) -> Vec<Probe> {
+        let mut probes: FuturesUnordered<_> = urls
+            .iter()
+            .enumerate()
+            .map(|(idx, (_, url))| async move { (idx, self.probe(url).await) })
+            .collect();
+        let mut outcomes = vec![Probe::Pending; urls.len()];
+
+        while let Some((idx, outcome)) = probes.next().await {
+            outcomes[idx] = match outcome {
+                Ok(true) => Probe::Found,
+                Ok(false) => Probe::Missing,
+                Err(err) => {
+                    if err.is_transient() {
+                        *network_error = Some(err);
+                    }
+                    Probe::Failed
+                }
+            };
+
+            if first_only {
+                let first = outcomes
+                    .iter()
+                    .position(|outcome| matches!(outcome, Probe::Pending | Probe::Found));
+                match first {
+                    Some(first) if outcomes[first] == Probe::Found => break,
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+        }
+        outcomes
+    }
+}
+
+/// Outcome of probing one candidate URL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Probe {
+    /// Not answered before the probes were cancelled
+    Pending,
+    Found,
+    /// The server has no such file
+    Missing,
+    /// The probe could not tell
+    Failed,
+}
+
+#[async_trait]
+impl CoverSource for GameTdbSource {
+    fn name(&self) -> &str {
+        "gametdb"
+    }
+
+    fn supports(&self, platform: Platform) -> bool {
+        self.platforms.contains(&platform)
+    }
+
+    async fn fetch(&self, request: &CoverRequest) -> Result<Option<FetchedCover>> {
+        let strategy = self.selection;
+        let mut network_error = None;
+        let art_kinds = match &request.art_kind {
+            Some(art_kind) => std::slice::from_ref(art_kind),
+            None => self.art_kinds.as_slice(),
+        };
+        for &art_kind in art_kinds {
+            let mut candidates: Vec<(Candidate, FetchedCover)> = Vec::new();
+            let mut checked_region = None;
+            let urls = self.candidate_urls(art_kind, request);
+
+            let order: Vec<usize> = if self.parallel_probes {
+                request.log(format!(
+                    "🔍 Probing {} [{}] - {} candidates ({})",
+                    request.game_name,
+                    request.code,
+                    urls.len(),
+                    art_kind
+                ));
+                let first_only = !strategy.probes_all();
+                let outcomes = self
+                    .probe_candidates(&urls, first_only, &mut network_error)
+                    .await;
+                let hits: Vec<usize> = (0..urls.len())
+                    .filter(|&idx| outcomes[idx] == Probe::Found)
+                    .collect();
+                match hits.first() {
+                    // If the winner fails to download, fall back to the rest
+                    // that weren't ruled out
+                    Some(&winner) if first_only => (winner..urls.len())
+                        .filter(|&idx| outcomes[idx] != Probe::Missing)
+                        .collect(),
+                    _ => hits,
+                }
+            } else {
+                (0..urls.len()).collect()
+            };
+
+            for (region, url) in order.into_iter().map(|idx| urls[idx].clone()) {
+                if !self.parallel_probes && checked_region.as_ref() != Some(&region) {
+                    request.log(format!(
+                        "🔍 Checking {} [{}] - {} ({})",
+                        request.game_name, request.code, region, art_kind
+                    ));
+                    checked_region = Some(region.clone());
+                }
+
+                let cover = match self.download(&url).await {
+                    Ok(Some(cover)) => cover,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        // Other URLs may still work; only transient failures
+                        // make a miss inconclusive
+                        if err.is_transient() {
+                            network_error = Some(err);
+                        }
+                        continue;
+                    }
+                };
+                let cover = cover.with_region(region.clone()).with_art_kind(art_kind);
+
+                if !strategy.probes_all() {
+                    request.log(format!(
+                        "✅ Found {} [{}] - {} ({})",
+                        request.game_name, request.code, region, art_kind
+                    ));
+                    return Ok(Some(cover));
+                }
+                let candidate = Candidate::measure(&cover.image, cover.bytes.len(), strategy);
+                candidates.push((candidate, cover));
+            }
+
+            let total = candidates.len();
+            let measured: Vec<Candidate> = candidates.iter().map(|(c, _)| *c).collect();
+            if let Some(best) = strategy.pick(&measured) {
+                let (candidate, cover) = candidates.swap_remove(best);
+                request.log(format!(
+                    "✅ Found {} [{}] - {} ({}, {}×{}, best of {})",
+                    request.game_name,
+                    request.code,
+                    cover.region.as_deref().unwrap_or("?"),
+                    art_kind,
+                    candidate.width,
+                    candidate.height,
+                    total
+                ));
+                return Ok(Some(cover));
+            }
+        }
+
+        match network_error {
+            Some(err) => Err(err),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameCode;
+    use crate::source::testing::{png, MockClient};
+
+    fn request() -> CoverRequest {
+        CoverRequest::new(GameCode::from_bytes(b"ABCE").unwrap(), Platform::Nds)
+    }
+
+    #[test]
+    fn test_first_found_stops_at_first_region() {
+        let client = Arc::new(
+            MockClient::default()
+                .with_body(
+                    "https://art.gametdb.com/ds/cover/US/ABCE.jpg",
+                    png(160, 144),
+                )
+                .with_body(
+                    "https://art.gametdb.com/ds/cover/EU/ABCE.png",
+                    png(500, 450),
+                ),
+        );
+        let source = GameTdbSource::new(client.clone());
+
+        let cover = futures::executor::block_on(source.fetch(&request()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.region.as_deref(), Some("US"));
+        assert_eq!(cover.image.width(), 160);
+        assert_eq!(
+            client.requested().last().unwrap(),
+            "https://art.gametdb.com/ds/cover/US/ABCE.jpg"
+        );
+    }
+
+    #[test]
+    fn test_best_quality_probes_all_regions() {
+        let client = Arc::new(
+            MockClient::default()
+                .with_body(
+                    "https://art.gametdb.com/ds/cover/EN/ABCE.png",
+                    png(160, 144),
+                )
+                .with_body(
+                    "https://art.gametdb.com/ds/cover/US/ABCE.png",
+                    png(500, 450),
+                ),
+        );
+        let source =
+            GameTdbSource::new(client.clone()).with_selection(SelectionStrategy::BestQuality);
+
+        let cover = futures::executor::block_on(source.fetch(&request()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.region.as_deref(), Some("US"));
+        assert_eq!(client.requested().len(), DEFAULT_REGIONS.len() * 2);
+    }
+
+    #[test]
+    fn test_art_kinds_in_preference_order() {
+        let client = Arc::new(
+            MockClient::default()
+                .with_body("https://art.gametdb.com/ds/cover/EN/ABCE.png", png(10, 10))
+                .with_body("https://art.gametdb.com/ds/cart/JA/ABCE.png", png(10, 10)),
+        );
+        let source = GameTdbSource::new(client).with_art_kinds(vec![ArtKind::Cart, ArtKind::Cover]);
+
+        let cover = futures::executor::block_on(source.fetch(&request()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.art_kind, ArtKind::Cart);
+        assert_eq!(cover.origin, "https://art.gametdb.com/ds/cart/JA/ABCE.png");
+    }
+
+    #[test]
+    fn test_network_failure_is_not_reported_as_missing() {
+        let flaky = Arc::new(
+            MockClient::default().with_failure("https://art.gametdb.com/ds/cover/JA/ABCE.png"),
+        );
+        let err =
+            futures::executor::block_on(GameTdbSource::new(flaky).fetch(&request())).unwrap_err();
+        assert!(err.is_transient());
+
+        let broken = Arc::new(MockClient::default().with_body(
+            "https://art.gametdb.com/ds/cover/EN/ABCE.png",
+            b"<html>".to_vec(),
+        ));
+        let result =
+            futures::executor::block_on(GameTdbSource::new(broken).fetch(&request())).unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_parallel_probes_download_only_the_winner() {
+        let client = Arc::new(
+            MockClient::default()
+                .with_body(
+                    "https://art.gametdb.com/ds/cover/PT/ABCE.png",
+                    png(160, 144),
+                )
+                .with_body(
+                    "https://art.gametdb.com/ds/cover/NL/ABCE.jpg",
+                    png(160, 144),
+                ),
+        );
+        let source = GameTdbSource::new(client.clone()).with_parallel_probes(true);
+
+        let cover = futures::executor::block_on(source.fetch(&request()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.region.as_deref(), Some("NL"));
+        let downloads: Vec<_> = client
+            .requested()
+            .into_iter()
+            .filter(|url| !url.starts_with("HEAD "))
+            .collect();
+        assert_eq!(downloads, ["https://art.gametdb.com/ds/cover/NL/ABCE.jpg"]);
+    }
+
+    #[test]
+    fn test_parallel_fallback_skips_missing_candidates() {
+        let client = Arc::new(
+            MockClient::default()
+                .with_body(
+                    "https://art.gametdb.com/ds/cover/NL/ABCE.jpg",
+                    b"<html>".to_vec(),
+                )
+                .with_body(
+                    "https://art.gametdb.com/ds/cover/PT/ABCE.png",
+                    png(160, 144),
+                ),
+        );
+        let source = GameTdbSource::new(client.clone()).with_parallel_probes(true);
+
+        let cover = futures::executor::block_on(source.fetch(&request()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.region.as_deref(), Some("PT"));
+        let downloads: Vec<_> = client
+            .requested()
+            .into_iter()
+            .filter(|url| !url.starts_with("HEAD "))
+            .collect();
+        assert_eq!(
+            downloads,
+            [
+                "https://art.gametdb.com/ds/cover/NL/ABCE.jpg",
+                "https://art.gametdb.com/ds/cover/PT/ABCE.png"
+            ]
+        );
+    }
+}
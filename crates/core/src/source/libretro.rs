@@ -0,0 +1,260 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use super::{percent_encode, CoverRequest, CoverSource, FetchedCover};
+use crate::errors::{Error, FetchErrorKind, Result};
+use crate::game::Platform;
+use crate::http::{HttpClient, HttpRequest};
+
+/// Public libretro thumbnail server, laid out as `<system>/Named_Boxarts/<title>.png`
+pub const DEFAULT_LIBRETRO_URL: &str = "https://thumbnails.libretro.com/";
+
+/// Characters libretro replaces with `_` in thumbnail file names
+const RESERVED_CHARS: [char; 11] = ['&', '*', '/', ':', '`', '<', '>', '?', '\\', '|', '"'];
+
+/// Where the thumbnails live
+enum Location {
+    Remote {
+        client: Arc<dyn HttpClient>,
+        base_url: String,
+    },
+    Local(PathBuf),
+}
+
+/// Fetches boxart from libretro-thumbnails by canonical (No-Intro) title
+///
+/// The title comes from the request, usually a DAT or title database entry,
+/// and falls back to the ROM file stem, which is already the No-Intro name
+/// for most dumps.
+pub struct LibretroSource {
+    location: Location,
+    platforms: Vec<Platform>,
+}
+
+impl LibretroSource {
+    /// Create a source for a thumbnail server or raw repository URL at `base_url`
+    pub fn remote(client: Arc<dyn HttpClient>, base_url: impl Into<String>) -> Self {
+        let mut base_url = base_url.into();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        Self {
+            location: Location::Remote { client, base_url },
+            platforms: Platform::ALL.to_vec(),
+        }
+    }
+
+    /// Create a source for a local checkout of one or more thumbnail repositories
+    ///
+    /// `dir` may hold `<system>/Named_Boxarts`, `<repository>/Named_Boxarts`
+    /// or be a single repository with `Named_Boxarts` at its root.
+    pub fn local(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            location: Location::Local(dir.into()),
+            platforms: Platform::ALL.to_vec(),
+        }
+    }
+
+    /// Restrict the source to the given platforms
+    pub fn for_platforms(mut self, platforms: &[Platform]) -> Self {
+        self.platforms = platforms.to_vec();
+        self
+    }
+
+    /// Titles to try for a request, in order
+    fn titles(request: &CoverRequest) -> Vec<String> {
+        let stem = request
+            .rom_path
+            .as_deref()
+            .and_then(|path| path.file_stem())
+            .and_then(|stem| stem.to_str());
+        let mut titles: Vec<String> = Vec::new();
+        for title in request.title.as_deref().into_iter().chain(stem) {
+            let file_name = thumbnail_name(title);
+            if !file_name.is_empty() && !titles.contains(&file_name) {
+                titles.push(file_name);
+            }
+        }
+        titles
+    }
+
+    async fn fetch_remote(
+        &self,
+        client: &Arc<dyn HttpClient>,
+        base_url: &str,
+        request: &CoverRequest,
+    ) -> Result<Option<FetchedCover>> {
+        for title in Self::titles(request) {
+            let url = format!(
+                "{}{}/Named_Boxarts/{}.png",
+                base_url,
+                percent_encode(system_name(request.platform)),
+                percent_encode(&title)
+            );
+            let response = client.send(HttpRequest::get(&url)).await?;
+            match FetchErrorKind::from_status(response.status) {
+                None => return FetchedCover::from_response(response, self.name(), url).map(Some),
+                Some(FetchErrorKind::NotFound) => continue,
+                Some(kind) => return Err(Error::http_status(kind, &url, response.status)),
+            }
+        }
+        Ok(None)
+    }
+
+    fn fetch_local(&self, dir: &Path, request: &CoverRequest) -> Result<Option<FetchedCover>> {
+        let folders = [
+            dir.join(system_name(request.platform)),
+            dir.join(repository_name(request.platform)),
+            dir.to_path_buf(),
+        ];
+        for title in Self::titles(request) {
+            let file_name = format!("{}.png", title);
+            let found = folders
+                .iter()
+                .map(|folder| folder.join("Named_Boxarts").join(&file_name))
+                .find(|path| path.is_file());
+            if let Some(path) = found {
+                let bytes = std::fs::read(&path)?;
+                return FetchedCover::decode(bytes, self.name(), path.display().to_string())
+                    .map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// libretro system name, used for server folders
+fn system_name(platform: Platform) -> &'static str {
+    match platform {
+        Platform::Nds => "Nintendo - Nintendo DS",
+        Platform::Gba => "Nintendo - Game Boy Advance",
+    }
+}
+
+/// libretro-thumbnails repository name, used for checkouts
+fn repository_name(platform: Platform) -> &'static str {
+    match platform {
+        Platform::Nds => "Nintendo_-_Nintendo_DS",
+        Platform::Gba => "Nintendo_-_Game_Boy_Advance",
+    }
+}
+
+/// Apply libretro's file name rules to a title
+///
+/// `Tom & Jerry: The Magic Ring` becomes `Tom _ Jerry_ The Magic Ring`.
+pub fn thumbnail_name(title: &str) -> String {
+    title
+        .trim()
+        .chars()
+        .map(|c| if RESERVED_CHARS.contains(&c) { '_' } else { c })
+        .collect()
+}
+
+#[async_trait]
+impl CoverSource for LibretroSource {
+    fn name(&self) -> &str {
+        "libretro"
+    }
+
+    fn supports(&self, platform: Platform) -> bool {
+        self.platforms.contains(&platform)
+    }
+
+    fn accepts(&self, request: &CoverRequest) -> bool {
+        // Only front boxart, with no region choice beyond the title itself
+        self.supports(request.platform) && !request.wants_specific_art()
+    }
+
+    fn is_local(&self) -> bool {
+        matches!(self.location, Location::Local(_))
+    }
+
+    fn cache_misses(&self) -> bool {
+        // Misses are remembered by game code, but the lookup goes by title, and
+        // a retitled ROM or new title database must get a fresh look
+        false
+    }
+
+    async fn fetch(&self, request: &CoverRequest) -> Result<Option<FetchedCover>> {
+        let found = match &self.location {
+            Location::Remote { client, base_url } => {
+                self.fetch_remote(client, base_url, request).await?
+            }
+            Location::Local(dir) => self.fetch_local(dir, request)?,
+        };
+        if let Some(cover) = &found {
+            request.log(format!(
+                "✅ Found {} [{}] - libretro ({})",
+                request.game_name, request.code, cover.origin
+            ));
+        }
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameCode;
+    use crate::source::testing::{png, MockClient};
+
+    fn request() -> CoverRequest {
+        CoverRequest::new(GameCode::from_bytes(b"BPEE").unwrap(), Platform::Gba)
+            .with_rom_path("/sd/gba/Pokemon - Emerald Version (USA, Europe).gba")
+    }
+
+    #[test]
+    fn test_thumbnail_name() {
+        assert_eq!(
+            thumbnail_name("Tom & Jerry: The Magic Ring (USA)"),
+            "Tom _ Jerry_ The Magic Ring (USA)"
+        );
+        assert_eq!(thumbnail_name("What?/Why*"), "What__Why_");
+    }
+
+    #[test]
+    fn test_remote_tries_title_then_file_name() {
+        let client = Arc::new(MockClient::default().with_body(
+            "https://thumbs.test/Nintendo%20-%20Game%20Boy%20Advance/Named_Boxarts/Pokemon%20-%20Emerald%20Version%20%28USA%2C%20Europe%29.png",
+            png(64, 64),
+        ));
+        let source = LibretroSource::remote(client.clone(), "https://thumbs.test");
+
+        let request = request().with_title("Pokémon Emerald");
+        let cover = futures::executor::block_on(source.fetch(&request))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.source, "libretro");
+        assert_eq!(client.requested().len(), 2);
+
+        let homebrew = CoverRequest::new(GameCode::from_bytes(b"ZZZE").unwrap(), Platform::Gba);
+        assert!(futures::executor::block_on(source.fetch(&homebrew))
+            .unwrap()
+            .is_none());
+        assert!(!source.cache_misses());
+    }
+
+    #[test]
+    fn test_local_checkout_layouts() {
+        let dir = tempfile::tempdir().unwrap();
+        let boxarts = dir
+            .path()
+            .join("Nintendo_-_Game_Boy_Advance")
+            .join("Named_Boxarts");
+        std::fs::create_dir_all(&boxarts).unwrap();
+        std::fs::write(
+            boxarts.join("Pokemon - Emerald Version (USA, Europe).png"),
+            png(32, 32),
+        )
+        .unwrap();
+        let source = LibretroSource::local(dir.path());
+
+        let cover = futures::executor::block_on(source.fetch(&request()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.image.width(), 32);
+        assert!(!source.cache_misses());
+    }
+}
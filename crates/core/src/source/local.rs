@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use async_trait::async_trait;
+
+use super::{CoverRequest, CoverSource, FetchedCover};
+use crate::errors::Result;
+use crate::game::Platform;
+
+/// Image extensions looked up in local folders, in order
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "bmp"];
+
+/// Reads hand-picked covers from a folder on disk
+///
+/// Files are matched in this order:
+/// 1. `<CODE>.<ext>`
+/// 2. `<CODE>/front.<ext>`
+/// 3. the ROM file stem, e.g. `Mario Kart DS (USA).png`
+/// 4. the ROM file stem after [`normalize_title`], so `mario-kart-ds.jpg` also matches
+pub struct LocalFolderSource {
+    dir: PathBuf,
+    platforms: Vec<Platform>,
+    titles: OnceLock<HashMap<String, PathBuf>>,
+}
+
+impl LocalFolderSource {
+    /// Create a source for the folder at `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            platforms: Platform::ALL.to_vec(),
+            titles: OnceLock::new(),
+        }
+    }
+
+    /// Restrict the source to the given platforms
+    pub fn for_platforms(mut self, platforms: &[Platform]) -> Self {
+        self.platforms = platforms.to_vec();
+        self
+    }
+
+    /// Get the folder this source reads from
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn find(&self, request: &CoverRequest) -> Option<PathBuf> {
+        let code = request.code.as_str();
+        if let Some(path) = find_image(&self.dir, code) {
+            return Some(path);
+        }
+        if let Some(path) = find_image(&self.dir.join(code), "front") {
+            return Some(path);
+        }
+
+        let stem = request.rom_path.as_deref()?.file_stem()?.to_str()?;
+        if let Some(path) = find_image(&self.dir, stem) {
+            return Some(path);
+        }
+        self.titles()
+            .get(&normalize_title(stem))
+            .filter(|path| path.is_file())
+            .cloned()
+    }
+
+    /// Index of normalised file stems, built on first use
+    fn titles(&self) -> &HashMap<String, PathBuf> {
+        self.titles.get_or_init(|| {
+            let mut titles = HashMap::new();
+            let Ok(entries) = std::fs::read_dir(&self.dir) else {
+                return titles;
+            };
+            let mut paths: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.is_file() && is_image(path))
+                .collect();
+            // Sort so the extension preference order decides between duplicates
+            paths.sort_by_key(|path| extension_rank(path));
+            for path in paths {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    titles.entry(normalize_title(stem)).or_insert(path);
+                }
+            }
+            titles
+        })
+    }
+}
+
+/// Normalise a title for fuzzy file name matching
+///
+/// Drops bracketed tags such as `(USA)` or `[!]`, lowercases, folds common
+/// accents and keeps only letters and digits, so `Mario Kart DS (USA) [!]` and
+/// `mario_kart-ds` compare equal, as do `Pokémon` and `Pokemon`.
+pub fn normalize_title(title: &str) -> String {
+    let mut normalized = String::with_capacity(title.len());
+    let mut depth = 0usize;
+    for c in title.chars() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && c.is_alphanumeric() => {
+                normalized.extend(c.to_lowercase().map(fold_accent))
+            }
+            _ => {}
+        }
+    }
+    normalized
+}
+
+fn fold_accent(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+        'ç' => 'c',
+        'è' | 'é' | 'ê' | 'ë' => 'e',
+        'ì' | 'í' | 'î' | 'ï' => 'i',
+        'ñ' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'ý' | 'ÿ' => 'y',
+        _ => c,
+    }
+}
+
+fn find_image(dir: &Path, stem: &str) -> Option<PathBuf> {
+    IMAGE_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", stem, ext)))
+        .find(|path| path.is_file())
+}
+
+fn extension_rank(path: &Path) -> usize {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(|ext| {
+            IMAGE_EXTENSIONS
+                .iter()
+                .position(|known| known.eq_ignore_ascii_case(ext))
+        })
+        .unwrap_or(IMAGE_EXTENSIONS.len())
+}
+
+fn is_image(path: &Path) -> bool {
+    extension_rank(path) < IMAGE_EXTENSIONS.len()
+}
+
+#[async_trait]
+impl CoverSource for LocalFolderSource {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    fn supports(&self, platform: Platform) -> bool {
+        self.platforms.contains(&platform)
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+
+    fn cache_misses(&self) -> bool {
+        false
+    }
+
+    async fn fetch(&self, request: &CoverRequest) -> Result<Option<FetchedCover>> {
+        let Some(path) = self.find(request) else {
+            return Ok(None);
+        };
+
+        let bytes = std::fs::read(&path)?;
+        let cover = FetchedCover::decode(bytes, self.name(), path.display().to_string())?;
+        request.log(format!(
+            "✅ Found {} [{}] - local ({})",
+            request.game_name,
+            request.code,
+            path.display()
+        ));
+        Ok(Some(cover))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameCode;
+    use crate::source::testing::png;
+
+    #[test]
+    fn test_local_folder_matches_code() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("ABCE.png"), png(12, 10)).unwrap();
+        let source = LocalFolderSource::new(dir.path());
+
+        let hit = CoverRequest::new(GameCode::from_bytes(b"ABCE").unwrap(), Platform::Nds);
+        let cover = futures::executor::block_on(source.fetch(&hit))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.source, "local");
+        assert_eq!(cover.image.width(), 12);
+
+        let miss = CoverRequest::new(GameCode::from_bytes(b"ZZZE").unwrap(), Platform::Nds);
+        assert!(futures::executor::block_on(source.fetch(&miss))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_local_folder_matches_front_and_rom_name() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("ABCE")).unwrap();
+        std::fs::write(dir.path().join("ABCE/front.jpg"), png(8, 8)).unwrap();
+        std::fs::write(dir.path().join("mario_kart-ds.png"), png(6, 6)).unwrap();
+        let source = LocalFolderSource::new(dir.path());
+
+        let front = CoverRequest::new(GameCode::from_bytes(b"ABCE").unwrap(), Platform::Nds);
+        let cover = futures::executor::block_on(source.fetch(&front))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.image.width(), 8);
+
+        let by_name = CoverRequest::new(GameCode::from_bytes(b"AMCE").unwrap(), Platform::Nds)
+            .with_rom_path("roms/Mario Kart DS (USA) [!].nds");
+        let cover = futures::executor::block_on(source.fetch(&by_name))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.image.width(), 6);
+    }
+
+    #[test]
+    fn test_normalize_title() {
+        assert_eq!(normalize_title("Mario Kart DS (USA) [!]"), "mariokartds");
+        assert_eq!(normalize_title("mario_kart-ds"), "mariokartds");
+        assert_eq!(normalize_title("Pokémon Black"), "pokemonblack");
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::errors::{Error, Result};
+use crate::game::{GameCode, Platform};
+
+/// Default time a "not found" result is trusted (7 days)
+pub const DEFAULT_MISS_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Remembers which sources had no cover for a game
+///
+/// Entries are keyed by source name, platform and game code, and expire after
+/// the TTL so newly uploaded art is eventually picked up.
+pub struct MissCache {
+    path: Option<PathBuf>,
+    ttl: Duration,
+    recheck: bool,
+    state: Mutex<MissState>,
+}
+
+#[derive(Default)]
+struct MissState {
+    /// Key → unix time of the miss
+    misses: HashMap<String, u64>,
+    dirty: bool,
+}
+
+impl MissCache {
+    /// Create an in-memory cache
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            path: None,
+            ttl,
+            recheck: false,
+            state: Mutex::new(MissState::default()),
+        }
+    }
+
+    /// Load a cache persisted at `path`; a missing or corrupt file starts empty
+    pub fn load(path: impl Into<PathBuf>, ttl: Duration) -> Self {
+        let path = path.into();
+        let misses = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            ttl,
+            recheck: false,
+            state: Mutex::new(MissState {
+                misses,
+                dirty: false,
+            }),
+        }
+    }
+
+    /// Ignore remembered misses for lookups, while still recording new results
+    pub fn with_recheck(mut self, recheck: bool) -> Self {
+        self.recheck = recheck;
+        self
+    }
+
+    /// Get the file the cache is persisted to, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Whether the source is known to have no cover for the game
+    pub fn is_known_missing(&self, source: &str, platform: Platform, code: &GameCode) -> bool {
+        if self.recheck {
+            return false;
+        }
+        let state = self.state.lock().unwrap();
+        state
+            .misses
+            .get(&key(source, platform, code))
+            .is_some_and(|&missed_at| unix_now().saturating_sub(missed_at) < self.ttl.as_secs())
+    }
+
+    /// Remember that the source had no cover for the game
+    pub fn record_miss(&self, source: &str, platform: Platform, code: &GameCode) {
+        let mut state = self.state.lock().unwrap();
+        state.misses.insert(key(source, platform, code), unix_now());
+        state.dirty = true;
+    }
+
+    /// Forget a remembered miss after the source returned a cover
+    pub fn record_hit(&self, source: &str, platform: Platform, code: &GameCode) {
+        let mut state = self.state.lock().unwrap();
+        if state.misses.remove(&key(source, platform, code)).is_some() {
+            state.dirty = true;
+        }
+    }
+
+    /// Number of remembered misses, including expired ones
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().misses.len()
+    }
+
+    /// Whether no misses are remembered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write the cache back to its file, dropping expired entries
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut state = self.state.lock().unwrap();
+        if !state.dirty {
+            return Ok(());
+        }
+
+        let now = unix_now();
+        let ttl = self.ttl.as_secs();
+        state
+            .misses
+            .retain(|_, missed_at| now.saturating_sub(*missed_at) < ttl);
+        let json = serde_json::to_vec(&state.misses).map_err(|e| Error::Other(e.to_string()))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)?;
+        state.dirty = false;
+        Ok(())
+    }
+}
+
+fn key(source: &str, platform: Platform, code: &GameCode) -> String {
+    format!("{}/{}/{}", source, platform.as_str(), code)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_misses_persist_and_expire() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("misses.json");
+        let code = GameCode::from_bytes(b"ABCE").unwrap();
+
+        let cache = MissCache::load(&path, DEFAULT_MISS_TTL);
+        cache.record_miss("gametdb", Platform::Nds, &code);
+        cache.save().unwrap();
+
+        let reloaded = MissCache::load(&path, DEFAULT_MISS_TTL);
+        assert!(reloaded.is_known_missing("gametdb", Platform::Nds, &code));
+        assert!(!reloaded.is_known_missing("proxy", Platform::Nds, &code));
+        assert!(!MissCache::load(&path, DEFAULT_MISS_TTL)
+            .with_recheck(true)
+            .is_known_missing("gametdb", Platform::Nds, &code));
+        assert!(!MissCache::load(&path, Duration::ZERO).is_known_missing(
+            "gametdb",
+            Platform::Nds,
+            &code
+        ));
+
+        reloaded.record_hit("gametdb", Platform::Nds, &code);
+        assert!(reloaded.is_empty());
+    }
+}
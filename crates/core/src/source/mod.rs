@@ -0,0 +1,352 @@
+mod chain;
+mod gametdb;
+mod libretro;
+mod local;
+mod misses;
+mod overrides;
+mod proxy;
+#[cfg(feature = "native")]
+mod screenscraper;
+mod template;
+
+pub use chain::ChainedSource;
+pub use gametdb::{GameTdbSource, DEFAULT_REGIONS, DEFAULT_TEMPLATES};
+pub use libretro::{thumbnail_name, LibretroSource, DEFAULT_LIBRETRO_URL};
+pub use local::{normalize_title, LocalFolderSource};
+pub use misses::{MissCache, DEFAULT_MISS_TTL};
+pub use overrides::{Override, OverrideSource, Overrides};
+pub use proxy::{ProxySource, DEFAULT_PROXY_URL};
+#[cfg(feature = "native")]
+pub use screenscraper::{ScreenScraperLogin, ScreenScraperSource, SCREENSCRAPER_API_URL};
+pub use template::HttpTemplateSource;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use image::DynamicImage;
+
+use crate::art::ArtKind;
+use crate::errors::{Error, FetchErrorKind, Result};
+use crate::game::{GameCode, Platform};
+use crate::http::HttpResponse;
+
+/// Callback receiving human readable progress lines
+pub type Logger = Arc<dyn Fn(String) + Send + Sync>;
+
+/// A lookup for one game's cover
+#[derive(Clone)]
+pub struct CoverRequest {
+    pub code: GameCode,
+    pub platform: Platform,
+    /// Display name used in log lines (usually the ROM file stem)
+    pub game_name: String,
+    /// ROM file the code was read from, when known
+    pub rom_path: Option<PathBuf>,
+    /// Canonical title from a DAT or title database, for sources keyed by title
+    pub title: Option<String>,
+    /// Only accept art from this region (set by overrides)
+    pub region: Option<String>,
+    /// Only accept this kind of art (set by overrides)
+    pub art_kind: Option<ArtKind>,
+    /// Only ask sources that read local files (set for codes no database knows)
+    pub local_only: bool,
+    pub log: Option<Logger>,
+}
+
+impl CoverRequest {
+    /// Create a request for a game code; the code doubles as the display name
+    pub fn new(code: GameCode, platform: Platform) -> Self {
+        Self {
+            game_name: code.to_string(),
+            code,
+            platform,
+            rom_path: None,
+            title: None,
+            region: None,
+            art_kind: None,
+            local_only: false,
+            log: None,
+        }
+    }
+
+    /// Set the name shown in log lines
+    pub fn with_game_name(mut self, name: impl Into<String>) -> Self {
+        self.game_name = name.into();
+        self
+    }
+
+    /// Set the ROM file the request was made for
+    pub fn with_rom_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.rom_path = Some(path.into());
+        self
+    }
+
+    /// Set the canonical title of the game
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Restrict the lookup to one region's art
+    pub fn with_region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    /// Restrict the lookup to one kind of art
+    pub fn with_art_kind(mut self, art_kind: ArtKind) -> Self {
+        self.art_kind = Some(art_kind);
+        self
+    }
+
+    /// Skip sources that would go to the network
+    pub fn with_local_only(mut self) -> Self {
+        self.local_only = true;
+        self
+    }
+
+    /// Whether a specific region or non-cover art was asked for
+    pub fn wants_specific_art(&self) -> bool {
+        self.region.is_some() || self.art_kind.is_some_and(|kind| kind != ArtKind::Cover)
+    }
+
+    /// Set the progress callback
+    pub fn with_logger(mut self, log: Logger) -> Self {
+        self.log = Some(log);
+        self
+    }
+
+    /// Send a progress line to the logger, if any
+    pub fn log(&self, message: String) {
+        if let Some(log) = &self.log {
+            log(message);
+        }
+    }
+}
+
+/// A decoded cover returned by a source
+#[derive(Debug, Clone)]
+pub struct FetchedCover {
+    pub image: DynamicImage,
+    /// Original encoded bytes (PNG/JPEG/...)
+    pub bytes: Vec<u8>,
+    /// Name of the source that found it
+    pub source: String,
+    /// URL or file path the bytes came from
+    pub origin: String,
+    pub region: Option<String>,
+    pub art_kind: ArtKind,
+    /// Validators of the download, used to check later whether it changed
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl FetchedCover {
+    /// Decode image bytes, failing if they are not a supported image
+    pub fn decode(
+        bytes: Vec<u8>,
+        source: impl Into<String>,
+        origin: impl Into<String>,
+    ) -> Result<Self> {
+        let origin = origin.into();
+        let image = image::load_from_memory(&bytes).map_err(|e| {
+            Error::fetch(FetchErrorKind::InvalidImage, format!("{}: {}", origin, e))
+        })?;
+        Ok(Self {
+            image,
+            bytes,
+            source: source.into(),
+            origin,
+            region: None,
+            art_kind: ArtKind::Cover,
+            etag: None,
+            last_modified: None,
+        })
+    }
+
+    /// Decode a downloaded image, keeping its `ETag` and `Last-Modified`
+    pub fn from_response(
+        response: HttpResponse,
+        source: impl Into<String>,
+        origin: impl Into<String>,
+    ) -> Result<Self> {
+        let etag = response.header("etag").map(str::to_string);
+        let last_modified = response.header("last-modified").map(str::to_string);
+        let mut cover = Self::decode(response.body, source, origin)?;
+        cover.etag = etag;
+        cover.last_modified = last_modified;
+        Ok(cover)
+    }
+
+    /// Set the region the cover belongs to
+    pub fn with_region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    /// Set the kind of art
+    pub fn with_art_kind(mut self, art_kind: ArtKind) -> Self {
+        self.art_kind = art_kind;
+        self
+    }
+}
+
+/// Somewhere cover art can be looked up
+#[async_trait]
+pub trait CoverSource: Send + Sync {
+    /// Short name used in logs and results (e.g. "gametdb", "proxy")
+    fn name(&self) -> &str;
+
+    /// Whether this source can serve covers for the platform
+    fn supports(&self, platform: Platform) -> bool;
+
+    /// Whether this source should be asked for this request
+    ///
+    /// Sources that can't honour a forced region or art kind step aside.
+    fn accepts(&self, request: &CoverRequest) -> bool {
+        self.supports(request.platform)
+    }
+
+    /// Whether the source reads local files rather than the network
+    ///
+    /// [`ChainedSource`] asks only local sources for `local_only` requests.
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    /// Whether "not found" results may be remembered in a [`MissCache`]
+    ///
+    /// Sources backed by local files return `false` so new art is seen at once.
+    fn cache_misses(&self) -> bool {
+        true
+    }
+
+    /// Look up a cover; `Ok(None)` means the source has no art for this game
+    async fn fetch(&self, request: &CoverRequest) -> Result<Option<FetchedCover>>;
+}
+
+/// Expand the `{id}` and `{platform}` placeholders shared by URL templates
+pub(crate) fn expand_placeholders(template: &str, request: &CoverRequest) -> String {
+    template
+        .replace("{id}", request.code.as_str())
+        .replace("{platform}", request.platform.as_str())
+}
+
+/// Percent-encode everything but unreserved URL characters
+pub(crate) fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::collections::HashMap;
+    use std::io::Cursor;
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+
+    use crate::errors::{Error, FetchErrorKind, Result};
+    use crate::http::{HttpClient, HttpMethod, HttpRequest, HttpResponse};
+
+    /// Encode a solid PNG of the given size
+    pub fn png(width: u32, height: u32) -> Vec<u8> {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+            width,
+            height,
+            Rgba([30, 60, 90, 255]),
+        ));
+        let mut bytes = Vec::new();
+        img.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    /// In-memory HTTP client: known URLs return 200, anything else 404
+    #[derive(Default)]
+    pub struct MockClient {
+        pub responses: HashMap<String, HttpResponse>,
+        pub failing: Vec<String>,
+        pub requests: Mutex<Vec<String>>,
+        pub sent: Mutex<Vec<HttpRequest>>,
+    }
+
+    impl MockClient {
+        pub fn with_body(mut self, url: &str, body: Vec<u8>) -> Self {
+            self.responses.insert(
+                url.to_string(),
+                HttpResponse {
+                    status: 200,
+                    headers: Vec::new(),
+                    body,
+                },
+            );
+            self
+        }
+
+        pub fn with_status(mut self, url: &str, status: u16) -> Self {
+            self.responses.insert(
+                url.to_string(),
+                HttpResponse {
+                    status,
+                    ..HttpResponse::default()
+                },
+            );
+            self
+        }
+
+        pub fn with_failure(mut self, url: &str) -> Self {
+            self.failing.push(url.to_string());
+            self
+        }
+
+        pub fn requested(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+
+        #[cfg(feature = "native")]
+        pub fn sent(&self) -> Vec<HttpRequest> {
+            self.sent.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl HttpClient for MockClient {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+            let label = match request.method {
+                HttpMethod::Get => request.url.clone(),
+                HttpMethod::Head => format!("HEAD {}", request.url),
+            };
+            self.requests.lock().unwrap().push(label);
+            self.sent.lock().unwrap().push(request.clone());
+            if self.failing.contains(&request.url) {
+                return Err(Error::fetch(
+                    FetchErrorKind::Transient,
+                    format!("{}: connection reset", request.url),
+                ));
+            }
+            let mut response = self
+                .responses
+                .get(&request.url)
+                .cloned()
+                .unwrap_or(HttpResponse {
+                    status: 404,
+                    ..HttpResponse::default()
+                });
+            if request.method == HttpMethod::Head {
+                response.body.clear();
+            }
+            Ok(response)
+        }
+    }
+}
@@ -0,0 +1,435 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{CoverRequest, CoverSource, FetchedCover};
+use crate::art::ArtKind;
+use crate::errors::{Error, FetchErrorKind, Result};
+use crate::game::{GameCode, Platform};
+use crate::http::{HttpClient, HttpRequest};
+
+/// What to do instead of the normal lookup for one game
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Override {
+    /// Never fetch a cover for this game
+    #[serde(default)]
+    pub skip: bool,
+    /// Look the game up under another code
+    pub code: Option<String>,
+    /// Only use art from this GameTDB region (e.g. `JA`)
+    pub region: Option<String>,
+    /// Only use this kind of art
+    pub art_kind: Option<ArtKind>,
+    /// Download the cover from this URL
+    pub url: Option<String>,
+    /// Use this image file; relative paths are resolved against the override file
+    pub image: Option<PathBuf>,
+}
+
+impl Override {
+    /// The game code to look up instead of the ROM's own
+    pub fn code(&self) -> Option<GameCode> {
+        self.code.as_deref().and_then(parse_code)
+    }
+
+    /// Apply the forced code, region and art kind to a request
+    pub fn apply(&self, request: &CoverRequest) -> CoverRequest {
+        let mut request = request.clone();
+        if let Some(code) = self.code() {
+            request.code = code;
+        }
+        if let Some(region) = &self.region {
+            request = request.with_region(region.as_str());
+        }
+        if let Some(art_kind) = self.art_kind {
+            request = request.with_art_kind(art_kind);
+        }
+        request
+    }
+}
+
+/// Parse a game code written by the user, which must be exactly 4 characters
+fn parse_code(code: &str) -> Option<GameCode> {
+    if code.len() != 4 {
+        return None;
+    }
+    GameCode::from_bytes(code.to_ascii_uppercase().as_bytes()).ok()
+}
+
+/// An override written as a full entry or as the string `"skip"`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawOverride {
+    Keyword(String),
+    Entry(Override),
+}
+
+impl RawOverride {
+    fn resolve(self, key: &str) -> Result<Override> {
+        let entry = match self {
+            RawOverride::Keyword(keyword) if keyword.eq_ignore_ascii_case("skip") => Override {
+                skip: true,
+                ..Override::default()
+            },
+            RawOverride::Keyword(keyword) => {
+                return Err(Error::Processing(format!(
+                    "override {}: unknown action \"{}\"",
+                    key, keyword
+                )))
+            }
+            RawOverride::Entry(entry) => entry,
+        };
+        if let Some(code) = &entry.code {
+            parse_code(code)
+                .ok_or_else(|| Error::Processing(format!("override {}: bad code {}", key, code)))?;
+        }
+        Ok(entry)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OverrideFile {
+    #[serde(default)]
+    games: HashMap<String, RawOverride>,
+    #[serde(default)]
+    paths: BTreeMap<String, RawOverride>,
+}
+
+impl TryFrom<OverrideFile> for Overrides {
+    type Error = Error;
+
+    fn try_from(file: OverrideFile) -> Result<Self> {
+        let mut overrides = Self::new();
+        for (code, raw) in file.games {
+            let entry = raw.resolve(&code)?;
+            let code = parse_code(&code)
+                .ok_or_else(|| Error::Processing(format!("bad game code {}", code)))?;
+            overrides.games.insert(code.to_string(), entry);
+        }
+        for (path, raw) in file.paths {
+            let entry = raw.resolve(&path)?;
+            overrides.paths.insert(normalize_path(&path), entry);
+        }
+        Ok(overrides)
+    }
+}
+
+/// User-edited per-game overrides, keyed by game code or ROM path
+///
+/// ```json
+/// {
+///   "games": {
+///     "AMCE": { "region": "JA" },
+///     "ASME": { "artKind": "box3d" },
+///     "BPRE": "skip"
+///   },
+///   "paths": {
+///     "Homebrew/Tetris DS.nds": { "image": "covers/tetris.png" }
+///   }
+/// }
+/// ```
+///
+/// Path keys match the end of the ROM path, case-insensitively, and win over
+/// code keys.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(try_from = "OverrideFile")]
+pub struct Overrides {
+    games: HashMap<String, Override>,
+    paths: BTreeMap<String, Override>,
+}
+
+impl Overrides {
+    /// Create an empty set of overrides
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse an override file
+    pub fn parse(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| Error::Processing(format!("override file: {}", e)))
+    }
+
+    /// Load an override file, resolving image paths against its folder
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        let mut overrides: Self = serde_json::from_str(&json)
+            .map_err(|e| Error::Processing(format!("{}: {}", path.display(), e)))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for entry in overrides.entries_mut() {
+            if let Some(image) = &mut entry.image {
+                if image.is_relative() {
+                    *image = dir.join(&*image);
+                }
+            }
+        }
+        Ok(overrides)
+    }
+
+    /// Add `other`'s entries, replacing any for the same game or path
+    pub fn merge(&mut self, other: Overrides) {
+        self.games.extend(other.games);
+        self.paths.extend(other.paths);
+    }
+
+    /// Find the override for a game, by ROM path first and then by code
+    pub fn find(&self, code: Option<&GameCode>, rom_path: Option<&Path>) -> Option<&Override> {
+        let by_path = rom_path.and_then(|path| {
+            let path = normalize_path(&path.to_string_lossy());
+            self.paths
+                .iter()
+                .filter(|(key, _)| path == **key || path.ends_with(&format!("/{}", key)))
+                .max_by_key(|(key, _)| key.len())
+                .map(|(_, entry)| entry)
+        });
+        by_path.or_else(|| self.games.get(code?.as_str()))
+    }
+
+    /// Find the override for a request
+    pub fn lookup(&self, request: &CoverRequest) -> Option<&Override> {
+        self.find(Some(&request.code), request.rom_path.as_deref())
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.games.len() + self.paths.len()
+    }
+
+    /// Whether there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// All entries, by code and by path
+    pub fn entries(&self) -> impl Iterator<Item = &Override> {
+        self.games.values().chain(self.paths.values())
+    }
+
+    fn entries_mut(&mut self) -> impl Iterator<Item = &mut Override> {
+        self.games.values_mut().chain(self.paths.values_mut())
+    }
+}
+
+/// Compare paths with `/` separators, ignoring case and leading `./` or `/`
+fn normalize_path(path: &str) -> String {
+    let path = path.replace('\\', "/").to_lowercase();
+    path.trim_start_matches("./")
+        .trim_start_matches('/')
+        .to_string()
+}
+
+/// Applies [`Overrides`] before asking the wrapped source
+///
+/// Skipped games return `Ok(None)`, `url` and `image` entries replace the lookup
+/// entirely, and anything else is passed on with the forced code, region or art kind.
+/// Art that can't be loaded is logged and the normal lookup runs instead.
+pub struct OverrideSource {
+    inner: Arc<dyn CoverSource>,
+    overrides: Arc<Overrides>,
+    client: Arc<dyn HttpClient>,
+}
+
+impl OverrideSource {
+    /// Wrap `inner`; `client` downloads covers for `url` entries
+    pub fn new(
+        inner: Arc<dyn CoverSource>,
+        overrides: Arc<Overrides>,
+        client: Arc<dyn HttpClient>,
+    ) -> Self {
+        Self {
+            inner,
+            overrides,
+            client,
+        }
+    }
+
+    async fn download(&self, url: &str) -> Result<FetchedCover> {
+        let response = self.client.send(HttpRequest::get(url)).await?;
+        if let Some(kind) = FetchErrorKind::from_status(response.status) {
+            return Err(Error::http_status(kind, url, response.status));
+        }
+        FetchedCover::from_response(response, self.name(), url)
+    }
+}
+
+#[async_trait]
+impl CoverSource for OverrideSource {
+    fn name(&self) -> &str {
+        "override"
+    }
+
+    fn supports(&self, platform: Platform) -> bool {
+        self.inner.supports(platform)
+    }
+
+    fn cache_misses(&self) -> bool {
+        false
+    }
+
+    async fn fetch(&self, request: &CoverRequest) -> Result<Option<FetchedCover>> {
+        let Some(entry) = self.overrides.lookup(request) else {
+            return self.inner.fetch(request).await;
+        };
+
+        if entry.skip {
+            request.log(format!(
+                "⏭ Skipped {} [{}] - override",
+                request.game_name, request.code
+            ));
+            return Ok(None);
+        }
+
+        let direct = if let Some(image) = &entry.image {
+            Some(std::fs::read(image).map_err(Error::from).and_then(|bytes| {
+                FetchedCover::decode(bytes, self.name(), image.display().to_string())
+            }))
+        } else if let Some(url) = &entry.url {
+            Some(self.download(url).await)
+        } else {
+            None
+        };
+        match direct {
+            Some(Ok(mut cover)) => {
+                if let Some(art_kind) = entry.art_kind {
+                    cover = cover.with_art_kind(art_kind);
+                }
+                request.log(format!(
+                    "✅ Found {} [{}] - override ({})",
+                    request.game_name, request.code, cover.origin
+                ));
+                return Ok(Some(cover));
+            }
+            Some(Err(err)) => request.log(format!(
+                "⚠ {} [{}] - override art failed, using the normal lookup: {}",
+                request.game_name, request.code, err
+            )),
+            None => {}
+        }
+
+        self.inner.fetch(&entry.apply(request)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::testing::{png, MockClient};
+    use crate::source::GameTdbSource;
+
+    fn code(code: &[u8]) -> GameCode {
+        GameCode::from_bytes(code).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_find_overrides() {
+        let overrides = Overrides::parse(
+            r#"{
+                "games": { "amce": { "region": "JA", "artKind": "box3d" }, "BPRE": "skip" },
+                "paths": { "Homebrew\\Tetris.nds": { "code": "ATRE" } }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(overrides.len(), 3);
+
+        let entry = overrides.find(Some(&code(b"AMCE")), None).unwrap();
+        assert_eq!(entry.region.as_deref(), Some("JA"));
+        assert_eq!(entry.art_kind, Some(ArtKind::Box3d));
+        assert!(overrides.find(Some(&code(b"BPRE")), None).unwrap().skip);
+
+        let rom = Path::new("/media/sd/homebrew/TETRIS.nds");
+        let entry = overrides.find(Some(&code(b"AMCE")), Some(rom)).unwrap();
+        assert_eq!(entry.code(), Some(code(b"ATRE")));
+        assert!(overrides
+            .find(None, Some(Path::new("/sd/NotHomebrew/Tetris.nds")))
+            .is_none());
+
+        assert!(Overrides::parse(r#"{"games": {"AMCE": "later"}}"#).is_err());
+        assert!(Overrides::parse(r#"{"games": {"AMCE": {"colour": "red"}}}"#).is_err());
+        assert!(Overrides::parse(r#"{"games": {"A-CE": "skip"}}"#).is_err());
+        assert!(Overrides::parse(r#"{"games": {"AMCEX": "skip"}}"#).is_err());
+        assert!(Overrides::parse(r#"{"paths": {"a.nds": {"code": "ATREX"}}}"#).is_err());
+    }
+
+    #[test]
+    fn test_load_resolves_images_next_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("overrides.json");
+        std::fs::write(&path, r#"{"games": {"ABCE": {"image": "covers/abc.png"}}}"#).unwrap();
+
+        let overrides = Overrides::load(&path).unwrap();
+        let entry = overrides.find(Some(&code(b"ABCE")), None).unwrap();
+        assert_eq!(
+            entry.image.as_deref(),
+            Some(dir.path().join("covers/abc.png").as_path())
+        );
+    }
+
+    #[test]
+    fn test_override_source_applies_entries() {
+        let client = Arc::new(
+            MockClient::default()
+                .with_body("https://art.gametdb.com/ds/box/JA/ABCE.png", png(20, 18))
+                .with_body("https://example.test/custom.png", png(9, 9)),
+        );
+        let gametdb = GameTdbSource::new(client.clone());
+        let overrides = Overrides::parse(
+            r#"{"games": {
+                "ABCE": { "region": "JA", "artKind": "box3d" },
+                "URLE": { "url": "https://example.test/custom.png" },
+                "BADE": { "url": "https://example.test/missing.png", "region": "JA" },
+                "IMGE": { "image": "/nonexistent/cover.png" },
+                "SKPE": "skip"
+            }}"#,
+        )
+        .unwrap();
+        let source = OverrideSource::new(
+            Arc::new(gametdb),
+            Arc::new(overrides.clone()),
+            client.clone(),
+        );
+
+        let forced = CoverRequest::new(code(b"ABCE"), Platform::Nds);
+        let cover = futures::executor::block_on(source.fetch(&forced))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.region.as_deref(), Some("JA"));
+        assert_eq!(cover.art_kind, ArtKind::Box3d);
+
+        let direct = CoverRequest::new(code(b"URLE"), Platform::Nds);
+        let cover = futures::executor::block_on(source.fetch(&direct))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.source, "override");
+        assert_eq!(cover.image.width(), 9);
+
+        // Broken override art falls back to the normal lookup, still forced
+        let client = Arc::new(
+            MockClient::default()
+                .with_body("https://art.gametdb.com/ds/cover/JA/BADE.png", png(20, 18))
+                .with_body("https://art.gametdb.com/ds/cover/US/IMGE.png", png(20, 18)),
+        );
+        let source = OverrideSource::new(
+            Arc::new(GameTdbSource::new(client.clone())),
+            Arc::new(overrides),
+            client.clone(),
+        );
+        for game in [b"BADE", b"IMGE"] {
+            let request = CoverRequest::new(code(game), Platform::Nds);
+            let cover = futures::executor::block_on(source.fetch(&request))
+                .unwrap()
+                .unwrap();
+            assert_eq!(cover.source, "gametdb");
+        }
+
+        let skipped = CoverRequest::new(code(b"SKPE"), Platform::Nds);
+        let requests = client.requested().len();
+        assert!(futures::executor::block_on(source.fetch(&skipped))
+            .unwrap()
+            .is_none());
+        assert_eq!(client.requested().len(), requests);
+    }
+}
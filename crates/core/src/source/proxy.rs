@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use super::{CoverRequest, CoverSource, FetchedCover};
+use crate::errors::{Error, FetchErrorKind, Result};
+use crate::game::Platform;
+use crate::http::{HttpClient, HttpRequest};
+
+/// Public PicoCover proxy (Cloudflare Worker with KV cache and the GBA library)
+pub const DEFAULT_PROXY_URL: &str = "https://picocover.retrosave.games/";
+
+/// Fetches covers from a PicoCover proxy via `/{platform}/{id}`
+pub struct ProxySource {
+    client: Arc<dyn HttpClient>,
+    base_url: String,
+    platforms: Vec<Platform>,
+}
+
+impl ProxySource {
+    /// Create a source for the proxy at `base_url`
+    pub fn new(client: Arc<dyn HttpClient>, base_url: impl Into<String>) -> Self {
+        let mut base_url = base_url.into();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        Self {
+            client,
+            base_url,
+            platforms: Platform::ALL.to_vec(),
+        }
+    }
+
+    /// Restrict the source to the given platforms
+    pub fn for_platforms(mut self, platforms: &[Platform]) -> Self {
+        self.platforms = platforms.to_vec();
+        self
+    }
+
+    /// Get the base URL, always ending in `/`
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+}
+
+#[async_trait]
+impl CoverSource for ProxySource {
+    fn name(&self) -> &str {
+        "proxy"
+    }
+
+    fn supports(&self, platform: Platform) -> bool {
+        self.platforms.contains(&platform)
+    }
+
+    fn accepts(&self, request: &CoverRequest) -> bool {
+        // Serves a single front cover per game, so it can't honour overrides
+        self.supports(request.platform) && !request.wants_specific_art()
+    }
+
+    async fn fetch(&self, request: &CoverRequest) -> Result<Option<FetchedCover>> {
+        let url = format!(
+            "{}{}/{}",
+            self.base_url,
+            request.platform.as_str(),
+            request.code
+        );
+        let response = self.client.send(HttpRequest::get(&url)).await?;
+        match FetchErrorKind::from_status(response.status) {
+            None => {}
+            Some(FetchErrorKind::NotFound) => return Ok(None),
+            Some(kind) => return Err(Error::http_status(kind, &url, response.status)),
+        }
+
+        let cover = FetchedCover::from_response(response, self.name(), url)?;
+        request.log(format!(
+            "✅ Found {} [{}] - proxy",
+            request.game_name, request.code
+        ));
+        Ok(Some(cover))
+    }
+}
@@ -0,0 +1,514 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::sync::Semaphore;
+
+use super::{percent_encode, CoverRequest, CoverSource, FetchedCover};
+use crate::art::ArtKind;
+use crate::errors::{Error, FetchErrorKind, Result};
+use crate::game::Platform;
+use crate::http::{HttpClient, HttpRequest, HttpResponse};
+
+/// ScreenScraper API v2 base URL
+pub const SCREENSCRAPER_API_URL: &str = "https://api.screenscraper.fr/api2/";
+
+/// Media regions tried when the game code says nothing more specific
+const DEFAULT_MEDIA_REGIONS: [&str; 5] = ["wor", "us", "eu", "ss", "jp"];
+
+/// Account used for ScreenScraper requests
+///
+/// The developer pair identifies the application, the user pair the person
+/// whose thread and daily quota the requests count against.
+#[derive(Clone)]
+pub struct ScreenScraperLogin {
+    pub dev_id: String,
+    pub dev_password: String,
+    pub user: String,
+    pub password: String,
+}
+
+/// Limits reported in the `ssuser` block of each response
+#[derive(Default)]
+struct Quota {
+    /// Permits added to the semaphore so far (it starts with one)
+    threads: usize,
+    min_interval: Duration,
+    last_request: Option<Instant>,
+    /// Why no further requests are sent today
+    exhausted: Option<String>,
+}
+
+/// Looks games up in the ScreenScraper database by ROM hash and game code
+///
+/// Requests never exceed the account's thread count, are spaced to its
+/// per-minute limit, and stop for the run once a daily quota is used up.
+pub struct ScreenScraperSource {
+    client: Arc<dyn HttpClient>,
+    login: ScreenScraperLogin,
+    base_url: String,
+    hash_roms: bool,
+    platforms: Vec<Platform>,
+    threads: Semaphore,
+    quota: Mutex<Quota>,
+}
+
+impl ScreenScraperSource {
+    /// Create a source using the given account
+    pub fn new(client: Arc<dyn HttpClient>, login: ScreenScraperLogin) -> Self {
+        Self {
+            client,
+            login,
+            base_url: SCREENSCRAPER_API_URL.to_string(),
+            hash_roms: true,
+            platforms: Platform::ALL.to_vec(),
+            threads: Semaphore::new(1),
+            quota: Mutex::new(Quota {
+                threads: 1,
+                ..Quota::default()
+            }),
+        }
+    }
+
+    /// Use another API endpoint (mirrors, tests)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        if !self.base_url.ends_with('/') {
+            self.base_url.push('/');
+        }
+        self
+    }
+
+    /// Send the ROM's CRC32 and size; disable to skip reading whole ROMs
+    pub fn with_rom_hashes(mut self, hash_roms: bool) -> Self {
+        self.hash_roms = hash_roms;
+        self
+    }
+
+    /// Restrict the source to the given platforms
+    pub fn for_platforms(mut self, platforms: &[Platform]) -> Self {
+        self.platforms = platforms.to_vec();
+        self
+    }
+
+    fn game_url(&self, request: &CoverRequest, rom: Option<(u64, u32)>) -> String {
+        let mut query = vec![
+            ("devid", self.login.dev_id.clone()),
+            ("devpassword", self.login.dev_password.clone()),
+            ("softwarename", "PicoCover".to_string()),
+            ("ssid", self.login.user.clone()),
+            ("sspassword", self.login.password.clone()),
+            ("output", "json".to_string()),
+            ("romtype", "rom".to_string()),
+            ("systemeid", system_id(request.platform).to_string()),
+            ("serialnum", request.code.to_string()),
+        ];
+        if let Some(name) = request
+            .rom_path
+            .as_deref()
+            .and_then(|path| path.file_name())
+            .and_then(|name| name.to_str())
+        {
+            query.push(("romnom", name.to_string()));
+        }
+        if let Some((size, crc)) = rom {
+            query.push(("romtaille", size.to_string()));
+            query.push(("crc", format!("{:08X}", crc)));
+        }
+        let query: Vec<String> = query
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, percent_encode(&value)))
+            .collect();
+        format!("{}jeuInfos.php?{}", self.base_url, query.join("&"))
+    }
+
+    /// Wait until the per-minute limit allows another request
+    async fn pace(&self) -> Result<()> {
+        let wait = {
+            let mut quota = self.quota.lock().unwrap();
+            if let Some(reason) = &quota.exhausted {
+                return Err(Error::fetch(FetchErrorKind::Permanent, reason.clone()));
+            }
+            let now = Instant::now();
+            let next = quota
+                .last_request
+                .map_or(now, |last| (last + quota.min_interval).max(now));
+            quota.last_request = Some(next);
+            next - now
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// Apply the limits reported for the account
+    fn update_quota(&self, user: &Value) {
+        let mut quota = self.quota.lock().unwrap();
+        if let Some(threads) = number(&user["maxthreads"]) {
+            let threads = threads.max(1) as usize;
+            if threads > quota.threads {
+                self.threads.add_permits(threads - quota.threads);
+                quota.threads = threads;
+            }
+        }
+        if let Some(per_minute) = number(&user["maxrequestspermin"]).filter(|n| *n > 0) {
+            quota.min_interval = Duration::from_secs(60) / per_minute as u32;
+        }
+        let used_up = |used: &str, max: &str| match (number(&user[used]), number(&user[max])) {
+            (Some(used), Some(max)) => max > 0 && used >= max,
+            _ => false,
+        };
+        if used_up("requeststoday", "maxrequestsperday") {
+            quota.exhausted = Some("ScreenScraper daily request quota reached".to_string());
+        } else if used_up("requestskotoday", "maxrequestskoperday") {
+            quota.exhausted = Some("ScreenScraper daily not-found quota reached".to_string());
+        }
+    }
+
+    /// Stop asking for the rest of the run
+    fn exhaust(&self, reason: &str) -> Error {
+        let mut quota = self.quota.lock().unwrap();
+        quota.exhausted = Some(reason.to_string());
+        Error::fetch(FetchErrorKind::Permanent, reason)
+    }
+
+    /// Send a GET request, naming only `label` if it fails
+    ///
+    /// Credentials are part of the URL, and transport errors quote it in full.
+    async fn send(&self, url: &str, label: &str) -> Result<HttpResponse> {
+        self.client
+            .send(HttpRequest::get(url))
+            .await
+            .map_err(|err| {
+                let kind = err.fetch_kind().unwrap_or(FetchErrorKind::Permanent);
+                Error::fetch(
+                    kind,
+                    format!("{}: {} request failure", label, kind.as_str()),
+                )
+            })
+    }
+
+    async fn download(&self, url: &str, label: &str) -> Result<Vec<u8>> {
+        let response = self.send(url, label).await?;
+        match FetchErrorKind::from_status(response.status) {
+            None => Ok(response.body),
+            // Credentials are part of the URL, so errors only name what was asked for
+            Some(kind) => Err(Error::http_status(kind, label, response.status)),
+        }
+    }
+
+    async fn lookup(&self, request: &CoverRequest) -> Result<Option<FetchedCover>> {
+        let rom = match (&request.rom_path, self.hash_roms) {
+            (Some(path), true) => {
+                let path = path.clone();
+                tokio::task::spawn_blocking(move || rom_crc(&path))
+                    .await
+                    .ok()
+                    .and_then(|rom| rom.ok())
+            }
+            _ => None,
+        };
+
+        let label = format!("screenscraper {} {}", request.platform, request.code);
+        let _permit = self
+            .threads
+            .acquire()
+            .await
+            .map_err(|e| Error::Processing(e.to_string()))?;
+        self.pace().await?;
+
+        let response = self.send(&self.game_url(request, rom), &label).await?;
+        match response.status {
+            200..=299 => {}
+            404 => return Ok(None),
+            430 => return Err(self.exhaust("ScreenScraper daily request quota reached")),
+            431 => return Err(self.exhaust("ScreenScraper daily not-found quota reached")),
+            401 | 403 | 423 | 426 => {
+                let reason = String::from_utf8_lossy(&response.body);
+                let reason = format!("ScreenScraper refused the request: {}", reason.trim());
+                return Err(self.exhaust(&reason));
+            }
+            status => {
+                let kind = FetchErrorKind::from_status(status).unwrap_or(FetchErrorKind::Permanent);
+                return Err(Error::http_status(kind, &label, status));
+            }
+        }
+
+        let body: Value = serde_json::from_slice(&response.body)
+            .map_err(|e| Error::fetch(FetchErrorKind::Permanent, format!("{}: {}", label, e)))?;
+        self.update_quota(&body["response"]["ssuser"]);
+
+        let art_kind = request.art_kind.unwrap_or_default();
+        let Some((region, url)) = pick_media(
+            &body["response"]["jeu"]["medias"],
+            media_type(art_kind),
+            &media_regions(request),
+        ) else {
+            return Ok(None);
+        };
+
+        let bytes = self.download(&url, &format!("{} media", label)).await?;
+        let cover = FetchedCover::decode(bytes, self.name(), format!("{} ({})", label, region))?
+            .with_region(region)
+            .with_art_kind(art_kind);
+        Ok(Some(cover))
+    }
+}
+
+/// ScreenScraper system id
+fn system_id(platform: Platform) -> u32 {
+    match platform {
+        Platform::Nds => 15,
+        Platform::Gba => 12,
+    }
+}
+
+/// ScreenScraper media type for an art kind
+fn media_type(art_kind: ArtKind) -> &'static str {
+    match art_kind {
+        ArtKind::Cover | ArtKind::CoverM | ArtKind::CoverS | ArtKind::CoverHq => "box-2D",
+        ArtKind::Box3d => "box-3D",
+        ArtKind::Cart => "support-2D",
+        ArtKind::Full | ArtKind::FullHq => "box-texture",
+    }
+}
+
+/// Media regions in preference order
+///
+/// A forced GameTDB region is the only one accepted; otherwise the region
+/// letter of the game code goes first.
+fn media_regions(request: &CoverRequest) -> Vec<&'static str> {
+    if let Some(region) = &request.region {
+        return match region.to_ascii_uppercase().as_str() {
+            "US" => vec!["us"],
+            "EN" | "EU" => vec!["eu", "uk"],
+            "JA" => vec!["jp"],
+            "FR" => vec!["fr"],
+            "DE" => vec!["de"],
+            "IT" => vec!["it"],
+            "ES" => vec!["sp"],
+            "NL" => vec!["nl"],
+            "PT" => vec!["pt"],
+            "KO" => vec!["kr"],
+            _ => vec![],
+        };
+    }
+    let first = match request.code.region() {
+        Some('E') => Some("us"),
+        Some('P') => Some("eu"),
+        Some('J') => Some("jp"),
+        Some('K') => Some("kr"),
+        _ => None,
+    };
+    let mut regions: Vec<&'static str> = first.into_iter().collect();
+    regions.extend(DEFAULT_MEDIA_REGIONS.iter().filter(|r| Some(**r) != first));
+    regions
+}
+
+/// Pick the media of the given type in the most preferred region
+fn pick_media(medias: &Value, media_type: &str, regions: &[&str]) -> Option<(String, String)> {
+    medias
+        .as_array()?
+        .iter()
+        .filter(|media| media["type"].as_str() == Some(media_type))
+        .filter_map(|media| {
+            let region = media["region"].as_str().unwrap_or("wor");
+            let rank = regions.iter().position(|r| *r == region)?;
+            Some((rank, region, media["url"].as_str()?))
+        })
+        .min_by_key(|(rank, _, _)| *rank)
+        .map(|(_, region, url)| (region.to_string(), url.to_string()))
+}
+
+/// ScreenScraper sends most numbers as strings
+fn number(value: &Value) -> Option<u64> {
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+/// Size and CRC32 of a ROM file
+fn rom_crc(path: &Path) -> std::io::Result<(u64, u32)> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut buffer = vec![0u8; 1 << 16];
+    let mut size = 0u64;
+    let mut crc = !0u32;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        size += read as u64;
+        crc = crc32_update(crc, &buffer[..read]);
+    }
+    Ok((size, !crc))
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32_update(crc: u32, bytes: &[u8]) -> u32 {
+    bytes.iter().fold(crc, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+#[async_trait]
+impl CoverSource for ScreenScraperSource {
+    fn name(&self) -> &str {
+        "screenscraper"
+    }
+
+    fn supports(&self, platform: Platform) -> bool {
+        self.platforms.contains(&platform)
+    }
+
+    async fn fetch(&self, request: &CoverRequest) -> Result<Option<FetchedCover>> {
+        let found = self.lookup(request).await?;
+        if let Some(cover) = &found {
+            request.log(format!(
+                "✅ Found {} [{}] - screenscraper ({})",
+                request.game_name,
+                request.code,
+                cover.region.as_deref().unwrap_or("wor")
+            ));
+        }
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameCode;
+    use crate::source::testing::{png, MockClient};
+
+    fn login() -> ScreenScraperLogin {
+        ScreenScraperLogin {
+            dev_id: "dev".to_string(),
+            dev_password: "devpass".to_string(),
+            user: "player".to_string(),
+            password: "secret word".to_string(),
+        }
+    }
+
+    fn game_url(code: &str, system: u32) -> String {
+        format!(
+            "https://ss.test/jeuInfos.php?devid=dev&devpassword=devpass&softwarename=PicoCover\
+             &ssid=player&sspassword=secret%20word&output=json&romtype=rom&systemeid={}&serialnum={}",
+            system, code
+        )
+    }
+
+    fn game_json(max_threads: &str, today: &str) -> Vec<u8> {
+        format!(
+            r#"{{"response": {{
+                "ssuser": {{"maxthreads": "{}", "maxrequestspermin": "600",
+                            "requeststoday": "{}", "maxrequestsperday": "100"}},
+                "jeu": {{"medias": [
+                    {{"type": "box-2D", "region": "jp", "url": "https://ss.test/media/jp.png"}},
+                    {{"type": "box-2D", "region": "eu", "url": "https://ss.test/media/eu.png"}},
+                    {{"type": "box-3D", "region": "eu", "url": "https://ss.test/media/eu-3d.png"}}
+                ]}}
+            }}}}"#,
+            max_threads, today
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(!crc32_update(!0, b"123456789"), 0xCBF4_3926);
+    }
+
+    #[tokio::test]
+    async fn test_picks_region_and_media_type() {
+        let client = Arc::new(
+            MockClient::default()
+                .with_body(&game_url("BPEP", 12), game_json("4", "1"))
+                .with_body("https://ss.test/media/eu.png", png(30, 20))
+                .with_body("https://ss.test/media/eu-3d.png", png(40, 20)),
+        );
+        let source = ScreenScraperSource::new(client.clone(), login())
+            .with_base_url("https://ss.test")
+            .with_rom_hashes(false);
+
+        let request = CoverRequest::new(GameCode::from_bytes(b"BPEP").unwrap(), Platform::Gba);
+        let cover = source.fetch(&request).await.unwrap().unwrap();
+        assert_eq!(cover.region.as_deref(), Some("eu"));
+        assert_eq!(cover.image.width(), 30);
+        assert_eq!(source.threads.available_permits(), 4);
+
+        let cover = source
+            .fetch(&request.clone().with_art_kind(ArtKind::Box3d))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cover.image.width(), 40);
+        assert_eq!(cover.art_kind, ArtKind::Box3d);
+
+        // Only the forced region counts, and there is no US box for this game
+        let forced = request.with_region("US");
+        assert!(source.fetch(&forced).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stops_when_quota_is_used_up() {
+        let client = Arc::new(
+            MockClient::default()
+                .with_body(&game_url("AMCE", 15), game_json("1", "100"))
+                .with_body("https://ss.test/media/eu.png", png(30, 20)),
+        );
+        let source = ScreenScraperSource::new(client.clone(), login())
+            .with_base_url("https://ss.test")
+            .with_rom_hashes(false);
+
+        let request = CoverRequest::new(GameCode::from_bytes(b"AMCE").unwrap(), Platform::Nds);
+        // The last request of the day still gets its cover
+        assert!(source.fetch(&request).await.unwrap().is_some());
+        let requests = client.requested().len();
+
+        let next = CoverRequest::new(GameCode::from_bytes(b"ABCE").unwrap(), Platform::Nds);
+        let err = source.fetch(&next).await.unwrap_err();
+        assert_eq!(err.fetch_kind(), Some(FetchErrorKind::Permanent));
+        assert_eq!(client.requested().len(), requests);
+    }
+
+    #[tokio::test]
+    async fn test_transport_errors_hide_credentials() {
+        let client = Arc::new(MockClient::default().with_failure(&game_url("AMCE", 15)));
+        let source = ScreenScraperSource::new(client.clone(), login())
+            .with_base_url("https://ss.test")
+            .with_rom_hashes(false);
+
+        let request = CoverRequest::new(GameCode::from_bytes(b"AMCE").unwrap(), Platform::Nds);
+        let err = source.fetch(&request).await.unwrap_err();
+        assert!(err.is_transient());
+        let message = err.to_string();
+        assert!(message.contains("screenscraper"), "{}", message);
+        assert!(!message.contains("devpass"), "{}", message);
+        assert!(!message.contains("secret"), "{}", message);
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use super::{CoverRequest, CoverSource, FetchedCover};
+use crate::errors::{Error, FetchErrorKind, Result};
+use crate::game::Platform;
+use crate::http::{HttpClient, HttpRequest};
+
+/// Fetches covers from an arbitrary URL template with `{platform}` and `{id}` placeholders
+pub struct HttpTemplateSource {
+    client: Arc<dyn HttpClient>,
+    name: String,
+    template: String,
+    platforms: Vec<Platform>,
+}
+
+impl HttpTemplateSource {
+    /// Create a source named `name` for `template`
+    pub fn new(
+        client: Arc<dyn HttpClient>,
+        name: impl Into<String>,
+        template: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            name: name.into(),
+            template: template.into(),
+            platforms: Platform::ALL.to_vec(),
+        }
+    }
+
+    /// Restrict the source to the given platforms
+    pub fn for_platforms(mut self, platforms: &[Platform]) -> Self {
+        self.platforms = platforms.to_vec();
+        self
+    }
+}
+
+#[async_trait]
+impl CoverSource for HttpTemplateSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn supports(&self, platform: Platform) -> bool {
+        self.platforms.contains(&platform)
+    }
+
+    fn accepts(&self, request: &CoverRequest) -> bool {
+        // Serves a single front cover per game, so it can't honour overrides
+        self.supports(request.platform) && !request.wants_specific_art()
+    }
+
+    async fn fetch(&self, request: &CoverRequest) -> Result<Option<FetchedCover>> {
+        let url = super::expand_placeholders(&self.template, request);
+        let response = self.client.send(HttpRequest::get(&url)).await?;
+        match FetchErrorKind::from_status(response.status) {
+            None => {}
+            Some(FetchErrorKind::NotFound) => return Ok(None),
+            Some(kind) => return Err(Error::http_status(kind, &url, response.status)),
+        }
+
+        let cover = FetchedCover::from_response(response, self.name(), url)?;
+        request.log(format!(
+            "✅ Found {} [{}] - {}",
+            request.game_name, request.code, self.name
+        ));
+        Ok(Some(cover))
+    }
+}
@@ -0,0 +1,560 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::errors::{Error, Result};
+use crate::game::{GameCode, Platform};
+use crate::source::normalize_title;
+
+/// Language used when neither the requested one nor a title in it exists
+pub const DEFAULT_LANGUAGE: &str = "EN";
+
+/// Minimum similarity for a title match to be trusted
+pub const DEFAULT_MATCH_THRESHOLD: f32 = 0.8;
+
+/// Region letters preferred when several releases match equally well
+const REGION_PREFERENCE: [char; 3] = ['E', 'P', 'J'];
+
+/// Release date; GameTDB often only knows the year
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReleaseDate {
+    pub year: u16,
+    pub month: Option<u8>,
+    pub day: Option<u8>,
+}
+
+impl std::fmt::Display for ReleaseDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}", self.year)?;
+        if let Some(month) = self.month {
+            write!(f, "-{:02}", month)?;
+            if let Some(day) = self.day {
+                write!(f, "-{:02}", day)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Everything the database knows about one game
+#[derive(Debug, Clone, Default)]
+pub struct GameInfo {
+    pub code: String,
+    /// Titles by upper-case language code (`EN`, `JA`, ...)
+    pub titles: BTreeMap<String, String>,
+    /// GameTDB region, e.g. `NTSC-U` or `PAL`
+    pub region: Option<String>,
+    pub languages: Vec<String>,
+    pub developer: Option<String>,
+    pub publisher: Option<String>,
+    pub release: Option<ReleaseDate>,
+}
+
+impl GameInfo {
+    /// Title in `language`, falling back to English and then any title
+    pub fn title(&self, language: &str) -> Option<&str> {
+        self.titles
+            .get(&language.to_ascii_uppercase())
+            .or_else(|| self.titles.get(DEFAULT_LANGUAGE))
+            .or_else(|| self.titles.values().next())
+            .map(String::as_str)
+    }
+}
+
+/// A game found by comparing titles instead of codes
+#[derive(Debug, Clone, PartialEq)]
+pub struct TitleMatch {
+    pub code: GameCode,
+    /// Title that matched, in the database's language
+    pub title: String,
+    /// Similarity between 0 and 1
+    pub score: f32,
+}
+
+impl std::fmt::Display for TitleMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}] ({:.0}%)",
+            self.title,
+            self.code,
+            self.score * 100.0
+        )
+    }
+}
+
+/// Indexed store of [`GameInfo`] for one platform
+#[derive(Debug, Clone)]
+pub struct TitleDb {
+    platform: Platform,
+    language: String,
+    games: HashMap<String, GameInfo>,
+}
+
+impl TitleDb {
+    /// Create an empty database for `platform`
+    pub fn new(platform: Platform) -> Self {
+        Self {
+            platform,
+            language: DEFAULT_LANGUAGE.to_string(),
+            games: HashMap::new(),
+        }
+    }
+
+    /// Set the language titles are returned in
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = language.into().to_ascii_uppercase();
+        self
+    }
+
+    /// Read `dstdb.xml` or `dstdb.txt` from disk
+    pub fn load(platform: Platform, path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(platform, &text)
+    }
+
+    /// Parse either export, detected from the first character
+    pub fn parse(platform: Platform, text: &str) -> Result<Self> {
+        if text.trim_start().starts_with('<') {
+            Self::parse_xml(platform, text)
+        } else {
+            Self::parse_txt(platform, text)
+        }
+    }
+
+    /// Parse the full XML export or a DAT file with ROM serials
+    pub fn parse_xml(platform: Platform, xml: &str) -> Result<Self> {
+        let datafile: XmlDatafile = quick_xml::de::from_str(xml)
+            .map_err(|e| Error::Processing(format!("Invalid title database XML: {}", e)))?;
+
+        let mut db = Self::new(platform);
+        for game in datafile.games {
+            let code = match &game.id {
+                Some(id) => Some(id.trim().to_ascii_uppercase()),
+                None => game
+                    .roms
+                    .iter()
+                    .find_map(|rom| serial_code(rom.serial.as_deref()?)),
+            };
+            let Some(code) = code.filter(|code| !code.is_empty()) else {
+                continue;
+            };
+            let mut info = GameInfo {
+                code,
+                region: non_empty(game.region),
+                languages: game
+                    .languages
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|lang| lang.trim().to_ascii_uppercase())
+                    .filter(|lang| !lang.is_empty())
+                    .collect(),
+                developer: non_empty(game.developer),
+                publisher: non_empty(game.publisher),
+                release: game.date.and_then(XmlDate::parse),
+                ..GameInfo::default()
+            };
+            for locale in game.locales {
+                if let Some(title) = non_empty(locale.title) {
+                    info.titles.insert(locale.lang.to_ascii_uppercase(), title);
+                }
+            }
+            if info.titles.is_empty() {
+                if let Some(name) = non_empty(game.name) {
+                    info.titles.insert(DEFAULT_LANGUAGE.to_string(), name);
+                }
+            }
+            db.insert(info);
+        }
+        Ok(db)
+    }
+
+    /// Parse the `CODE = Title` text export
+    ///
+    /// The header line (`TITLES = ... language: EN ...`) sets the language.
+    pub fn parse_txt(platform: Platform, text: &str) -> Result<Self> {
+        let mut db = Self::new(platform);
+        let mut language = DEFAULT_LANGUAGE.to_string();
+        for line in text.lines() {
+            let Some((code, title)) = line.split_once('=') else {
+                continue;
+            };
+            let (code, title) = (code.trim(), title.trim());
+            if code.eq_ignore_ascii_case("TITLES") {
+                if let Some(lang) = title.split("language:").nth(1) {
+                    language = lang.split_whitespace().next().unwrap_or("EN").to_string();
+                }
+                continue;
+            }
+            if code.is_empty() || title.is_empty() {
+                continue;
+            }
+
+            let mut info = GameInfo {
+                code: code.to_ascii_uppercase(),
+                ..GameInfo::default()
+            };
+            info.titles
+                .insert(language.to_ascii_uppercase(), title.to_string());
+            db.insert(info);
+        }
+        if db.is_empty() {
+            return Err(Error::Processing(
+                "GameTDB title list contains no games".to_string(),
+            ));
+        }
+        Ok(db)
+    }
+
+    /// Add or replace a game
+    pub fn insert(&mut self, info: GameInfo) {
+        self.games.insert(info.code.clone(), info);
+    }
+
+    /// Platform the codes belong to
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    /// Language titles are returned in
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Look up a game
+    pub fn get(&self, code: &GameCode) -> Option<&GameInfo> {
+        self.games.get(code.as_str())
+    }
+
+    /// Whether GameTDB knows the code
+    pub fn contains(&self, code: &GameCode) -> bool {
+        self.games.contains_key(code.as_str())
+    }
+
+    /// Title of a game in the preferred language
+    pub fn title(&self, code: &GameCode) -> Option<&str> {
+        self.get(code)?.title(&self.language)
+    }
+
+    /// Find games whose title (in any language) contains `query`
+    ///
+    /// Matching ignores case, punctuation and bracketed tags. Exact matches come
+    /// first, then prefix matches, then the rest by title.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&GameInfo> {
+        let query = normalize_title(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut matches: Vec<(u8, &str, &GameInfo)> = self
+            .games
+            .values()
+            .filter_map(|info| {
+                let rank = info
+                    .titles
+                    .values()
+                    .map(|title| normalize_title(title))
+                    .filter_map(|title| {
+                        if title == query {
+                            Some(0)
+                        } else if title.starts_with(&query) {
+                            Some(1)
+                        } else if title.contains(&query) {
+                            Some(2)
+                        } else {
+                            None
+                        }
+                    })
+                    .min()?;
+                Some((rank, info.title(&self.language).unwrap_or_default(), info))
+            })
+            .collect();
+        matches.sort_by(|a, b| (a.0, a.1, &a.2.code).cmp(&(b.0, b.1, &b.2.code)));
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, _, info)| info)
+            .collect()
+    }
+
+    /// Find the game whose title is most similar to `title`
+    ///
+    /// Titles are compared after [`normalize_title`] using the Dice coefficient
+    /// of character bigrams; ties prefer US, then European, then Japanese releases.
+    pub fn best_match(&self, title: &str) -> Option<TitleMatch> {
+        let query = normalize_title(title);
+        if query.is_empty() {
+            return None;
+        }
+        let query_bigrams = bigrams(&query);
+
+        let mut best: Option<(f32, usize, &GameInfo)> = None;
+        for info in self.games.values() {
+            let score = info
+                .titles
+                .values()
+                .map(|title| similarity(&query, &query_bigrams, &normalize_title(title)))
+                .fold(0.0, f32::max);
+            if score <= 0.0 {
+                continue;
+            }
+            let region = region_rank(&info.code);
+            let better = match best {
+                None => true,
+                Some((best_score, best_region, best_info)) => {
+                    score > best_score
+                        || (score == best_score
+                            && (region, &info.code) < (best_region, &best_info.code))
+                }
+            };
+            if better {
+                best = Some((score, region, info));
+            }
+        }
+
+        let (score, _, info) = best?;
+        Some(TitleMatch {
+            code: GameCode::from_bytes(info.code.as_bytes()).ok()?,
+            title: info.title(&self.language).unwrap_or_default().to_string(),
+            score,
+        })
+    }
+
+    /// Number of games
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    /// Whether the database has no games
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+}
+
+fn bigrams(text: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut pairs: Vec<(char, char)> = chars.windows(2).map(|w| (w[0], w[1])).collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// Dice coefficient of two normalised titles
+fn similarity(query: &str, query_bigrams: &[(char, char)], candidate: &str) -> f32 {
+    if query == candidate {
+        return 1.0;
+    }
+    let candidate_bigrams = bigrams(candidate);
+    let total = query_bigrams.len() + candidate_bigrams.len();
+    if total == 0 {
+        return 0.0;
+    }
+
+    // Both lists are sorted, so shared bigrams can be counted in one pass
+    let (mut i, mut j, mut shared) = (0, 0, 0);
+    while i < query_bigrams.len() && j < candidate_bigrams.len() {
+        match query_bigrams[i].cmp(&candidate_bigrams[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                shared += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    (2 * shared) as f32 / total as f32
+}
+
+fn region_rank(code: &str) -> usize {
+    code.chars()
+        .last()
+        .and_then(|region| REGION_PREFERENCE.iter().position(|&r| r == region))
+        .unwrap_or(REGION_PREFERENCE.len())
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[derive(Deserialize)]
+struct XmlDatafile {
+    #[serde(rename = "game", default)]
+    games: Vec<XmlGame>,
+}
+
+#[derive(Deserialize)]
+struct XmlGame {
+    #[serde(rename = "@name")]
+    name: Option<String>,
+    id: Option<String>,
+    #[serde(rename = "rom", default)]
+    roms: Vec<XmlRom>,
+    region: Option<String>,
+    languages: Option<String>,
+    #[serde(rename = "locale", default)]
+    locales: Vec<XmlLocale>,
+    developer: Option<String>,
+    publisher: Option<String>,
+    date: Option<XmlDate>,
+}
+
+#[derive(Deserialize)]
+struct XmlRom {
+    #[serde(rename = "@serial")]
+    serial: Option<String>,
+}
+
+/// Game code inside a DAT serial such as `NTR-AMCE-USA` or `AMCE`
+fn serial_code(serial: &str) -> Option<String> {
+    serial
+        .split(['-', ' ', ','])
+        .filter(|part| part.len() == 4 && part.chars().all(|c| c.is_ascii_alphanumeric()))
+        .find(|part| !matches!(*part, "NTR" | "TWL" | "AGB"))
+        .map(|part| part.to_ascii_uppercase())
+}
+
+#[derive(Deserialize)]
+struct XmlLocale {
+    #[serde(rename = "@lang")]
+    lang: String,
+    title: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct XmlDate {
+    #[serde(rename = "@year")]
+    year: Option<String>,
+    #[serde(rename = "@month")]
+    month: Option<String>,
+    #[serde(rename = "@day")]
+    day: Option<String>,
+}
+
+impl XmlDate {
+    fn parse(self) -> Option<ReleaseDate> {
+        let year = self.year?.trim().parse().ok()?;
+        let month = self.month.and_then(|m| m.trim().parse().ok());
+        let day = month.and(self.day.and_then(|d| d.trim().parse().ok()));
+        Some(ReleaseDate { year, month, day })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<datafile>
+  <WiiTDB version="20240101" games="2"/>
+  <game name="Mario Kart DS">
+    <id>AMCE</id>
+    <type/>
+    <region>NTSC-U</region>
+    <languages>EN</languages>
+    <locale lang="EN"><title>Mario Kart DS</title><synopsis>Race &amp; battle.</synopsis></locale>
+    <locale lang="JA"><title>マリオカートDS</title></locale>
+    <developer>Nintendo EAD</developer>
+    <publisher>Nintendo</publisher>
+    <date year="2005" month="11" day="14"/>
+    <genre>racing</genre>
+  </game>
+  <game name="Pokémon Platinum">
+    <id>CPUE</id>
+    <region>NTSC-U</region>
+    <locale lang="EN"><title>Pokémon Platinum Version</title></locale>
+    <publisher>Nintendo</publisher>
+    <date year="2009" month="" day=""/>
+  </game>
+</datafile>"#;
+
+    fn code(code: &str) -> GameCode {
+        GameCode::from_bytes(code.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_xml() {
+        let db = TitleDb::parse(Platform::Nds, XML).unwrap();
+        assert_eq!(db.len(), 2);
+        assert_eq!(db.title(&code("AMCE")), Some("Mario Kart DS"));
+        assert!(!db.contains(&code("ZZZZ")));
+
+        let kart = db.get(&code("AMCE")).unwrap();
+        assert_eq!(kart.title("ja"), Some("マリオカートDS"));
+        assert_eq!(kart.title("FR"), Some("Mario Kart DS"));
+        assert_eq!(kart.publisher.as_deref(), Some("Nintendo"));
+        assert_eq!(kart.release.unwrap().to_string(), "2005-11-14");
+        assert_eq!(
+            db.get(&code("CPUE")).unwrap().release.unwrap().to_string(),
+            "2009"
+        );
+
+        let db = db.with_language("ja");
+        assert_eq!(db.title(&code("AMCE")), Some("マリオカートDS"));
+    }
+
+    #[test]
+    fn test_parse_txt_and_search() {
+        let txt = "TITLES = https://www.gametdb.com (type: DS language: EN version: 20240101)\n\
+                   AMCE = Mario Kart DS\n\
+                   AMKE = Mario Kart DS (Demo)\n\
+                   ASME = Super Mario 64 DS\n";
+        let db = TitleDb::parse(Platform::Nds, txt).unwrap();
+        assert_eq!(db.len(), 3);
+
+        let found: Vec<&str> = db
+            .search("mario kart", 10)
+            .iter()
+            .map(|info| info.code.as_str())
+            .collect();
+        // The demo normalises to the same title, so both are exact matches
+        assert_eq!(found, ["AMCE", "AMKE"]);
+        assert_eq!(db.search("mario", 10).len(), 3);
+        assert_eq!(db.search("mario", 1).len(), 1);
+        assert!(db.search("zelda", 10).is_empty());
+    }
+
+    #[test]
+    fn test_parse_dat() {
+        let dat = r#"<?xml version="1.0"?>
+<datafile>
+  <header><name>Nintendo - Nintendo DS</name></header>
+  <game name="Mario Kart DS (USA, Australia)">
+    <description>Mario Kart DS (USA, Australia)</description>
+    <rom name="Mario Kart DS (USA, Australia).nds" size="33554432" serial="NTR-AMCE-USA"/>
+  </game>
+  <game name="Some Homebrew">
+    <rom name="Some Homebrew.nds" size="1024"/>
+  </game>
+</datafile>"#;
+        let db = TitleDb::parse(Platform::Nds, dat).unwrap();
+        assert_eq!(db.len(), 1);
+        assert_eq!(
+            db.title(&code("AMCE")),
+            Some("Mario Kart DS (USA, Australia)")
+        );
+    }
+
+    #[test]
+    fn test_best_match() {
+        let txt = "AMCE = Mario Kart DS\n\
+                   AMCP = Mario Kart DS\n\
+                   ASME = Super Mario 64 DS\n\
+                   ADAE = Pokémon Diamond Version\n";
+        let db = TitleDb::parse(Platform::Nds, txt).unwrap();
+
+        let found = db.best_match("mario_kart_ds (Hack) [v2]").unwrap();
+        assert_eq!(found.code.as_str(), "AMCE");
+        assert_eq!(found.score, 1.0);
+
+        let found = db.best_match("Pokemon Diamond").unwrap();
+        assert_eq!(found.code.as_str(), "ADAE");
+        assert!(found.score > 0.7 && found.score < 1.0);
+
+        let found = db.best_match("Tetris DS").unwrap();
+        assert!(found.score < DEFAULT_MATCH_THRESHOLD);
+        assert!(db.best_match("[!]").is_none());
+    }
+}
@@ -1,17 +1,27 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, Receiver};
 use std::thread;
 
-#[cfg(windows)]
-use std::path::Path;
-
 #[cfg(not(windows))]
 use std::fs;
 
 use anyhow::anyhow;
 use eframe::egui;
+use pico_cover_core::art::ArtKind;
+use pico_cover_core::display::DisplayProfile;
+use pico_cover_core::game::Platform;
+use pico_cover_core::http::ConcurrencyLimits;
+use pico_cover_core::image_processing::RenderOptions;
+use pico_cover_core::pack::{ConflictPolicy, CoverPack};
+use pico_cover_core::provenance::Provenance;
+use pico_cover_core::resample::Sharpening;
+use pico_cover_core::selection::SelectionStrategy;
+use pico_cover_core::source::{DEFAULT_LIBRETRO_URL, DEFAULT_MISS_TTL, DEFAULT_PROXY_URL};
+use pico_cover_core::titles::{DEFAULT_LANGUAGE, DEFAULT_MATCH_THRESHOLD};
 
-use crate::processing::{process_root, ProcessStats};
-use crate::{updater, Config};
+use crate::processing::{process_root, screenscraper_login, ProcessStats};
+use crate::{updater, CacheConfig, Config};
 
 #[derive(Clone)]
 pub struct DriveInfo {
@@ -70,20 +80,79 @@ pub struct GuiApp {
     rx: Option<Receiver<GuiMessage>>,
     running: bool,
     stats: Option<ProcessStats>,
+    /// Card the last run wrote to
+    last_root: Option<PathBuf>,
+    /// Lock state of the covers in the game lists
+    locks: HashMap<(Platform, String), bool>,
     error: Option<String>,
     drives: Vec<DriveInfo>,
     selected_drive: usize,
     logo_texture: Option<egui::TextureHandle>,
     update_available: Option<String>,
     show_update_dialog: bool,
+    show_saved: bool,
     show_skipped: bool,
     show_failed: bool,
+    show_matched: bool,
+    show_locked: bool,
     show_games_list: bool,
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct UiConfig {
     pub overwrite: bool,
+    pub force: bool,
+    pub refresh: bool,
+    pub display: DisplayProfile,
+    pub linear_light: bool,
+    pub sharpen: Sharpening,
+    pub art_kind: ArtKind,
+    pub best_quality: bool,
+    pub quality_report: bool,
+    pub library: Option<PathBuf>,
+    pub titles_db: Option<PathBuf>,
+    pub screenscraper_user: String,
+    pub screenscraper_password: String,
+    pub recheck_missing: bool,
+    pub pack_conflict: ConflictPolicy,
+    pub parallel_probes: bool,
+    pub use_proxy: bool,
+    pub use_libretro: bool,
+    pub include_gba: bool,
+    pub downloads: usize,
+    pub per_host: usize,
+    /// Requests per second per host; 0 means unlimited
+    pub rps: f64,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        let limits = ConcurrencyLimits::default();
+        Self {
+            overwrite: false,
+            force: false,
+            refresh: false,
+            display: DisplayProfile::default(),
+            linear_light: false,
+            sharpen: Sharpening::default(),
+            art_kind: ArtKind::default(),
+            best_quality: false,
+            quality_report: false,
+            library: None,
+            titles_db: None,
+            screenscraper_user: String::new(),
+            screenscraper_password: String::new(),
+            recheck_missing: false,
+            pack_conflict: ConflictPolicy::default(),
+            parallel_probes: false,
+            use_proxy: true,
+            use_libretro: true,
+            include_gba: false,
+            downloads: limits.max_concurrent,
+            per_host: limits.per_host,
+            rps: 0.0,
+        }
+    }
 }
 
 enum GuiMessage {
@@ -105,39 +174,50 @@ impl GuiApp {
             rx: None,
             running: false,
             stats: None,
+            last_root: None,
+            locks: HashMap::new(),
             error: None,
             drives,
             selected_drive,
             logo_texture: None,
             update_available,
             show_update_dialog: false,
+            show_saved: false,
             show_skipped: false,
             show_failed: false,
+            show_matched: false,
+            show_locked: false,
             show_games_list: false,
         }
     }
 
-    fn start_processing(&mut self) -> anyhow::Result<()> {
+    fn drive_path(&self) -> anyhow::Result<String> {
         if self.drives.is_empty() {
             return Err(anyhow!("No drives detected"));
         }
-        let drive_path = self
+        Ok(self
             .drives
             .get(self.selected_drive)
             .ok_or_else(|| anyhow!("Invalid drive selection"))?
             .path
-            .clone();
+            .clone())
+    }
+
+    fn start_processing(&mut self) -> anyhow::Result<()> {
+        let drive_path = self.drive_path()?;
         let config = self.ui_config.to_config(&drive_path)?;
         let (tx, rx) = mpsc::channel();
         self.logs.clear();
         self.stats = None;
+        self.last_root = Some(config.root.clone());
         self.error = None;
         self.running = true;
         self.rx = Some(rx);
 
         thread::spawn(move || {
-            let send_log = |msg: String| {
-                let _ = tx.send(GuiMessage::Log(msg));
+            let log_tx = tx.clone();
+            let send_log = move |msg: String| {
+                let _ = log_tx.send(GuiMessage::Log(msg));
             };
             let result = process_root(&config, send_log);
             match result {
@@ -156,6 +236,55 @@ impl GuiApp {
         Ok(())
     }
 
+    fn export_pack(&mut self, path: &Path) -> anyhow::Result<()> {
+        let root = PathBuf::from(self.drive_path()?);
+        let mut pack = CoverPack::new();
+        pack.add_card(&root)?;
+        pack.write(path)?;
+        self.logs.push(format!(
+            "📦 Exported {} covers to {}",
+            pack.len(),
+            path.display()
+        ));
+        Ok(())
+    }
+
+    fn import_pack(&mut self, path: &Path) -> anyhow::Result<()> {
+        let root = PathBuf::from(self.drive_path()?);
+        let (pack, invalid) = CoverPack::read(path)?;
+        let stats = pack.import(&root, self.ui_config.pack_conflict)?;
+        self.logs.push(format!(
+            "📥 Imported {} (added={} replaced={} kept={} locked={})",
+            path.display(),
+            stats.added,
+            stats.replaced,
+            stats.kept,
+            stats.locked
+        ));
+        for name in invalid {
+            self.logs.push(format!("⚠ Ignored {}", name));
+        }
+        Ok(())
+    }
+
+    /// Lock or unlock a cover from the last run's card
+    fn set_lock(
+        &mut self,
+        platform: Platform,
+        cover_name: String,
+        locked: bool,
+    ) -> anyhow::Result<()> {
+        let root = self
+            .last_root
+            .clone()
+            .ok_or_else(|| anyhow!("No card to lock covers on"))?;
+        let mut provenance = Provenance::load(&root)?;
+        provenance.set_lock(platform, &cover_name, Some(locked));
+        provenance.save(&root)?;
+        self.locks.insert((platform, cover_name), locked);
+        Ok(())
+    }
+
     fn poll_messages(&mut self) {
         if let Some(rx) = &self.rx {
             while let Ok(msg) = rx.try_recv() {
@@ -163,6 +292,30 @@ impl GuiApp {
                     GuiMessage::Log(line) => self.logs.push(line),
                     GuiMessage::Done(stats, err) => {
                         self.running = false;
+                        // Skipped covers show the lock list as it is on the card
+                        let provenance = self
+                            .last_root
+                            .as_deref()
+                            .and_then(|root| Provenance::load(root).ok())
+                            .unwrap_or_default();
+                        self.locks =
+                            stats
+                                .skipped_games
+                                .iter()
+                                .filter_map(|game| {
+                                    let cover_name = game.cover_name.clone()?;
+                                    let locked = provenance.lock_state(game.platform, &cover_name);
+                                    Some(((game.platform, cover_name), locked == Some(true)))
+                                })
+                                .chain(
+                                    stats.saved_games.iter().map(|game| {
+                                        ((game.platform, game.cover_name.clone()), false)
+                                    }),
+                                )
+                                .chain(stats.locked_games.iter().map(|locked| {
+                                    ((locked.platform, locked.cover_name.clone()), true)
+                                }))
+                                .collect();
                         self.stats = Some(stats);
                         self.error = err;
                     }
@@ -334,6 +487,207 @@ impl eframe::App for GuiApp {
 
             ui.horizontal(|ui| {
                 ui.checkbox(&mut self.ui_config.overwrite, "Overwrite existing BMPs");
+                ui.checkbox(&mut self.ui_config.refresh, "Refresh changed")
+                    .on_hover_text(
+                        "Only re-render covers whose source image changed since they were made",
+                    );
+                ui.add_enabled(
+                    self.ui_config.overwrite || self.ui_config.refresh,
+                    egui::Checkbox::new(&mut self.ui_config.force, "Force"),
+                )
+                .on_hover_text("Also replace locked and hand-edited covers");
+                ui.separator();
+                ui.checkbox(&mut self.ui_config.best_quality, "Best quality")
+                    .on_hover_text(
+                        "Check every region and keep the highest resolution scan (slower)",
+                    );
+                ui.separator();
+                ui.checkbox(&mut self.ui_config.use_proxy, "Use proxy")
+                    .on_hover_text("Fetch through the PicoCover proxy first, GameTDB as fallback");
+                ui.checkbox(&mut self.ui_config.use_libretro, "libretro")
+                    .on_hover_text("Fall back to libretro-thumbnails boxart, looked up by title");
+                ui.add_enabled(
+                    self.ui_config.use_proxy || self.ui_config.use_libretro,
+                    egui::Checkbox::new(&mut self.ui_config.include_gba, "GBA (beta)"),
+                )
+                .on_hover_text("Also process .gba ROMs (covers come from the proxy and libretro)");
+                ui.separator();
+                ui.checkbox(&mut self.ui_config.recheck_missing, "Recheck missing")
+                    .on_hover_text("Look up games that had no cover on recent runs again");
+                ui.separator();
+                ui.label("Screen:");
+                egui::ComboBox::from_id_salt("display_selector")
+                    .selected_text(self.ui_config.display.label())
+                    .show_ui(ui, |ui| {
+                        for profile in DisplayProfile::ALL {
+                            ui.selectable_value(
+                                &mut self.ui_config.display,
+                                profile,
+                                profile.label(),
+                            );
+                        }
+                    });
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Art:");
+                egui::ComboBox::from_id_salt("art_kind_selector")
+                    .selected_text(self.ui_config.art_kind.label())
+                    .show_ui(ui, |ui| {
+                        for kind in [
+                            ArtKind::Cover,
+                            ArtKind::Cart,
+                            ArtKind::Box3d,
+                            ArtKind::FullHq,
+                        ] {
+                            ui.selectable_value(&mut self.ui_config.art_kind, kind, kind.label());
+                        }
+                    })
+                    .response
+                    .on_hover_text("Falls back to the front cover when unavailable");
+                ui.separator();
+                ui.checkbox(&mut self.ui_config.linear_light, "Linear-light resize");
+                ui.separator();
+                ui.checkbox(&mut self.ui_config.quality_report, "Quality report")
+                    .on_hover_text("Measure PSNR/SSIM and flag covers that quantized badly");
+                ui.separator();
+                ui.label("Sharpen:");
+                egui::ComboBox::from_id_salt("sharpen_selector")
+                    .selected_text(self.ui_config.sharpen.label())
+                    .show_ui(ui, |ui| {
+                        for preset in Sharpening::PRESETS {
+                            ui.selectable_value(
+                                &mut self.ui_config.sharpen,
+                                preset,
+                                preset.label(),
+                            );
+                        }
+                    });
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Downloads:");
+                ui.add(egui::DragValue::new(&mut self.ui_config.downloads).range(1..=64))
+                    .on_hover_text("Downloads in flight across all servers");
+                ui.label("Per server:");
+                ui.add(egui::DragValue::new(&mut self.ui_config.per_host).range(1..=16))
+                    .on_hover_text("Downloads in flight to a single server");
+                ui.label("Max req/s:");
+                ui.add(
+                    egui::DragValue::new(&mut self.ui_config.rps)
+                        .range(0.0..=50.0)
+                        .speed(0.1),
+                )
+                .on_hover_text("Requests per second to a single server (0 = unlimited)");
+                ui.separator();
+                ui.checkbox(&mut self.ui_config.parallel_probes, "Parallel probing")
+                    .on_hover_text("Check all regions at once and download only the best match");
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Cover library:");
+                match &self.ui_config.library {
+                    Some(dir) => {
+                        ui.label(dir.display().to_string())
+                            .on_hover_text("Covers here are used before downloading");
+                    }
+                    None => {
+                        ui.weak("none");
+                    }
+                }
+                if ui.button("📂 Choose...").clicked() {
+                    if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                        self.ui_config.library = Some(dir);
+                    }
+                }
+                if self.ui_config.library.is_some() && ui.button("✖").clicked() {
+                    self.ui_config.library = None;
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("ScreenScraper:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.ui_config.screenscraper_user)
+                        .hint_text("user")
+                        .desired_width(120.0),
+                )
+                .on_hover_text("Asked last, by ROM hash and game code; counts against your daily quota");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.ui_config.screenscraper_password)
+                        .hint_text("password")
+                        .password(true)
+                        .desired_width(120.0),
+                );
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Title database:");
+                match &self.ui_config.titles_db {
+                    Some(path) => {
+                        ui.label(path.display().to_string())
+                            .on_hover_text(
+                            "GameTDB titles in the log; ROMs without a known code are matched by title",
+                        );
+                    }
+                    None => {
+                        ui.weak("none");
+                    }
+                }
+                if ui.button("📂 Choose...").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("GameTDB database", &["xml", "txt"])
+                        .pick_file()
+                    {
+                        self.ui_config.titles_db = Some(path);
+                    }
+                }
+                if self.ui_config.titles_db.is_some() && ui.button("✖").clicked() {
+                    self.ui_config.titles_db = None;
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Cover pack:");
+                ui.add_enabled_ui(!self.running, |ui| {
+                    if ui
+                        .button("📦 Export...")
+                        .on_hover_text("Zip the covers on the selected drive to share them")
+                        .clicked()
+                    {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("Cover pack", &["zip"])
+                            .set_file_name("covers.zip")
+                            .save_file()
+                        {
+                            self.error = self.export_pack(&path).err().map(|e| e.to_string());
+                        }
+                    }
+                    if ui
+                        .button("📥 Import...")
+                        .on_hover_text("Copy a cover pack or web app ZIP export onto the selected drive")
+                        .clicked()
+                    {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("Cover pack", &["zip"])
+                            .pick_file()
+                        {
+                            self.error = self.import_pack(&path).err().map(|e| e.to_string());
+                        }
+                    }
+                });
+                ui.label("Existing covers:");
+                egui::ComboBox::from_id_salt("pack_conflict")
+                    .selected_text(self.ui_config.pack_conflict.as_str())
+                    .show_ui(ui, |ui| {
+                        for policy in ConflictPolicy::ALL {
+                            ui.selectable_value(
+                                &mut self.ui_config.pack_conflict,
+                                policy,
+                                policy.as_str(),
+                            );
+                        }
+                    });
             });
 
             ui.add_space(10.0);
@@ -426,6 +780,7 @@ impl eframe::App for GuiApp {
                 ui.separator();
                 ui.add_space(5.0);
 
+                let mut toggle: Option<((Platform, String), bool)> = None;
                 if !self.show_games_list {
                     // Output Log view
                     egui::ScrollArea::vertical()
@@ -466,6 +821,59 @@ impl eframe::App for GuiApp {
                         });
                 } else if let Some(stats) = &self.stats {
                     // Game Lists view
+                    if !stats.saved_games.is_empty() {
+                        if ui
+                            .button(if self.show_saved {
+                                "▼ Saved Games"
+                            } else {
+                                "► Saved Games"
+                            })
+                            .on_hover_text(format!(
+                                "Click to toggle ({} games)",
+                                stats.saved_games.len()
+                            ))
+                            .clicked()
+                        {
+                            self.show_saved = !self.show_saved;
+                        }
+
+                        if self.show_saved {
+                            ui.add_space(3.0);
+                            egui::ScrollArea::vertical()
+                                .id_salt("saved_games")
+                                .auto_shrink([false; 2])
+                                .show(ui, |ui| {
+                                    ui.set_min_width(ui.available_width());
+                                    for game in &stats.saved_games {
+                                        let mut line = format!(
+                                            "  • {} [{}] - {} ({})",
+                                            game.game_name,
+                                            game.game_code,
+                                            game.region,
+                                            game.art_kind.label()
+                                        );
+                                        let mut color = egui::Color32::from_rgb(100, 200, 100);
+                                        if let Some(metrics) = &game.metrics {
+                                            line.push_str(&format!(" - {}", metrics));
+                                            if metrics.needs_review() {
+                                                line.push_str(" ⚠");
+                                                color = egui::Color32::from_rgb(230, 150, 60);
+                                            }
+                                        }
+                                        let key = (game.platform, game.cover_name.clone());
+                                        let locked = self.locks.get(&key).copied().unwrap_or(false);
+                                        ui.horizontal(|ui| {
+                                            if lock_button(ui, locked) {
+                                                toggle = Some((key, !locked));
+                                            }
+                                            ui.colored_label(color, line);
+                                        });
+                                    }
+                                });
+                        }
+                        ui.add_space(8.0);
+                    }
+
                     if !stats.skipped_games.is_empty() {
                         if ui
                             .button(if self.show_skipped {
@@ -489,10 +897,61 @@ impl eframe::App for GuiApp {
                                 .show(ui, |ui| {
                                     ui.set_min_width(ui.available_width());
                                     for game in &stats.skipped_games {
-                                        ui.colored_label(
-                                            egui::Color32::from_rgb(200, 200, 100),
-                                            format!("  • {}", game),
-                                        );
+                                        let line = format!("  • {}", game);
+                                        let color = egui::Color32::from_rgb(200, 200, 100);
+                                        let Some(cover_name) = &game.cover_name else {
+                                            ui.colored_label(color, line);
+                                            continue;
+                                        };
+                                        let key = (game.platform, cover_name.clone());
+                                        let locked = self.locks.get(&key).copied().unwrap_or(false);
+                                        ui.horizontal(|ui| {
+                                            if lock_button(ui, locked) {
+                                                toggle = Some((key, !locked));
+                                            }
+                                            ui.colored_label(color, line);
+                                        });
+                                    }
+                                });
+                        }
+                    }
+
+                    if !stats.locked_games.is_empty() {
+                        ui.add_space(8.0);
+                        if ui
+                            .button(if self.show_locked {
+                                "▼ Kept Covers"
+                            } else {
+                                "► Kept Covers"
+                            })
+                            .on_hover_text(format!(
+                                "Locked or hand-edited covers left alone ({} games)",
+                                stats.locked_games.len()
+                            ))
+                            .clicked()
+                        {
+                            self.show_locked = !self.show_locked;
+                        }
+
+                        if self.show_locked {
+                            ui.add_space(3.0);
+                            egui::ScrollArea::vertical()
+                                .id_salt("locked_games")
+                                .auto_shrink([false; 2])
+                                .show(ui, |ui| {
+                                    ui.set_min_width(ui.available_width());
+                                    for game in &stats.locked_games {
+                                        let key = (game.platform, game.cover_name.clone());
+                                        let locked = self.locks.get(&key).copied().unwrap_or(true);
+                                        ui.horizontal(|ui| {
+                                            if lock_button(ui, locked) {
+                                                toggle = Some((key, !locked));
+                                            }
+                                            ui.colored_label(
+                                                egui::Color32::from_rgb(170, 150, 220),
+                                                game.to_string(),
+                                            );
+                                        });
                                     }
                                 });
                         }
@@ -530,12 +989,52 @@ impl eframe::App for GuiApp {
                                 });
                         }
                     }
+
+                    if !stats.title_matches.is_empty() {
+                        ui.add_space(8.0);
+                        if ui
+                            .button(if self.show_matched {
+                                "▼ Matched by Title"
+                            } else {
+                                "► Matched by Title"
+                            })
+                            .on_hover_text(format!(
+                                "ROMs without a usable game code ({} games)",
+                                stats.title_matches.len()
+                            ))
+                            .clicked()
+                        {
+                            self.show_matched = !self.show_matched;
+                        }
+
+                        if self.show_matched {
+                            ui.add_space(3.0);
+                            egui::ScrollArea::vertical()
+                                .id_salt("title_matches")
+                                .auto_shrink([false; 2])
+                                .show(ui, |ui| {
+                                    ui.set_min_width(ui.available_width());
+                                    for matched in &stats.title_matches {
+                                        ui.colored_label(
+                                            egui::Color32::from_rgb(120, 170, 230),
+                                            format!("  • {}", matched),
+                                        );
+                                    }
+                                });
+                        }
+                    }
                 } else {
                     ui.colored_label(
                         egui::Color32::GRAY,
                         "Game lists will appear here after processing completes...",
                     );
                 }
+
+                if let Some(((platform, cover_name), locked)) = toggle {
+                    if let Err(err) = self.set_lock(platform, cover_name, locked) {
+                        self.error = Some(err.to_string());
+                    }
+                }
             });
         });
 
@@ -552,6 +1051,20 @@ impl UiConfig {
             return Err(anyhow!("Selected drive does not exist"));
         }
 
+        let screenscraper = if self.screenscraper_user.is_empty() {
+            None
+        } else {
+            Some(
+                screenscraper_login(
+                    self.screenscraper_user.clone(),
+                    self.screenscraper_password.clone(),
+                    None,
+                    None,
+                )
+                .ok_or_else(|| anyhow!("This build has no ScreenScraper developer credentials"))?,
+            )
+        };
+
         Ok(Config {
             root,
             regions: vec![
@@ -566,17 +1079,69 @@ impl UiConfig {
                 "NL".to_string(),
                 "PT".to_string(),
             ],
+            art_kinds: if self.art_kind == ArtKind::Cover {
+                vec![ArtKind::Cover]
+            } else {
+                vec![self.art_kind, ArtKind::Cover]
+            },
             url_templates: vec![
-                "https://art.gametdb.com/ds/cover/{region}/{id}.png".to_string(),
-                "https://art.gametdb.com/ds/cover/{region}/{id}.jpg".to_string(),
+                "https://art.gametdb.com/ds/{kind}/{region}/{id}.png".to_string(),
+                "https://art.gametdb.com/ds/{kind}/{region}/{id}.jpg".to_string(),
             ],
+            selection: if self.best_quality {
+                SelectionStrategy::BestQuality
+            } else {
+                SelectionStrategy::FirstFound
+            },
+            parallel_probes: self.parallel_probes,
+            proxy_url: self.use_proxy.then(|| DEFAULT_PROXY_URL.to_string()),
+            libretro: self.use_libretro.then(|| DEFAULT_LIBRETRO_URL.to_string()),
+            screenscraper,
+            platforms: if self.include_gba {
+                vec![Platform::Nds, Platform::Gba]
+            } else {
+                vec![Platform::Nds]
+            },
+            library: self.library.clone(),
+            titles_db: self.titles_db.clone(),
+            gba_titles_db: None,
+            language: DEFAULT_LANGUAGE.to_string(),
+            title_match_threshold: DEFAULT_MATCH_THRESHOLD,
+            overrides: None,
             overwrite: self.overwrite,
+            force: (self.overwrite || self.refresh) && self.force,
+            refresh: self.refresh,
             timeout_secs: 15,
+            retries: 3,
             threads: None,
+            limits: ConcurrencyLimits {
+                max_concurrent: self.downloads,
+                per_host: self.per_host,
+                requests_per_second: (self.rps > 0.0).then_some(self.rps),
+            },
+            render: RenderOptions {
+                display: self.display,
+                linear_light: self.linear_light,
+                sharpen: self.sharpen,
+                compute_metrics: self.quality_report,
+            },
+            cache: CacheConfig::user_default(),
+            miss_ttl: DEFAULT_MISS_TTL,
+            recheck_missing: self.recheck_missing,
         })
     }
 }
 
+/// Padlock toggle for a cover in the game lists; returns whether it was clicked
+fn lock_button(ui: &mut egui::Ui, locked: bool) -> bool {
+    let (icon, hint) = if locked {
+        ("🔒", "Kept when overwriting - click to allow replacing it")
+    } else {
+        ("🔓", "Click to lock, so overwriting keeps this cover")
+    };
+    ui.small_button(icon).on_hover_text(hint).clicked()
+}
+
 pub fn load_logo_image() -> Option<egui::ColorImage> {
     let bytes = include_bytes!("../../../assets/icon.png");
     let image = image::load_from_memory(bytes).ok()?.into_rgba8();
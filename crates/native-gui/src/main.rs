@@ -4,19 +4,33 @@ mod gui;
 mod processing;
 mod updater;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
 use eframe::{egui, NativeOptions};
+use pico_cover_core::art::ArtKind;
+use pico_cover_core::cache::{self, DiskCache};
+use pico_cover_core::display::DisplayProfile;
+use pico_cover_core::errors::FetchErrorKind;
+use pico_cover_core::game::Platform;
+use pico_cover_core::http::ConcurrencyLimits;
+use pico_cover_core::image_processing::RenderOptions;
+use pico_cover_core::pack::{ConflictPolicy, CoverPack};
+use pico_cover_core::provenance::Provenance;
+use pico_cover_core::resample::Sharpening;
+use pico_cover_core::selection::SelectionStrategy;
+use pico_cover_core::source::{ScreenScraperLogin, DEFAULT_LIBRETRO_URL, DEFAULT_PROXY_URL};
+use pico_cover_core::titles::{TitleDb, DEFAULT_MATCH_THRESHOLD};
 
 #[derive(Parser, Debug)]
 #[command(
     name = "pico-cover",
-    about = "Fetch and downscale NDS cover art to 8bpp BMP for Pico Launcher."
+    about = "Fetch and downscale NDS and GBA cover art to 8bpp BMP for Pico Launcher."
 )]
 struct Args {
-    /// Root folder or drive containing NDS ROMs.
+    /// Root folder or drive containing NDS and GBA ROMs.
     #[arg(long, default_value = ".")]
     root: PathBuf,
 
@@ -32,26 +46,198 @@ struct Args {
     #[arg(long, default_value = "_pico/covers/nds")]
     output_subdir: String,
 
-    /// Custom URL templates; use {region} and {id} placeholders.
+    /// Custom URL templates; use {kind}, {region} and {id} placeholders.
     #[arg(
         long,
         value_delimiter = ';',
-        default_value = "https://art.gametdb.com/ds/cover/{region}/{id}.png;https://art.gametdb.com/ds/cover/{region}/{id}.jpg"
+        default_value = "https://art.gametdb.com/ds/{kind}/{region}/{id}.png;https://art.gametdb.com/ds/{kind}/{region}/{id}.jpg"
     )]
     url_templates: Vec<String>,
 
+    /// Probe all regions at once with HEAD requests and download only the winner.
+    #[arg(long, default_value_t = false)]
+    parallel_probes: bool,
+
+    /// PicoCover proxy used before GameTDB (serves NDS and GBA covers).
+    #[arg(long, default_value = DEFAULT_PROXY_URL)]
+    proxy_url: String,
+
+    /// Skip the PicoCover proxy and query GameTDB directly.
+    #[arg(long, default_value_t = false)]
+    no_proxy: bool,
+
+    /// libretro-thumbnails server, raw repository URL or local checkout, asked last by title.
+    #[arg(long, default_value = DEFAULT_LIBRETRO_URL)]
+    libretro: String,
+
+    /// Skip the libretro-thumbnails fallback.
+    #[arg(long, default_value_t = false)]
+    no_libretro: bool,
+
+    /// ScreenScraper account asked last, looked up by ROM hash and game code.
+    #[arg(long, requires = "screenscraper_password")]
+    screenscraper_user: Option<String>,
+
+    /// Password for --screenscraper-user.
+    #[arg(long, requires = "screenscraper_user")]
+    screenscraper_password: Option<String>,
+
+    /// ScreenScraper developer id (defaults to the one built in, if any).
+    #[arg(long)]
+    screenscraper_dev_id: Option<String>,
+
+    /// ScreenScraper developer password (defaults to the one built in, if any).
+    #[arg(long)]
+    screenscraper_dev_password: Option<String>,
+
+    /// ROM platforms to process (nds, gba); GBA covers need the proxy or libretro.
+    #[arg(long, value_delimiter = ',', default_value = "nds,gba")]
+    platforms: Vec<Platform>,
+
+    /// Folder of hand-picked covers that take priority over downloads.
+    #[arg(long)]
+    library: Option<PathBuf>,
+
+    /// Offline GameTDB database (dstdb.xml or dstdb.txt) for titles; unknown codes only get local art.
+    #[arg(long)]
+    titles_db: Option<PathBuf>,
+
+    /// DAT or title database for GBA ROMs (e.g. the No-Intro GBA DAT with serials).
+    #[arg(long)]
+    gba_titles_db: Option<PathBuf>,
+
+    /// Language for titles from the database (EN, JA, FR, DE, ...).
+    #[arg(long, default_value = "EN")]
+    language: String,
+
+    /// Per-game override file (JSON); also read from the config folder and `_pico/pico-cover-overrides.json`.
+    #[arg(long)]
+    overrides: Option<PathBuf>,
+
+    /// Minimum title similarity (0-1) for identifying ROMs without a usable code.
+    #[arg(long, default_value_t = DEFAULT_MATCH_THRESHOLD)]
+    match_threshold: f32,
+
+    /// Search the title database and exit.
+    #[arg(long, requires = "titles_db")]
+    find_title: Option<String>,
+
+    /// Show where a cover on the SD card came from and exit.
+    #[arg(long, value_name = "CODE")]
+    explain_cover: Option<String>,
+
+    /// Art kinds to try (order matters): cover, cover-m, cover-s, cover-hq, box3d, cart, full, full-hq.
+    #[arg(long, value_delimiter = ',', default_value = "cover")]
+    art_kinds: Vec<ArtKind>,
+
+    /// How to pick between covers found in several regions (first-found, best-quality, best-sharpness).
+    #[arg(long, default_value = "first-found")]
+    selection: SelectionStrategy,
+
     /// Overwrite existing BMPs instead of skipping.
     #[arg(long, default_value_t = false)]
     overwrite: bool,
 
+    /// Re-render only covers whose source image changed since they were made.
+    #[arg(long, default_value_t = false)]
+    refresh: bool,
+
+    /// With --overwrite or --refresh, also replace locked and hand-edited covers.
+    #[arg(long, default_value_t = false)]
+    force: bool,
+
+    /// Lock covers so --overwrite keeps them, then exit.
+    #[arg(long, value_delimiter = ',', value_name = "CODE")]
+    lock: Vec<String>,
+
+    /// Let --overwrite replace these covers even if edited by hand, then exit.
+    #[arg(long, value_delimiter = ',', value_name = "CODE")]
+    unlock: Vec<String>,
+
     /// Network timeout in seconds.
     #[arg(long, default_value_t = 15)]
     timeout_secs: u64,
 
-    /// Number of parallel download threads (defaults to CPU core count).
+    /// Retries for timeouts, connection failures and 5xx responses.
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+
+    /// Number of rendering threads (defaults to CPU core count).
     #[arg(long)]
     threads: Option<usize>,
 
+    /// Downloads in flight across all hosts.
+    #[arg(long, default_value_t = 16)]
+    downloads: usize,
+
+    /// Downloads in flight to a single host.
+    #[arg(long, default_value_t = 4)]
+    per_host: usize,
+
+    /// Maximum requests per second to a single host (unlimited if not set).
+    #[arg(long)]
+    rps: Option<f64>,
+
+    /// Screen to tune cover colours for (ds-phat, ds-lite, dsi, emulator).
+    #[arg(long, default_value = "emulator")]
+    display: DisplayProfile,
+
+    /// Resize in linear light instead of directly on sRGB values.
+    #[arg(long, default_value_t = false)]
+    linear_light: bool,
+
+    /// Sharpening after resize (off, soft, balanced, crisp, crisp-text, custom:SIGMA,AMOUNT,THRESHOLD).
+    #[arg(long, default_value = "off")]
+    sharpen: Sharpening,
+
+    /// Compute PSNR/SSIM for each cover and report the ones worth re-rendering.
+    #[arg(long, default_value_t = false)]
+    metrics: bool,
+
+    /// Folder for cached original downloads (defaults to the user cache folder).
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// Always download originals instead of using the cache.
+    #[arg(long, default_value_t = false)]
+    no_cache: bool,
+
+    /// Size limit of the download cache in MiB.
+    #[arg(long, default_value_t = 512)]
+    cache_max_mb: u64,
+
+    /// Days before a cached original is revalidated with the server.
+    #[arg(long, default_value_t = 30)]
+    cache_max_age_days: u64,
+
+    /// Days to trust a "not found" result before asking a source again.
+    #[arg(long, default_value_t = 7)]
+    miss_ttl_days: u64,
+
+    /// Ignore remembered "not found" results and query every source again.
+    #[arg(long, default_value_t = false)]
+    recheck_missing: bool,
+
+    /// Trim the download cache to its size limit and exit.
+    #[arg(long, default_value_t = false)]
+    prune_cache: bool,
+
+    /// Zip the covers on the SD card into a cover pack and exit.
+    #[arg(long, conflicts_with = "import_pack")]
+    export_pack: Option<PathBuf>,
+
+    /// Build the exported pack from cached originals instead of the SD card.
+    #[arg(long, default_value_t = false, requires = "export_pack")]
+    pack_from_cache: bool,
+
+    /// Copy the covers in a cover pack onto the SD card and exit.
+    #[arg(long)]
+    import_pack: Option<PathBuf>,
+
+    /// Covers already on the card when importing (keep, replace, newest).
+    #[arg(long, default_value = "keep")]
+    pack_conflict: ConflictPolicy,
+
     /// Launch with CLI instead of GUI.
     #[arg(long, default_value_t = false)]
     cli: bool,
@@ -61,10 +247,70 @@ struct Args {
 pub struct Config {
     pub root: PathBuf,
     pub regions: Vec<String>,
+    pub art_kinds: Vec<ArtKind>,
     pub url_templates: Vec<String>,
+    pub selection: SelectionStrategy,
+    pub parallel_probes: bool,
+    /// PicoCover proxy base URL; `None` queries GameTDB only
+    pub proxy_url: Option<String>,
+    /// libretro-thumbnails URL or local checkout; `None` disables it
+    pub libretro: Option<String>,
+    /// ScreenScraper account; `None` leaves it out
+    pub screenscraper: Option<ScreenScraperLogin>,
+    pub platforms: Vec<Platform>,
+    pub library: Option<PathBuf>,
+    /// GameTDB export used for titles and to keep unknown codes off the network
+    pub titles_db: Option<PathBuf>,
+    pub gba_titles_db: Option<PathBuf>,
+    pub language: String,
+    /// Minimum similarity for matching ROMs without a usable code by title
+    pub title_match_threshold: f32,
+    /// Override file merged over the config folder and SD card ones
+    pub overrides: Option<PathBuf>,
     pub overwrite: bool,
+    /// Overwrite locked and hand-edited covers too
+    pub force: bool,
+    /// Revalidate recorded covers instead of looking up new ones
+    pub refresh: bool,
     pub timeout_secs: u64,
+    pub retries: u32,
     pub threads: Option<usize>,
+    pub limits: ConcurrencyLimits,
+    pub render: RenderOptions,
+    pub cache: Option<CacheConfig>,
+    pub miss_ttl: Duration,
+    pub recheck_missing: bool,
+}
+
+/// Where and how long original downloads are kept
+#[derive(Clone)]
+pub struct CacheConfig {
+    pub dir: PathBuf,
+    pub max_bytes: u64,
+    pub max_age: Duration,
+}
+
+impl CacheConfig {
+    /// Cache in the user cache folder with the default limits
+    pub fn user_default() -> Option<Self> {
+        Some(Self {
+            dir: dirs::cache_dir()?.join("pico-cover"),
+            max_bytes: cache::DEFAULT_MAX_BYTES,
+            max_age: cache::DEFAULT_MAX_AGE,
+        })
+    }
+
+    /// File remembering games no source had a cover for
+    pub fn misses_path(&self) -> PathBuf {
+        self.dir.join("misses.json")
+    }
+
+    /// Open the cache folder
+    pub fn open(&self) -> Result<DiskCache> {
+        Ok(DiskCache::open(&self.dir)?
+            .with_max_bytes(self.max_bytes)
+            .with_max_age(self.max_age))
+    }
 }
 
 fn main() -> Result<()> {
@@ -78,20 +324,305 @@ fn main() -> Result<()> {
 }
 
 fn run_cli(args: Args) -> Result<()> {
+    let cache = if args.no_cache {
+        None
+    } else {
+        args.cache_dir
+            .or_else(|| CacheConfig::user_default().map(|c| c.dir))
+            .map(|dir| CacheConfig {
+                dir,
+                max_bytes: args.cache_max_mb * 1024 * 1024,
+                max_age: Duration::from_secs(args.cache_max_age_days * 24 * 60 * 60),
+            })
+    };
+
+    if args.prune_cache {
+        let Some(cache) = cache else {
+            println!("Download cache is disabled.");
+            return Ok(());
+        };
+        let stats = cache.open()?.prune()?;
+        println!(
+            "Pruned {} ({} removed, {:.1} MiB freed, {} left using {:.1} MiB)",
+            cache.dir.display(),
+            stats.removed,
+            stats.freed_bytes as f64 / (1024.0 * 1024.0),
+            stats.remaining,
+            stats.remaining_bytes as f64 / (1024.0 * 1024.0)
+        );
+        return Ok(());
+    }
+
+    let render = RenderOptions {
+        display: args.display,
+        linear_light: args.linear_light,
+        sharpen: args.sharpen,
+        compute_metrics: args.metrics,
+    };
+
+    if let Some(path) = &args.export_pack {
+        let mut pack = CoverPack::new();
+        if args.pack_from_cache {
+            let cache = cache.context("--pack-from-cache needs the download cache")?;
+            pack.add_cache(&cache.open()?, &render)?;
+        } else {
+            pack.add_card(&args.root)?;
+        }
+        pack.write(path)
+            .with_context(|| format!("Writing {}", path.display()))?;
+        println!("Exported {} covers to {}", pack.len(), path.display());
+        return Ok(());
+    }
+
+    if let Some(path) = &args.import_pack {
+        let (pack, invalid) =
+            CoverPack::read(path).with_context(|| format!("Reading {}", path.display()))?;
+        let stats = pack.import(&args.root, args.pack_conflict)?;
+        println!(
+            "Imported {} (added={} replaced={} kept={} locked={})",
+            path.display(),
+            stats.added,
+            stats.replaced,
+            stats.kept,
+            stats.locked
+        );
+        for name in invalid {
+            println!("  ⚠ Ignored {}", name);
+        }
+        return Ok(());
+    }
+
+    if !args.lock.is_empty() || !args.unlock.is_empty() {
+        let mut provenance = Provenance::load(&args.root)?;
+        let changes = args.lock.iter().map(|name| (name, true));
+        for (name, locked) in changes.chain(args.unlock.iter().map(|name| (name, false))) {
+            let platforms: Vec<Platform> = Platform::ALL
+                .into_iter()
+                .filter(|platform| {
+                    let cover = args.root.join(platform.cover_dir());
+                    cover.join(format!("{}.bmp", name)).is_file()
+                        || provenance.get(*platform, name).is_some()
+                })
+                .collect();
+            if platforms.is_empty() {
+                println!("No cover named {} on {}.", name, args.root.display());
+            }
+            for platform in platforms {
+                provenance.set_lock(platform, name, Some(locked));
+                let icon = if locked {
+                    "🔒 Locked"
+                } else {
+                    "🔓 Unlocked"
+                };
+                println!("{} {}/{}", icon, platform, name);
+            }
+        }
+        provenance.save(&args.root)?;
+        return Ok(());
+    }
+
+    if let Some(name) = &args.explain_cover {
+        explain_cover(&args.root, name, &render)?;
+        return Ok(());
+    }
+
+    if let (Some(query), Some(path)) = (&args.find_title, &args.titles_db) {
+        let titles = TitleDb::load(Platform::Nds, path)
+            .with_context(|| format!("Loading {}", path.display()))?
+            .with_language(args.language.as_str());
+        let found = titles.search(query, 50);
+        if found.is_empty() {
+            println!("No titles match \"{}\".", query);
+        }
+        for game in found {
+            let mut details = Vec::new();
+            details.extend(game.region.clone());
+            details.extend(game.publisher.clone());
+            details.extend(game.release.map(|date| date.to_string()));
+            let title = game.title(titles.language()).unwrap_or_default();
+            if details.is_empty() {
+                println!("{}  {}", game.code, title);
+            } else {
+                println!("{}  {}  ({})", game.code, title, details.join(", "));
+            }
+        }
+        return Ok(());
+    }
+
+    let screenscraper = match (args.screenscraper_user, args.screenscraper_password) {
+        (Some(user), Some(password)) => Some(
+            processing::screenscraper_login(
+                user,
+                password,
+                args.screenscraper_dev_id,
+                args.screenscraper_dev_password,
+            )
+            .context(
+                "ScreenScraper needs developer credentials (--screenscraper-dev-id and --screenscraper-dev-password)",
+            )?,
+        ),
+        _ => None,
+    };
+
     let config = Config {
         root: args.root,
         regions: args.regions,
+        art_kinds: args.art_kinds,
         url_templates: args.url_templates,
+        selection: args.selection,
+        parallel_probes: args.parallel_probes,
+        proxy_url: (!args.no_proxy).then_some(args.proxy_url),
+        libretro: (!args.no_libretro).then_some(args.libretro),
+        screenscraper,
+        platforms: args.platforms,
+        library: args.library,
+        titles_db: args.titles_db,
+        gba_titles_db: args.gba_titles_db,
+        language: args.language,
+        title_match_threshold: args.match_threshold,
+        overrides: args.overrides,
         overwrite: args.overwrite,
+        force: args.force,
+        refresh: args.refresh,
         timeout_secs: args.timeout_secs,
+        retries: args.retries,
         threads: args.threads,
+        limits: ConcurrencyLimits {
+            max_concurrent: args.downloads,
+            per_host: args.per_host,
+            requests_per_second: args.rps,
+        },
+        render,
+        cache,
+        miss_ttl: Duration::from_secs(args.miss_ttl_days * 24 * 60 * 60),
+        recheck_missing: args.recheck_missing,
     };
     let logger = |msg: String| println!("{}", msg);
     let stats = processing::process_root(&config, logger)?;
-    println!(
-        "Done. Processed={} Saved={} Skipped={} Errors={}",
-        stats.processed, stats.saved, stats.skipped, stats.errors
-    );
+    if config.refresh {
+        println!(
+            "Done. Checked={} Upgraded={} Unchanged={} Errors={}",
+            stats.processed, stats.saved, stats.skipped, stats.errors
+        );
+        if !stats.saved_games.is_empty() {
+            println!("Upgraded ({}):", stats.saved_games.len());
+            for game in &stats.saved_games {
+                println!("  ⬆ {} [{}]", game.game_name, game.cover_name);
+            }
+        }
+    } else {
+        println!(
+            "Done. Processed={} Saved={} Skipped={} Errors={}",
+            stats.processed, stats.saved, stats.skipped, stats.errors
+        );
+    }
+
+    if !stats.failed_games.is_empty() {
+        let network = stats
+            .failed_games
+            .iter()
+            .filter(|game| game.kind == Some(FetchErrorKind::Transient))
+            .count();
+        println!(
+            "Failed ({}, {} network failures - re-run to retry):",
+            stats.failed_games.len(),
+            network
+        );
+        for game in &stats.failed_games {
+            println!("  ❌ {}", game);
+        }
+    }
+
+    if !stats.locked_games.is_empty() {
+        println!(
+            "Kept ({}, use --force to replace):",
+            stats.locked_games.len()
+        );
+        for locked in &stats.locked_games {
+            println!("  🔒 {}", locked);
+        }
+    }
+
+    if !stats.title_matches.is_empty() {
+        println!("Matched by title ({}):", stats.title_matches.len());
+        for matched in &stats.title_matches {
+            println!("  🔎 {}", matched);
+        }
+    }
+
+    let flagged: Vec<_> = stats
+        .saved_games
+        .iter()
+        .filter_map(|game| Some((game, game.metrics?)))
+        .filter(|(_, metrics)| metrics.needs_review())
+        .collect();
+    if !flagged.is_empty() {
+        println!("Covers worth re-rendering ({}):", flagged.len());
+        for (game, metrics) in flagged {
+            println!("  ⚠ {} [{}] - {}", game.game_name, game.game_code, metrics);
+        }
+    }
+    Ok(())
+}
+
+/// Print the provenance records of a cover and whether the card still matches them
+fn explain_cover(root: &Path, name: &str, render: &RenderOptions) -> Result<()> {
+    let provenance = Provenance::load(root)?;
+    let mut found = false;
+    for platform in Platform::ALL {
+        let record = provenance
+            .get(platform, name)
+            .or_else(|| provenance.get(platform, &name.to_ascii_uppercase()));
+        let cover_name = record.map_or(name, |record| record.cover_name.as_str());
+        let cover = root
+            .join(platform.cover_dir())
+            .join(format!("{}.bmp", cover_name));
+        let Some(record) = record else {
+            if cover.is_file() {
+                found = true;
+                println!(
+                    "{}: not made by PicoCover (no provenance record)",
+                    cover.display()
+                );
+            }
+            continue;
+        };
+        found = true;
+        println!("{}", cover.display());
+        println!("  Game:     {}", record.game_name);
+        if let Some(code) = &record.game_code {
+            println!("  Code:     {}", code);
+        }
+        println!("  Source:   {} ({})", record.source, record.origin);
+        println!(
+            "  Art:      {}, region {}",
+            record.art_kind.label(),
+            record.region.as_deref().unwrap_or("-")
+        );
+        println!("  Image:    sha256 {}", record.source_sha256);
+        println!(
+            "  Render:   {}, linear light {}, sharpen {}",
+            record.render.display.label(),
+            record.render.linear_light,
+            record.render.sharpen.label()
+        );
+        println!("  Tool:     {}", record.tool);
+        let status = match std::fs::read(&cover) {
+            Err(_) => "missing from the card",
+            Ok(bmp) if !record.matches(&bmp) => "changed outside PicoCover",
+            Ok(_) if !record.is_current(render) => "stale (other version or render settings)",
+            Ok(_) => "current",
+        };
+        println!("  Status:   {}", status);
+        match provenance.lock_state(platform, cover_name) {
+            Some(true) => println!("  Lock:     locked"),
+            Some(false) => println!("  Lock:     unlocked (may be overwritten after edits)"),
+            None => {}
+        }
+    }
+    if !found {
+        println!("No cover named {} on {}.", name, root.display());
+    }
     Ok(())
 }
 
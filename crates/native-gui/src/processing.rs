@@ -1,324 +1,238 @@
-use std::fs::File;
-use std::io::{Read, Write};
-use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use color_quant::NeuQuant;
-use image::imageops::{replace, resize, FilterType};
-use image::{DynamicImage, ImageBuffer, Rgba};
-use rayon::prelude::*;
-use reqwest::blocking::Client;
-use walkdir::WalkDir;
+use pico_cover_core::art::ArtKind;
+use pico_cover_core::cache::CachingClient;
+use pico_cover_core::game::Platform;
+use pico_cover_core::http::{
+    HttpClient, LimitedClient, ReqwestClient, RetryPolicy, RetryingClient,
+};
+use pico_cover_core::pipeline::{Pipeline, PipelineOptions};
+use pico_cover_core::source::{
+    ChainedSource, GameTdbSource, LibretroSource, LocalFolderSource, Logger, MissCache,
+    OverrideSource, Overrides, ProxySource, ScreenScraperLogin, ScreenScraperSource,
+};
+use pico_cover_core::titles::TitleDb;
+use tokio::runtime::Runtime;
+
+pub use pico_cover_core::pipeline::ProcessStats;
+
+/// Override file name looked for in the config folder and on the SD card
+const OVERRIDES_FILE: &str = "pico-cover-overrides.json";
 
 use crate::Config;
 
-#[derive(Default, Clone)]
-pub struct ProcessStats {
-    pub processed: u32,
-    pub saved: u32,
-    pub skipped: u32,
-    pub errors: u32,
-    pub skipped_games: Vec<String>,
-    pub failed_games: Vec<String>,
-}
-
-pub fn process_root(config: &Config, log: impl Fn(String) + Send + Sync) -> Result<ProcessStats> {
-    let client = Client::builder()
-        .user_agent("pico-cover/0.1")
-        .timeout(std::time::Duration::from_secs(config.timeout_secs))
-        .build()
-        .context("Building HTTP client")?;
-
-    let output_dir = config.root.join("_pico/covers/nds");
-    std::fs::create_dir_all(&output_dir).context("Creating output directory")?;
-
-    let stats = Arc::new(Mutex::new(ProcessStats::default()));
-    let skipped_games = Arc::new(Mutex::new(Vec::new()));
-    let failed_games = Arc::new(Mutex::new(Vec::new()));
-    let log = Arc::new(log);
-    let client = Arc::new(client);
-    let config = Arc::new(config.clone());
-    let output_dir = Arc::new(output_dir);
-    let skipped_games_clone = Arc::clone(&skipped_games);
-    let failed_games_clone = Arc::clone(&failed_games);
-
-    // Collect all NDS files first
-    let mut nds_files = Vec::new();
-    for entry in WalkDir::new(&config.root)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        if !entry.file_type().is_file() {
-            continue;
+pub fn process_root(
+    config: &Config,
+    log: impl Fn(String) + Send + Sync + 'static,
+) -> Result<ProcessStats> {
+    let log: Logger = Arc::new(log);
+
+    // Limits sit below the retries so backoff sleeps don't hold a download slot
+    let mut client: Arc<dyn HttpClient> = Arc::new(ReqwestClient::new(
+        std::time::Duration::from_secs(config.timeout_secs),
+    )?);
+    client = Arc::new(LimitedClient::new(client, config.limits));
+    client = Arc::new(RetryingClient::new(
+        client,
+        RetryPolicy::default().with_max_retries(config.retries),
+    ));
+    // ScreenScraper URLs carry credentials and its answers carry live quotas
+    let uncached = Arc::clone(&client);
+    let refresh_client = Arc::clone(&client);
+    let mut disk_cache = None;
+    if let Some(cache) = &config.cache {
+        match cache.open() {
+            Ok(disk) => {
+                client = Arc::new(CachingClient::new(client, disk.clone()));
+                disk_cache = Some(disk);
+            }
+            Err(err) => log(format!(
+                "⚠ Download cache unavailable ({}): {}",
+                cache.dir.display(),
+                err
+            )),
         }
-
-        let path = entry.path();
-        if !is_nds(path) {
+    }
+    let misses = Arc::new(
+        match &config.cache {
+            Some(cache) => MissCache::load(cache.misses_path(), config.miss_ttl),
+            None => MissCache::new(config.miss_ttl),
+        }
+        .with_recheck(config.recheck_missing),
+    );
+    let overrides = Arc::new(load_overrides(config, &log));
+    let sources = Arc::new(OverrideSource::new(
+        Arc::new(
+            build_sources(config, Arc::clone(&client), uncached)
+                .with_miss_cache(Arc::clone(&misses)),
+        ),
+        Arc::clone(&overrides),
+        client,
+    ));
+    let runtime = Runtime::new().context("Starting async runtime")?;
+
+    let mut options = PipelineOptions {
+        platforms: config.platforms.clone(),
+        overwrite: config.overwrite,
+        force: config.force,
+        render: config.render.clone(),
+        title_match_threshold: config.title_match_threshold,
+        ..PipelineOptions::default()
+    };
+    options.fetch_workers = options.fetch_workers.max(config.limits.max_concurrent);
+    if let Some(threads) = config.threads {
+        options.render_workers = threads;
+    }
+    let mut pipeline = Pipeline::new(sources)
+        .with_options(options)
+        .with_overrides(overrides)
+        .with_logger(Arc::clone(&log));
+    let databases = [
+        (Platform::Nds, &config.titles_db),
+        (Platform::Gba, &config.gba_titles_db),
+    ];
+    for (platform, path) in databases {
+        let Some(path) = path else {
             continue;
+        };
+        match TitleDb::load(platform, path) {
+            Ok(titles) => {
+                log(format!(
+                    "📚 Loaded {} titles from {}",
+                    titles.len(),
+                    path.display()
+                ));
+                pipeline =
+                    pipeline.with_titles(Arc::new(titles.with_language(config.language.as_str())));
+            }
+            Err(err) => log(format!(
+                "⚠ Title database unavailable ({}): {}",
+                path.display(),
+                err
+            )),
         }
-
-        nds_files.push(path.to_path_buf());
     }
-
-    // Process files in parallel with thread pool sized to CPU cores (or custom count)
-    let num_threads = config.threads.unwrap_or_else(num_cpus::get);
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(num_threads)
-        .build()
-        .context("Building thread pool")?
-        .install(|| {
-            nds_files.par_iter().for_each(|path| {
-                let game_name = path
-                    .file_stem()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("Unknown")
-                    .to_string();
-
-                {
-                    let mut stats = stats.lock().unwrap();
-                    stats.processed += 1;
-                }
-
-                match handle_file(path, &output_dir, &config, &client, &log) {
-                    Ok(true) => {
-                        let mut stats = stats.lock().unwrap();
-                        stats.saved += 1;
-                    }
-                    Ok(false) => {
-                        let mut stats = stats.lock().unwrap();
-                        stats.skipped += 1;
-                        let mut skipped = skipped_games_clone.lock().unwrap();
-                        skipped.push(game_name);
-                    }
-                    Err(err) => {
-                        let mut stats = stats.lock().unwrap();
-                        stats.errors += 1;
-                        let mut failed = failed_games_clone.lock().unwrap();
-                        failed.push(game_name);
-                        log(format!("❌ Error {}: {}", path.display(), err));
-                    }
-                }
-            });
-        });
-
-    let mut final_stats = stats.lock().unwrap().clone();
-    final_stats.skipped_games = skipped_games.lock().unwrap().clone();
-    final_stats.failed_games = failed_games.lock().unwrap().clone();
-    Ok(final_stats)
-}
-
-fn is_nds(path: &Path) -> bool {
-    path.extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| matches_ignore_ascii_case(ext, "nds"))
-        .unwrap_or(false)
-}
-
-fn matches_ignore_ascii_case(value: &str, needle: &str) -> bool {
-    value.len() == needle.len()
-        && value
-            .chars()
-            .zip(needle.chars())
-            .all(|(a, b)| a.eq_ignore_ascii_case(&b))
-}
-
-fn handle_file(
-    path: &Path,
-    output_dir: &Path,
-    config: &Config,
-    client: &Client,
-    log: &Arc<impl Fn(String) + Send + Sync + ?Sized>,
-) -> Result<bool> {
-    let game_code = match read_game_code(path)? {
-        Some(code) => code,
-        None => return Ok(false),
+    let stats = if config.refresh {
+        // Revalidation has to reach the servers, not the download cache
+        runtime.block_on(pipeline.refresh(&config.root, refresh_client))?
+    } else {
+        runtime.block_on(pipeline.run(&config.root))?
     };
 
-    let game_name = path
-        .file_stem()
-        .and_then(|n| n.to_str())
-        .unwrap_or("Unknown");
-
-    let target = output_dir.join(format!("{game_code}.bmp"));
-    if target.exists() && !config.overwrite {
-        log(format!(
-            "⏭ Skipped {} [{}] - already exists",
-            game_name, game_code
-        ));
-        return Ok(false);
+    if let Err(err) = misses.save() {
+        log(format!("⚠ Saving missing cover list failed: {}", err));
     }
 
-    let image = match fetch_cover(
-        &game_code,
-        game_name,
-        &config.regions,
-        &config.url_templates,
-        client,
-        log,
-    ) {
-        Some(img) => img,
-        None => {
-            log(format!(
-                "❌ Not found {} [{}] - no covers available",
-                game_name, game_code
-            ));
-            return Ok(false);
+    // Keep the cache within its size limit after every run
+    if let Some(disk) = disk_cache {
+        if let Err(err) = disk.prune() {
+            log(format!("⚠ Pruning download cache failed: {}", err));
         }
-    };
+    }
+    Ok(stats)
+}
 
-    let bmp_bytes = render_cover_bmp(image).context("rendering BMP")?;
-    let mut file = File::create(&target).context("creating output file")?;
-    file.write_all(&bmp_bytes).context("writing BMP")?;
-    log(format!("💾 Stored {} [{}]", game_name, game_code));
-    Ok(true)
+/// Combine a user's ScreenScraper login with developer credentials
+///
+/// Developer credentials not given explicitly come from the
+/// `SCREENSCRAPER_DEVID` and `SCREENSCRAPER_DEVPASS` variables at build time.
+pub fn screenscraper_login(
+    user: String,
+    password: String,
+    dev_id: Option<String>,
+    dev_password: Option<String>,
+) -> Option<ScreenScraperLogin> {
+    Some(ScreenScraperLogin {
+        dev_id: dev_id.or_else(|| option_env!("SCREENSCRAPER_DEVID").map(str::to_string))?,
+        dev_password: dev_password
+            .or_else(|| option_env!("SCREENSCRAPER_DEVPASS").map(str::to_string))?,
+        user,
+        password,
+    })
 }
 
-fn read_game_code(path: &Path) -> Result<Option<String>> {
-    let mut file = File::open(path).context("opening NDS file")?;
-    let mut header = [0u8; 16];
-    if let Err(err) = file.read_exact(&mut header) {
-        if err.kind() == std::io::ErrorKind::UnexpectedEof {
-            return Ok(None);
-        }
-        return Err(err).context("reading NDS header");
+/// Override files, in the order later ones win: user config folder, SD card, `--overrides`
+fn override_paths(config: &Config) -> Vec<(PathBuf, bool)> {
+    let mut paths = Vec::new();
+    if let Some(dir) = dirs::config_dir() {
+        paths.push((dir.join("pico-cover").join(OVERRIDES_FILE), false));
     }
-
-    let code_bytes = &header[0x0C..0x10];
-    if !code_bytes.iter().all(|b| b.is_ascii_alphanumeric()) {
-        return Ok(None);
+    paths.push((config.root.join("_pico").join(OVERRIDES_FILE), false));
+    if let Some(path) = &config.overrides {
+        paths.push((path.clone(), true));
     }
-
-    let code = String::from_utf8_lossy(code_bytes).to_string();
-    Ok(Some(code))
+    paths
 }
 
-fn fetch_cover(
-    game_code: &str,
-    game_name: &str,
-    regions: &[String],
-    templates: &[String],
-    client: &Client,
-    log: &Arc<impl Fn(String) + Send + Sync + ?Sized>,
-) -> Option<DynamicImage> {
-    for region in regions {
-        log(format!(
-            "🔍 Checking {} [{}] - {}",
-            game_name, game_code, region
-        ));
-        for template in templates {
-            let url = template
-                .replace("{region}", region)
-                .replace("{id}", game_code);
-            match client.get(&url).send() {
-                Ok(resp) if resp.status().is_success() => match resp.bytes() {
-                    Ok(bytes) => match image::load_from_memory(&bytes) {
-                        Ok(img) => {
-                            log(format!(
-                                "✅ Found {} [{}] - {}",
-                                game_name, game_code, region
-                            ));
-                            return Some(img);
-                        }
-                        Err(_) => {
-                            // Skip invalid image, try next URL
-                        }
-                    },
-                    Err(_) => {
-                        // Skip read error, try next URL
-                    }
-                },
-                Ok(resp) if resp.status().as_u16() == 404 => {
-                    // 404 is expected, skip to next template
-                }
-                Ok(_resp) => {
-                    // Other HTTP errors, skip to next template
-                }
-                Err(_) => {
-                    // Network error, skip to next template
-                }
+/// Merge every override file that exists; only a missing `--overrides` file is reported
+fn load_overrides(config: &Config, log: &Logger) -> Overrides {
+    let mut overrides = Overrides::new();
+    for (path, explicit) in override_paths(config) {
+        if !explicit && !path.is_file() {
+            continue;
+        }
+        match Overrides::load(&path) {
+            Ok(loaded) => {
+                log(format!(
+                    "📝 Loaded {} overrides from {}",
+                    loaded.len(),
+                    path.display()
+                ));
+                overrides.merge(loaded);
             }
+            Err(err) => log(format!(
+                "⚠ Overrides unavailable ({}): {}",
+                path.display(),
+                err
+            )),
         }
     }
-
-    None
+    overrides
 }
 
-fn render_cover_bmp(image: DynamicImage) -> Result<Vec<u8>> {
-    let source = image.to_rgba8();
-    let resized = resize(&source, 106, 96, FilterType::Lanczos3);
-
-    let mut canvas: ImageBuffer<Rgba<u8>, Vec<u8>> =
-        ImageBuffer::from_pixel(128, 96, Rgba([0, 0, 0, 255]));
-    replace(&mut canvas, &resized, 0, 0);
-
-    let rgba_data: Vec<u8> = canvas
-        .pixels()
-        .flat_map(|p| [p[0], p[1], p[2], p[3]])
-        .collect();
-
-    let quantizer = NeuQuant::new(10, 256, &rgba_data);
-    let palette = quantizer.color_map_rgb();
-
-    let mut indices = Vec::with_capacity(128 * 96);
-    for chunk in rgba_data.chunks(4) {
-        indices.push(quantizer.index_of(chunk) as u8);
+/// Build the cover source chain for the native app, in priority order:
+/// local library, PicoCover proxy, GameTDB directly, libretro-thumbnails by title,
+/// then ScreenScraper, which has daily quotas
+fn build_sources(
+    config: &Config,
+    client: Arc<dyn HttpClient>,
+    uncached: Arc<dyn HttpClient>,
+) -> ChainedSource {
+    let mut sources = ChainedSource::new();
+    if let Some(library) = &config.library {
+        sources.push(Box::new(LocalFolderSource::new(library)));
     }
-
-    Ok(write_paletted_bmp(128, 96, &palette, &indices))
-}
-
-fn write_paletted_bmp(width: u32, height: u32, palette: &[u8], indices: &[u8]) -> Vec<u8> {
-    let palette_colors = palette.len() / 3;
-    let palette_entries = 256usize;
-
-    let row_padding = (4 - (width as usize % 4)) % 4;
-    let row_size = width as usize + row_padding;
-    let image_size = row_size * height as usize;
-    let header_size = 14 + 40 + palette_entries * 4;
-    let file_size = header_size + image_size;
-
-    let mut data = Vec::with_capacity(file_size);
-
-    data.extend_from_slice(b"BM");
-    data.extend_from_slice(&(file_size as u32).to_le_bytes());
-    data.extend_from_slice(&0u16.to_le_bytes());
-    data.extend_from_slice(&0u16.to_le_bytes());
-    data.extend_from_slice(&(header_size as u32).to_le_bytes());
-
-    data.extend_from_slice(&40u32.to_le_bytes());
-    data.extend_from_slice(&(width as i32).to_le_bytes());
-    data.extend_from_slice(&(height as i32).to_le_bytes());
-    data.extend_from_slice(&1u16.to_le_bytes());
-    data.extend_from_slice(&8u16.to_le_bytes());
-    data.extend_from_slice(&0u32.to_le_bytes());
-    data.extend_from_slice(&(image_size as u32).to_le_bytes());
-    data.extend_from_slice(&0i32.to_le_bytes());
-    data.extend_from_slice(&0i32.to_le_bytes());
-    data.extend_from_slice(&(palette_entries as u32).to_le_bytes());
-    data.extend_from_slice(&0u32.to_le_bytes());
-
-    for idx in 0..palette_entries {
-        if idx < palette_colors {
-            let base = idx * 3;
-            data.push(palette[base + 2]);
-            data.push(palette[base + 1]);
-            data.push(palette[base]);
+    if let Some(proxy_url) = &config.proxy_url {
+        // The proxy only serves front covers, so it can't stand in for
+        // GameTDB when other NDS art kinds are preferred
+        let platforms: &[Platform] = if config.art_kinds.first() == Some(&ArtKind::Cover) {
+            &Platform::ALL
         } else {
-            data.extend_from_slice(&[0u8, 0u8, 0u8]);
-        }
-        data.push(0u8);
+            &[Platform::Gba]
+        };
+        sources.push(Box::new(
+            ProxySource::new(Arc::clone(&client), proxy_url.as_str()).for_platforms(platforms),
+        ));
     }
-
-    let width_usize = width as usize;
-    for row in (0..height as usize).rev() {
-        let start = row * width_usize;
-        let end = start + width_usize;
-        data.extend_from_slice(&indices[start..end]);
-        if row_padding > 0 {
-            data.extend(std::iter::repeat_n(0u8, row_padding));
+    sources.push(Box::new(
+        GameTdbSource::new(Arc::clone(&client))
+            .with_regions(config.regions.clone())
+            .with_templates(config.url_templates.clone())
+            .with_art_kinds(config.art_kinds.clone())
+            .with_selection(config.selection)
+            .with_parallel_probes(config.parallel_probes),
+    ));
+    if let Some(libretro) = &config.libretro {
+        if libretro.starts_with("http://") || libretro.starts_with("https://") {
+            sources.push(Box::new(LibretroSource::remote(client, libretro.as_str())));
+        } else {
+            sources.push(Box::new(LibretroSource::local(libretro)));
         }
     }
-
-    data
+    if let Some(login) = &config.screenscraper {
+        sources.push(Box::new(ScreenScraperSource::new(uncached, login.clone())));
+    }
+    sources
 }
@@ -0,0 +1,133 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use pico_cover_core::cache::DiskCache;
+use pico_cover_core::game::Platform;
+use pico_cover_core::http::{HttpClient, ReqwestClient, RetryPolicy, RetryingClient};
+use pico_cover_core::source::{ChainedSource, GameTdbSource, LocalFolderSource, ProxySource};
+
+mod server;
+
+use server::AppState;
+
+#[derive(Parser, Debug)]
+#[command(
+    author,
+    version,
+    about = "Self-hosted PicoCover proxy serving /nds/{id}, /gba/{id} and /stats."
+)]
+struct Args {
+    /// Address to listen on (the Worker dev server uses port 8787).
+    #[arg(long, default_value = "127.0.0.1:8787")]
+    bind: SocketAddr,
+
+    /// Folder of covers served first (`<CODE>.png`, `nds/<CODE>.png`, `gba/<CODE>.jpg`, ...).
+    #[arg(long)]
+    library: Option<PathBuf>,
+
+    /// Folder for covers fetched from upstream (defaults to the user cache folder).
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// Don't keep fetched covers on disk.
+    #[arg(long, default_value_t = false)]
+    no_cache: bool,
+
+    /// Maximum download cache size in megabytes.
+    #[arg(long, default_value_t = 1024)]
+    cache_max_mb: u64,
+
+    /// Never contact the internet; serve only the library and the cache.
+    #[arg(long, default_value_t = false)]
+    offline: bool,
+
+    /// Another PicoCover proxy asked after GameTDB (e.g. the public one for GBA covers).
+    #[arg(long)]
+    upstream_proxy: Option<String>,
+
+    /// HTTP timeout for upstream requests in seconds.
+    #[arg(long, default_value_t = 20)]
+    timeout_secs: u64,
+
+    /// Token required to read /stats (query `token` or `Authorization: Bearer`).
+    #[arg(long)]
+    stats_token: Option<String>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let state = Arc::new(build_state(&args)?);
+    let runtime = tokio::runtime::Runtime::new().context("Starting async runtime")?;
+    runtime.block_on(serve(args.bind, state))
+}
+
+async fn serve(bind: SocketAddr, state: Arc<AppState>) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(bind)
+        .await
+        .with_context(|| format!("Binding {}", bind))?;
+    println!("PicoCover proxy listening on http://{}/", bind);
+    axum::serve(
+        listener,
+        server::router(state).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async {
+        let _ = tokio::signal::ctrl_c().await;
+    })
+    .await
+    .context("Serving requests")
+}
+
+fn build_state(args: &Args) -> Result<AppState> {
+    let mut state = AppState::new();
+    state.stats_token = args.stats_token.clone();
+
+    if let Some(library) = &args.library {
+        let mut sources = ChainedSource::new();
+        for platform in Platform::ALL {
+            sources.push(Box::new(
+                LocalFolderSource::new(library.join(platform.as_str())).for_platforms(&[platform]),
+            ));
+        }
+        sources.push(Box::new(LocalFolderSource::new(library)));
+        println!("Library: {}", library.display());
+        state.library = Some(sources);
+    }
+
+    if !args.no_cache {
+        let dir = match &args.cache_dir {
+            Some(dir) => dir.clone(),
+            None => dirs::cache_dir()
+                .context("No user cache folder; pass --cache-dir or --no-cache")?
+                .join("pico-cover-proxy"),
+        };
+        let cache = DiskCache::open(&dir)
+            .with_context(|| format!("Opening cache {}", dir.display()))?
+            .with_max_bytes(args.cache_max_mb * 1024 * 1024);
+        if let Ok(pruned) = cache.prune() {
+            println!(
+                "Cache: {} ({} covers, {} MB)",
+                dir.display(),
+                pruned.remaining,
+                pruned.remaining_bytes / (1024 * 1024)
+            );
+        }
+        state.cache = Some(cache);
+    }
+
+    if !args.offline {
+        let mut client: Arc<dyn HttpClient> =
+            Arc::new(ReqwestClient::new(Duration::from_secs(args.timeout_secs))?);
+        client = Arc::new(RetryingClient::new(client, RetryPolicy::default()));
+        let mut upstream = ChainedSource::new().with(GameTdbSource::new(Arc::clone(&client)));
+        if let Some(url) = &args.upstream_proxy {
+            upstream.push(Box::new(ProxySource::new(client, url.as_str())));
+        }
+        state.upstream = Some(upstream);
+    }
+
+    Ok(state)
+}
@@ -0,0 +1,432 @@
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{ConnectInfo, Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use pico_cover_core::cache::{CacheEntry, DiskCache};
+use pico_cover_core::game::{GameCode, Platform};
+use pico_cover_core::http::HttpResponse;
+use pico_cover_core::source::{ChainedSource, CoverRequest, CoverSource};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+/// Browser cache lifetime, same as the Worker (30 days)
+const CACHE_TTL_SECS: u64 = 2_628_000;
+
+const CORS_HEADERS: [(HeaderName, &str); 3] = [
+    (header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"),
+    (header::ACCESS_CONTROL_ALLOW_METHODS, "GET, HEAD, OPTIONS"),
+    (header::ACCESS_CONTROL_ALLOW_HEADERS, "Content-Type"),
+];
+
+/// Shared server state
+pub struct AppState {
+    /// Hand-picked covers served as-is (`X-Cache: LIBRARY`)
+    pub library: Option<ChainedSource>,
+    /// Previously fetched upstream covers (`X-Cache: HIT`)
+    pub cache: Option<DiskCache>,
+    /// Remote sources queried on a miss (`X-Cache: MISS`)
+    pub upstream: Option<ChainedSource>,
+    /// Required `token` for `/stats`, if set
+    pub stats_token: Option<String>,
+    pub stats: Stats,
+}
+
+impl AppState {
+    /// Create a state with no sources; everything returns 404
+    pub fn new() -> Self {
+        Self {
+            library: None,
+            cache: None,
+            upstream: None,
+            stats_token: None,
+            stats: Stats::default(),
+        }
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// In-memory download counters
+#[derive(Default)]
+pub struct Stats {
+    downloads: AtomicU64,
+    users: Mutex<HashSet<String>>,
+}
+
+impl Stats {
+    fn track(&self, user: String) {
+        self.downloads.fetch_add(1, Ordering::Relaxed);
+        self.users.lock().unwrap().insert(user);
+    }
+
+    fn downloads(&self) -> u64 {
+        self.downloads.load(Ordering::Relaxed)
+    }
+
+    fn unique_users(&self) -> usize {
+        self.users.lock().unwrap().len()
+    }
+}
+
+/// Build the router, mirroring the Cloudflare Worker in `worker/src/index.ts`
+///
+/// - `GET /nds/{id}` and `GET /gba/{id}` serve the original cover image
+/// - `GET /stats` reports download counters
+///
+/// Covers are looked up in the local library first, then the disk cache,
+/// then the upstream sources (if any). Expired cache entries are still
+/// served (`X-Cache: STALE`) when there is no upstream or it fails.
+/// Responses carry the same CORS, cache and `X-Cache` headers as the Worker
+/// so clients can't tell the two apart.
+pub fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/stats", get(stats))
+        .route("/{platform}/{id}", get(cover).options(preflight))
+        .fallback(invalid_route)
+        .with_state(state)
+}
+
+async fn cover(
+    State(state): State<Arc<AppState>>,
+    Path((platform, id)): Path<(String, String)>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Response {
+    let Ok(platform) = platform.to_lowercase().parse::<Platform>() else {
+        return invalid_route().await;
+    };
+    let id = id.to_uppercase();
+    if id.len() != 4 {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "Invalid gameId. Must be exactly 4 characters.",
+        );
+    }
+    let Ok(code) = GameCode::from_bytes(id.as_bytes()) else {
+        return json_error(StatusCode::BAD_REQUEST, "Invalid gameId.");
+    };
+    let request = CoverRequest::new(code, platform);
+    let user = user_key(&headers, peer);
+
+    if let Some(library) = &state.library {
+        if let Ok(Some(found)) = library.fetch(&request).await {
+            state.stats.track(user);
+            println!("{}/{} LIBRARY {}", platform.as_str(), id, found.origin);
+            return image_response(found.bytes, "LIBRARY");
+        }
+    }
+
+    // Same key layout as the Worker's KV cache
+    let cache_key = format!("{}:{}", platform.as_str(), id);
+    let mut stale = None;
+    if let Some(cache) = &state.cache {
+        if let Some(entry) = cache.get(&cache_key) {
+            if entry.is_fresh(cache.max_age()) {
+                state.stats.track(user);
+                println!("{}/{} HIT", platform.as_str(), id);
+                return image_response(entry.body, "HIT");
+            }
+            stale = Some(entry);
+        }
+    }
+
+    // Old covers beat no cover when upstream is off or unreachable
+    let serve_stale = |stale: Option<CacheEntry>, user: String| {
+        stale.map(|entry| {
+            state.stats.track(user);
+            println!("{}/{} STALE", platform.as_str(), id);
+            image_response(entry.body, "STALE")
+        })
+    };
+
+    let Some(upstream) = &state.upstream else {
+        return serve_stale(stale, user).unwrap_or_else(|| not_found(platform, &id));
+    };
+    match upstream.fetch(&request).await {
+        Ok(Some(found)) => {
+            if let Some(cache) = &state.cache {
+                let response = HttpResponse {
+                    status: 200,
+                    headers: vec![(
+                        "content-type".to_string(),
+                        content_type(&found.bytes).to_string(),
+                    )],
+                    body: found.bytes.clone(),
+                };
+                if let Err(err) = cache.put(&cache_key, &response) {
+                    eprintln!("⚠ Caching {} failed: {}", cache_key, err);
+                }
+            }
+            state.stats.track(user);
+            println!("{}/{} MISS {}", platform.as_str(), id, found.origin);
+            image_response(found.bytes, "MISS")
+        }
+        Ok(None) => not_found(platform, &id),
+        Err(err) => {
+            eprintln!("⚠ {}/{} upstream failed: {}", platform.as_str(), id, err);
+            serve_stale(stale, user).unwrap_or_else(|| {
+                json_error(
+                    StatusCode::BAD_GATEWAY,
+                    &format!("Upstream failed: {}", err),
+                )
+            })
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct StatsQuery {
+    token: Option<String>,
+}
+
+async fn stats(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<StatsQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(expected) = &state.stats_token {
+        let bearer = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                value
+                    .strip_prefix("Bearer ")
+                    .or(value.strip_prefix("bearer "))
+            });
+        let token = query.token.as_deref().or(bearer);
+        if token != Some(expected.as_str()) {
+            return json_error(StatusCode::UNAUTHORIZED, "Unauthorized");
+        }
+    }
+
+    with_headers(
+        Json(json!({
+            "downloadsTotal": state.stats.downloads(),
+            "uniqueUsers": state.stats.unique_users(),
+        }))
+        .into_response(),
+    )
+}
+
+async fn preflight() -> Response {
+    with_headers(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn invalid_route() -> Response {
+    json_error(
+        StatusCode::BAD_REQUEST,
+        "Invalid platform. Must be /nds/{gameid} or /gba/{gameid}",
+    )
+}
+
+fn not_found(platform: Platform, id: &str) -> Response {
+    let body = json!({
+        "error": format!("Cover not found for {} game", platform.as_str().to_uppercase()),
+        "gameId": id,
+        "platform": platform.as_str(),
+    });
+    with_headers((StatusCode::NOT_FOUND, Json(body)).into_response())
+}
+
+fn json_error(status: StatusCode, message: &str) -> Response {
+    with_headers((status, Json(json!({ "error": message }))).into_response())
+}
+
+fn image_response(bytes: Vec<u8>, cache_status: &'static str) -> Response {
+    let headers = [
+        (header::CONTENT_TYPE, content_type(&bytes)),
+        (HeaderName::from_static("x-cache"), cache_status),
+    ];
+    with_headers((headers, bytes).into_response())
+}
+
+/// Add the CORS and `Cache-Control` headers every Worker response carries
+///
+/// Server errors are not cached, so clients retry once upstream recovers.
+fn with_headers(mut response: Response) -> Response {
+    let cacheable = !response.status().is_server_error();
+    let headers = response.headers_mut();
+    for (name, value) in CORS_HEADERS {
+        headers.insert(name, HeaderValue::from_static(value));
+    }
+    if cacheable {
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_str(&format!("public, max-age={}", CACHE_TTL_SECS)).unwrap(),
+        );
+    }
+    response
+}
+
+fn content_type(bytes: &[u8]) -> &'static str {
+    image::guess_format(bytes)
+        .map(|format| format.to_mime_type())
+        .unwrap_or("application/octet-stream")
+}
+
+/// Hash of client address and user agent used to count unique users
+fn user_key(headers: &HeaderMap, peer: SocketAddr) -> String {
+    let forwarded = headers
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(str::trim);
+    let ip = forwarded.map_or_else(|| peer.ip().to_string(), str::to_string);
+    let agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let digest = Sha256::digest(format!("{}|{}", ip, agent).as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::extract::connect_info::MockConnectInfo;
+    use axum::http::Request;
+    use pico_cover_core::http::ReqwestClient;
+    use pico_cover_core::source::{LocalFolderSource, ProxySource};
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    fn png() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::DynamicImage::new_rgb8(4, 4)
+            .write_to(
+                &mut std::io::Cursor::new(&mut bytes),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+        bytes
+    }
+
+    async fn get(state: Arc<AppState>, uri: &str) -> Response {
+        router(state)
+            .layer(MockConnectInfo(SocketAddr::from(([127, 0, 0, 1], 1234))))
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_routes() {
+        let state = Arc::new(AppState::new());
+        let response = get(Arc::clone(&state), "/snes/ABCE").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+
+        let response = get(Arc::clone(&state), "/nds/ABC").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = get(state, "/nds/ABCE").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(json_body(response).await["gameId"], "ABCE");
+    }
+
+    #[tokio::test]
+    async fn test_serves_library_and_cache() {
+        let library = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        std::fs::write(library.path().join("ABCE.png"), png()).unwrap();
+        let cache = DiskCache::open(cache_dir.path()).unwrap();
+        cache
+            .put(
+                "gba:BPEE",
+                &HttpResponse {
+                    status: 200,
+                    headers: Vec::new(),
+                    body: png(),
+                },
+            )
+            .unwrap();
+        let state = Arc::new(AppState {
+            library: Some(ChainedSource::new().with(LocalFolderSource::new(library.path()))),
+            cache: Some(cache),
+            ..AppState::new()
+        });
+
+        let response = get(Arc::clone(&state), "/nds/abce").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-cache"], "LIBRARY");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+
+        let response = get(Arc::clone(&state), "/gba/BPEE").await;
+        assert_eq!(response.headers()["x-cache"], "HIT");
+
+        let stats = json_body(get(state, "/stats").await).await;
+        assert_eq!(stats["downloadsTotal"], 2);
+        assert_eq!(stats["uniqueUsers"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_serves_stale_cache_without_upstream() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::open(cache_dir.path())
+            .unwrap()
+            .with_max_age(Duration::ZERO);
+        cache
+            .put(
+                "nds:ABCE",
+                &HttpResponse {
+                    status: 200,
+                    headers: Vec::new(),
+                    body: png(),
+                },
+            )
+            .unwrap();
+        let state = Arc::new(AppState {
+            cache: Some(cache),
+            ..AppState::new()
+        });
+
+        let response = get(Arc::clone(&state), "/nds/ABCE").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-cache"], "STALE");
+
+        // Nothing listens on the discard port, so upstream fails
+        let client = Arc::new(ReqwestClient::new(Duration::from_secs(5)).unwrap());
+        let state = Arc::new(AppState {
+            cache: state.cache.clone(),
+            upstream: Some(
+                ChainedSource::new().with(ProxySource::new(client, "http://127.0.0.1:9")),
+            ),
+            ..AppState::new()
+        });
+        let response = get(Arc::clone(&state), "/nds/ABCE").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-cache"], "STALE");
+
+        let response = get(state, "/nds/BBCE").await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        assert!(response.headers().get(header::CACHE_CONTROL).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stats_token() {
+        let state = Arc::new(AppState {
+            stats_token: Some("secret".to_string()),
+            ..AppState::new()
+        });
+        let response = get(Arc::clone(&state), "/stats").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = get(state, "/stats?token=secret").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
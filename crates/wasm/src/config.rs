@@ -0,0 +1,131 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use pico_cover_core::http::HttpClient;
+use pico_cover_core::source::{
+    ChainedSource, GameTdbSource, HttpTemplateSource, LibretroSource, OverrideSource, Overrides,
+    ProxySource, DEFAULT_LIBRETRO_URL, DEFAULT_PROXY_URL,
+};
+use serde::Deserialize;
+
+use crate::http::GlooClient;
+
+/// Proxy used when the page is served from a local dev server
+const LOCAL_PROXY_URL: &str = "http://localhost:8787/";
+
+/// Download settings set from JS with `configure`
+///
+/// ```js
+/// configure({
+///   baseUrl: "https://covers.example.org/",
+///   headers: { "X-Api-Key": "..." },
+///   timeoutMs: 10000,
+///   fallbacks: [
+///     { type: "proxy", url: "https://staging.example.org/" },
+///     { type: "gametdb", templates: ["https://art.gametdb.com/ds/{kind}/{region}/{id}.png"] },
+///   ],
+///   overrides: { games: { AMCE: { region: "JA" }, BPRE: "skip" } },
+/// })
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DownloadConfig {
+    /// Primary PicoCover proxy; picked from the page origin when unset
+    pub base_url: Option<String>,
+    /// Extra headers sent with every request
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Abort requests that take longer than this
+    pub timeout_ms: Option<u32>,
+    /// Endpoints tried in order after the primary proxy
+    #[serde(default)]
+    pub fallbacks: Vec<Endpoint>,
+    /// Per-game overrides applied before any endpoint is asked
+    #[serde(default)]
+    pub overrides: Overrides,
+}
+
+/// A cover endpoint tried after the primary proxy
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum Endpoint {
+    /// Another PicoCover proxy (`/{platform}/{id}`)
+    Proxy { url: String },
+    /// GameTDB-style templates with `{kind}`, `{region}` and `{id}` placeholders (NDS only)
+    Gametdb {
+        #[serde(default)]
+        templates: Vec<String>,
+        #[serde(default)]
+        regions: Vec<String>,
+    },
+    /// Any URL with `{platform}` and `{id}` placeholders
+    Template { url: String },
+    /// libretro-thumbnails boxart named by the ROM file name (needs `romPath`)
+    Libretro { url: Option<String> },
+}
+
+thread_local! {
+    static CONFIG: RefCell<DownloadConfig> = RefCell::new(DownloadConfig::default());
+}
+
+/// Replace the active download settings
+pub fn set(config: DownloadConfig) {
+    CONFIG.with(|current| *current.borrow_mut() = config);
+}
+
+/// Get a copy of the active download settings
+pub fn get() -> DownloadConfig {
+    CONFIG.with(|current| current.borrow().clone())
+}
+
+impl DownloadConfig {
+    /// Primary proxy URL, falling back to the dev server on localhost
+    pub fn base_url(&self) -> String {
+        if let Some(url) = &self.base_url {
+            return url.clone();
+        }
+        let origin = web_sys::window().and_then(|window| window.location().origin().ok());
+        match origin {
+            Some(origin) if origin.contains("localhost") || origin.contains("127.0.0.1") => {
+                LOCAL_PROXY_URL.to_string()
+            }
+            _ => DEFAULT_PROXY_URL.to_string(),
+        }
+    }
+
+    /// Build the source chain: overrides, primary proxy, then each fallback in order
+    pub fn sources(&self) -> OverrideSource {
+        let client: Arc<dyn HttpClient> =
+            Arc::new(GlooClient::new(self.headers.clone(), self.timeout_ms));
+        let mut chain =
+            ChainedSource::new().with(ProxySource::new(Arc::clone(&client), self.base_url()));
+
+        for (index, endpoint) in self.fallbacks.iter().enumerate() {
+            let client = Arc::clone(&client);
+            match endpoint {
+                Endpoint::Proxy { url } => chain.push(Box::new(ProxySource::new(client, url))),
+                Endpoint::Gametdb { templates, regions } => {
+                    let mut source = GameTdbSource::new(client);
+                    if !templates.is_empty() {
+                        source = source.with_templates(templates.clone());
+                    }
+                    if !regions.is_empty() {
+                        source = source.with_regions(regions.clone());
+                    }
+                    chain.push(Box::new(source));
+                }
+                Endpoint::Libretro { url } => chain.push(Box::new(LibretroSource::remote(
+                    client,
+                    url.as_deref().unwrap_or(DEFAULT_LIBRETRO_URL),
+                ))),
+                Endpoint::Template { url } => chain.push(Box::new(HttpTemplateSource::new(
+                    client,
+                    format!("fallback-{}", index + 1),
+                    url,
+                ))),
+            }
+        }
+        OverrideSource::new(Arc::new(chain), Arc::new(self.overrides.clone()), client)
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use gloo_net::http::{Method, RequestBuilder};
+use pico_cover_core::errors::{Error, FetchErrorKind, Result};
+use pico_cover_core::http::{HttpClient, HttpMethod, HttpRequest, HttpResponse};
+use send_wrapper::SendWrapper;
+use web_sys::AbortSignal;
+
+/// HTTP client backed by the browser `fetch` API
+///
+/// Browser futures are not `Send`; the wasm runtime is single threaded, so they
+/// are wrapped to satisfy the `HttpClient` bound.
+#[derive(Default)]
+pub struct GlooClient {
+    headers: BTreeMap<String, String>,
+    timeout_ms: Option<u32>,
+}
+
+impl GlooClient {
+    /// Create a client adding `headers` to every request and aborting after `timeout_ms`
+    pub fn new(headers: BTreeMap<String, String>, timeout_ms: Option<u32>) -> Self {
+        Self {
+            headers,
+            timeout_ms,
+        }
+    }
+}
+
+#[async_trait]
+impl HttpClient for GlooClient {
+    async fn send(&self, mut request: HttpRequest) -> Result<HttpResponse> {
+        for (name, value) in &self.headers {
+            let exists = request
+                .headers
+                .iter()
+                .any(|(existing, _)| existing.eq_ignore_ascii_case(name));
+            if !exists {
+                request.headers.push((name.clone(), value.clone()));
+            }
+        }
+        SendWrapper::new(fetch(request, self.timeout_ms)).await
+    }
+}
+
+async fn fetch(request: HttpRequest, timeout_ms: Option<u32>) -> Result<HttpResponse> {
+    let method = match request.method {
+        HttpMethod::Get => Method::GET,
+        HttpMethod::Head => Method::HEAD,
+    };
+    let mut builder = RequestBuilder::new(&request.url).method(method);
+    for (name, value) in &request.headers {
+        builder = builder.header(name, value);
+    }
+    let signal = timeout_ms.map(AbortSignal::timeout_with_u32);
+    builder = builder.abort_signal(signal.as_ref());
+
+    let response = builder
+        .send()
+        .await
+        .map_err(|e| Error::fetch(FetchErrorKind::Transient, format!("{}: {}", request.url, e)))?;
+    let headers = response.headers().entries().collect();
+    let status = response.status();
+    let body = response
+        .binary()
+        .await
+        .map_err(|e| Error::fetch(FetchErrorKind::Transient, format!("{}: {}", request.url, e)))?;
+
+    Ok(HttpResponse {
+        status,
+        headers,
+        body,
+    })
+}
@@ -1,7 +1,14 @@
-use pico_cover_core::game::GameCode;
-use pico_cover_core::image_processing::ImageProcessor;
+use pico_cover_core::errors::{Error, FetchErrorKind};
+use pico_cover_core::game::{GameCode, Platform};
+use pico_cover_core::image_processing::{ImageProcessor, RenderOptions};
+use pico_cover_core::source::{CoverRequest, CoverSource, Overrides};
 use wasm_bindgen::prelude::*;
 
+mod config;
+mod http;
+
+use config::DownloadConfig;
+
 #[wasm_bindgen(start)]
 pub fn init() {
     console_error_panic_hook::set_once();
@@ -68,6 +75,62 @@ pub fn process_cover_image(
         .map_err(|e| JsValue::from_str(&format!("Failed to process image: {}", e)))
 }
 
+/// Process cover image with render options (e.g. `{ display: "ds-lite" }`)
+#[wasm_bindgen]
+pub async fn process_cover_image_with_options(
+    image_data: &[u8],
+    options: JsValue,
+) -> std::result::Result<Vec<u8>, JsValue> {
+    crate::yield_to_event_loop().await;
+
+    let options = parse_render_options(options)?;
+    ImageProcessor::process_cover_with_options(image_data, &options)
+        .map_err(|e| JsValue::from_str(&format!("Failed to process image: {}", e)))
+}
+
+/// Render a cover and measure its quality
+///
+/// Returns `{ bmp: Uint8Array, metrics: { psnr, ssim, paletteEntries, needsReview } }`.
+#[wasm_bindgen]
+pub async fn render_cover_report(
+    image_data: &[u8],
+    options: JsValue,
+) -> std::result::Result<JsValue, JsValue> {
+    crate::yield_to_event_loop().await;
+
+    let mut options = parse_render_options(options)?;
+    options.compute_metrics = true;
+
+    let rendered = ImageProcessor::process_cover_report(image_data, &options)
+        .map_err(|e| JsValue::from_str(&format!("Failed to process image: {}", e)))?;
+
+    let report = js_sys::Object::new();
+    js_sys::Reflect::set(
+        &report,
+        &"bmp".into(),
+        &js_sys::Uint8Array::from(rendered.bmp.as_slice()),
+    )?;
+    if let Some(metrics) = rendered.metrics {
+        let value = serde_wasm_bindgen::to_value(&metrics)?;
+        js_sys::Reflect::set(
+            &value,
+            &"needsReview".into(),
+            &metrics.needs_review().into(),
+        )?;
+        js_sys::Reflect::set(&report, &"metrics".into(), &value)?;
+    }
+    Ok(report.into())
+}
+
+fn parse_render_options(options: JsValue) -> std::result::Result<RenderOptions, JsValue> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(RenderOptions::default());
+    }
+
+    serde_wasm_bindgen::from_value(options)
+        .map_err(|e| JsValue::from_str(&format!("Invalid render options: {}", e)))
+}
+
 /// Helper function to yield to event loop for better concurrency
 #[wasm_bindgen]
 pub async fn yield_to_event_loop() {
@@ -76,43 +139,122 @@ pub async fn yield_to_event_loop() {
     let _ = JsFuture::from(promise).await;
 }
 
-/// Download cover from PicoCover proxy
+/// Set the proxy base URL, extra headers, timeout and fallback endpoints
+///
+/// Accepts `{ baseUrl?, headers?, timeoutMs?, fallbacks?, overrides? }`; each
+/// fallback is `{ type: "proxy", url }`, `{ type: "gametdb", templates?, regions? }`,
+/// `{ type: "template", url }` or `{ type: "libretro", url? }`, and `overrides`
+/// uses the override file format without `image` entries.
+/// Unset fields go back to their defaults.
 #[wasm_bindgen]
-pub async fn download_cover(
-    game_code: String,
-    platform: String,
-) -> std::result::Result<Vec<u8>, JsValue> {
-    let base_url = if let Some(window) = web_sys::window() {
-        if let Ok(location) = window.location().origin() {
-            if location.contains("localhost") || location.contains("127.0.0.1") {
-                "http://localhost:8787/"
-            } else {
-                "https://picocover.retrosave.games/"
-            }
-        } else {
-            "https://picocover.retrosave.games/"
-        }
+pub fn configure(options: JsValue) -> std::result::Result<(), JsValue> {
+    let options = if options.is_undefined() || options.is_null() {
+        DownloadConfig::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(|e| {
+            cover_error(
+                "invalid-config",
+                &format!("Invalid configuration: {}", e),
+                None,
+            )
+        })?
+    };
+    check_overrides(&options.overrides)?;
+    config::set(options);
+    Ok(())
+}
+
+/// Replace only the per-game overrides, keeping the rest of the configuration
+///
+/// Takes the override file format without `image` entries; `null` or
+/// `undefined` clears them.
+#[wasm_bindgen]
+pub fn set_overrides(overrides: JsValue) -> std::result::Result<(), JsValue> {
+    let overrides: Overrides = if overrides.is_undefined() || overrides.is_null() {
+        Overrides::default()
     } else {
-        "https://picocover.retrosave.games/"
+        serde_wasm_bindgen::from_value(overrides).map_err(|e| {
+            cover_error("invalid-config", &format!("Invalid overrides: {}", e), None)
+        })?
     };
-    let platform_lower = platform.to_lowercase();
+    check_overrides(&overrides)?;
+    let mut options = config::get();
+    options.overrides = overrides;
+    config::set(options);
+    Ok(())
+}
 
-    // Validate platform
-    if !["nds", "gba"].contains(&platform_lower.as_str()) {
-        return Err(JsValue::from_str(
-            "Invalid platform. Must be 'nds' or 'gba'",
+/// Browsers can't read files by path, so image overrides would always fail
+fn check_overrides(overrides: &Overrides) -> std::result::Result<(), JsValue> {
+    if overrides.entries().any(|entry| entry.image.is_some()) {
+        return Err(cover_error(
+            "invalid-config",
+            "Invalid configuration: overrides can't use \"image\" in the browser; use \"url\"",
+            None,
         ));
     }
+    Ok(())
+}
 
-    let url = format!("{}{}/{}", base_url, platform_lower, game_code);
+/// Download a cover from the configured proxy and fallbacks
+///
+/// `rom_path` (relative to the card root) lets path overrides match. Rejects
+/// with an `Error` carrying a `kind` (`not-found`, `skipped`, `http-status`,
+/// `network`, `invalid-image`, `invalid-platform`, `invalid-game-code`, ...)
+/// and, for unexpected HTTP responses, the `status`.
+#[wasm_bindgen]
+pub async fn download_cover(
+    game_code: String,
+    platform: String,
+    rom_path: Option<String>,
+) -> std::result::Result<Vec<u8>, JsValue> {
+    let platform: Platform = platform
+        .parse()
+        .map_err(|e: Error| cover_error("invalid-platform", &e.to_string(), None))?;
+    let code = GameCode::from_bytes(game_code.as_bytes())
+        .map_err(|e| cover_error("invalid-game-code", &e.to_string(), None))?;
 
-    if let Ok(response) = gloo_net::http::Request::get(&url).send().await {
-        if response.ok() {
-            if let Ok(bytes) = response.binary().await {
-                return Ok(bytes);
-            }
-        }
+    let config = config::get();
+    let mut request = CoverRequest::new(code, platform);
+    if let Some(path) = rom_path {
+        request = request.with_rom_path(path);
+    }
+    if config
+        .overrides
+        .lookup(&request)
+        .is_some_and(|entry| entry.skip)
+    {
+        return Err(cover_error("skipped", "Cover skipped by an override", None));
     }
 
-    Err(JsValue::from_str("No cover found for this game"))
+    match config.sources().fetch(&request).await {
+        Ok(Some(found)) => Ok(found.bytes),
+        Ok(None) => Err(cover_error(
+            "not-found",
+            "No cover found for this game",
+            None,
+        )),
+        Err(err) => Err(fetch_error(&err)),
+    }
+}
+
+/// Convert a fetch failure into a JS error
+fn fetch_error(err: &Error) -> JsValue {
+    let kind = match (err.status(), err.fetch_kind()) {
+        (Some(_), _) => "http-status",
+        (None, Some(FetchErrorKind::Transient)) => "network",
+        (None, Some(kind)) => kind.as_str(),
+        (None, None) => "other",
+    };
+    cover_error(kind, &err.to_string(), err.status())
+}
+
+/// Build a JS `Error` with `kind` and optional `status` properties
+fn cover_error(kind: &str, message: &str, status: Option<u16>) -> JsValue {
+    let error = js_sys::Error::new(message);
+    let _ = js_sys::Reflect::set(&error, &"kind".into(), &kind.into());
+    if let Some(status) = status {
+        let _ = js_sys::Reflect::set(&error, &"status".into(), &status.into());
+    }
+    error.into()
 }
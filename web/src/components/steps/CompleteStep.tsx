@@ -2,6 +2,7 @@ import { Button, Card, CardBody, Divider } from "@heroui/react";
 import confetti from "@hiseb/confetti";
 import { useEffect } from "react";
 import readySound from "/ready.mp3";
+import type { QualityMetrics } from "../../lib/wasm";
 
 type ProcessingStatus = {
   total: number;
@@ -11,14 +12,21 @@ type ProcessingStatus = {
   errors: number;
 };
 
+type ReviewedCover = {
+  name: string;
+  metrics: QualityMetrics;
+};
+
 type CompleteStepProps = {
   status: ProcessingStatus;
+  reviewCovers: ReviewedCover[];
   rootDirName?: string;
   onRestart: () => void;
 };
 
 export default function CompleteStep({
   status,
+  reviewCovers,
   rootDirName,
   onRestart,
 }: CompleteStepProps) {
@@ -96,6 +104,27 @@ export default function CompleteStep({
         </code>
       </div>
 
+      {reviewCovers.length > 0 && (
+        <div className="rounded-lg border border-yellow-200/60 dark:border-yellow-900/50 bg-yellow-50/60 dark:bg-yellow-950/20 text-left max-w-2xl mx-auto">
+          <div className="px-4 py-2 text-sm font-semibold text-gray-700 dark:text-gray-200 border-b border-yellow-200/60 dark:border-yellow-900/50">
+            Worth re-rendering with other settings ({reviewCovers.length})
+          </div>
+          <div className="max-h-48 overflow-auto">
+            <ul className="divide-y divide-yellow-200/60 dark:divide-yellow-900/50">
+              {reviewCovers.map((cover, index) => (
+                <li key={`${cover.name}-${index}`} className="px-4 py-2 flex justify-between gap-4">
+                  <span className="text-sm text-gray-700 dark:text-gray-200 truncate">{cover.name}</span>
+                  <span className="text-xs text-gray-500 dark:text-gray-400 whitespace-nowrap">
+                    PSNR {cover.metrics.psnr.toFixed(1)} dB · SSIM {cover.metrics.ssim.toFixed(3)} ·{" "}
+                    {cover.metrics.paletteEntries} colours
+                  </span>
+                </li>
+              ))}
+            </ul>
+          </div>
+        </div>
+      )}
+
       <Button
         size="lg"
         color="primary"
@@ -1,14 +1,43 @@
-import { Button, Card, CardBody } from '@heroui/react'
+import { Button, Card, CardBody, Checkbox, Select, SelectItem } from '@heroui/react'
+import type { DisplayProfile, RenderOptions, SharpeningPreset } from '../../lib/wasm'
+
+const DISPLAY_PROFILES: Array<{ key: DisplayProfile; label: string }> = [
+  { key: 'ds-phat', label: 'DS (original)' },
+  { key: 'ds-lite', label: 'DS Lite' },
+  { key: 'dsi', label: 'DSi' },
+  { key: 'emulator', label: 'Emulator / PC' },
+]
+
+const SHARPENING_PRESETS: Array<{ key: SharpeningPreset; label: string }> = [
+  { key: 'off', label: 'Off' },
+  { key: 'soft', label: 'Soft' },
+  { key: 'balanced', label: 'Balanced' },
+  { key: 'crisp', label: 'Crisp' },
+  { key: 'crisp-text', label: 'Crisp text' },
+]
 
 type ProcessReadyStepProps = {
   romCount: number
   romFiles: Array<{ name: string; path: string }>
   rootDirName?: string
+  renderOptions: RenderOptions
+  onRenderOptionsChange: (options: RenderOptions) => void
   onBack: () => void
   onStart: () => void
 }
 
-export default function ProcessReadyStep({ romCount, romFiles, rootDirName, onBack, onStart }: ProcessReadyStepProps) {
+export default function ProcessReadyStep({
+  romCount,
+  romFiles,
+  rootDirName,
+  renderOptions,
+  onRenderOptionsChange,
+  onBack,
+  onStart,
+}: ProcessReadyStepProps) {
+  const display = renderOptions.display ?? 'emulator'
+  const sharpen = typeof renderOptions.sharpen === 'string' ? renderOptions.sharpen : 'off'
+
   return (
     <div className="space-y-6">
       <div className="text-center">
@@ -44,6 +73,38 @@ export default function ProcessReadyStep({ romCount, romFiles, rootDirName, onBa
         </CardBody>
       </Card>
 
+      <div className="rounded-lg border border-gray-200/60 dark:border-gray-700/60 bg-white/60 dark:bg-gray-900/40 p-4 space-y-3">
+        <p className="text-sm font-semibold text-gray-700 dark:text-gray-200">Cover Rendering</p>
+        <div className="grid grid-cols-1 md:grid-cols-2 gap-3">
+          <Select
+            label="Screen"
+            disallowEmptySelection
+            selectedKeys={[display]}
+            onChange={(e) => onRenderOptionsChange({ ...renderOptions, display: e.target.value as DisplayProfile })}
+          >
+            {DISPLAY_PROFILES.map(profile => (
+              <SelectItem key={profile.key}>{profile.label}</SelectItem>
+            ))}
+          </Select>
+          <Select
+            label="Sharpening"
+            disallowEmptySelection
+            selectedKeys={[sharpen]}
+            onChange={(e) => onRenderOptionsChange({ ...renderOptions, sharpen: e.target.value as SharpeningPreset })}
+          >
+            {SHARPENING_PRESETS.map(preset => (
+              <SelectItem key={preset.key}>{preset.label}</SelectItem>
+            ))}
+          </Select>
+        </div>
+        <Checkbox
+          isSelected={renderOptions.linearLight ?? false}
+          onChange={(e) => onRenderOptionsChange({ ...renderOptions, linearLight: e.target.checked })}
+        >
+          <span className="text-sm text-gray-700 dark:text-gray-300">Resize in linear light</span>
+        </Checkbox>
+      </div>
+
       {romFiles.length > 0 && (
         <div className="rounded-lg border border-gray-200/60 dark:border-gray-700/60 bg-white/60 dark:bg-gray-900/40">
           <div className="px-4 py-2 text-sm font-semibold text-gray-700 dark:text-gray-200 border-b border-gray-200/60 dark:border-gray-700/60">
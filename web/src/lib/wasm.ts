@@ -12,6 +12,92 @@ export interface CoverResult {
   coverData: Uint8Array
 }
 
+export type DisplayProfile = 'ds-phat' | 'ds-lite' | 'dsi' | 'emulator'
+
+export type SharpeningPreset = 'off' | 'soft' | 'balanced' | 'crisp' | 'crisp-text'
+
+export interface UnsharpMask {
+  sigma: number
+  amount: number
+  threshold: number
+}
+
+export interface RenderOptions {
+  display?: DisplayProfile
+  linearLight?: boolean
+  sharpen?: SharpeningPreset | { custom: UnsharpMask }
+}
+
+export interface QualityMetrics {
+  psnr: number
+  ssim: number
+  paletteEntries: number
+  needsReview: boolean
+}
+
+export interface CoverReport {
+  bmp: Uint8Array
+  metrics?: QualityMetrics
+}
+
+export type CoverEndpoint =
+  | { type: 'proxy'; url: string }
+  | { type: 'gametdb'; templates?: string[]; regions?: string[] }
+  | { type: 'template'; url: string }
+  | { type: 'libretro'; url?: string }
+
+export type ArtKind =
+  | 'cover'
+  | 'cover-m'
+  | 'cover-s'
+  | 'cover-hq'
+  | 'box3d'
+  | 'cart'
+  | 'full'
+  | 'full-hq'
+
+/** A per-game override; `image` files only work in the native app */
+export type CoverOverride =
+  | 'skip'
+  | {
+      skip?: boolean
+      code?: string
+      region?: string
+      artKind?: ArtKind
+      url?: string
+      image?: string
+    }
+
+export interface CoverOverrides {
+  games?: Record<string, CoverOverride>
+  paths?: Record<string, CoverOverride>
+}
+
+export interface DownloadConfig {
+  baseUrl?: string
+  headers?: Record<string, string>
+  timeoutMs?: number
+  fallbacks?: CoverEndpoint[]
+  overrides?: CoverOverrides
+}
+
+export type CoverErrorKind =
+  | 'not-found'
+  | 'skipped'
+  | 'http-status'
+  | 'network'
+  | 'invalid-image'
+  | 'permanent'
+  | 'invalid-platform'
+  | 'invalid-game-code'
+  | 'invalid-config'
+  | 'other'
+
+export interface CoverError extends Error {
+  kind: CoverErrorKind
+  status?: number
+}
+
 export interface ImageResult {
   bmpData: Uint8Array
   width: number
@@ -34,11 +120,34 @@ export async function extractGameCode(fileBytes: Uint8Array): Promise<string> {
 }
 
 /**
- * Download cover for a game
+ * Set the proxy base URL, extra headers, timeout, fallback endpoints and overrides
+ */
+export async function configureDownloads(config: DownloadConfig = {}): Promise<void> {
+  const wasm = await initWasm()
+  ;(wasm as any).configure(config)
+}
+
+/**
+ * Replace the per-game overrides, keeping the rest of the download settings;
+ * pass nothing to clear them
  */
-export async function downloadCover(gameCode: string, platform: 'nds' | 'gba'): Promise<Uint8Array> {
+export async function setOverrides(overrides?: CoverOverrides): Promise<void> {
   const wasm = await initWasm()
-  return wasm.download_cover(gameCode, platform)
+  ;(wasm as any).set_overrides(overrides ?? null)
+}
+
+/**
+ * Download cover for a game; rejects with a {@link CoverError}
+ *
+ * `romPath` (relative to the card root) lets path overrides match.
+ */
+export async function downloadCover(
+  gameCode: string,
+  platform: 'nds' | 'gba',
+  romPath?: string
+): Promise<Uint8Array> {
+  const wasm = await initWasm()
+  return wasm.download_cover(gameCode, platform, romPath)
 }
 
 /**
@@ -53,6 +162,28 @@ export async function processCoverImageAsync(
   return (wasm as any).process_cover_image_async(imageData, width, height)
 }
 
+/**
+ * Process cover image with render options (display colour tuning, ...)
+ */
+export async function processCoverImageWithOptions(
+  imageData: Uint8Array,
+  options: RenderOptions = {}
+): Promise<Uint8Array> {
+  const wasm = await initWasm()
+  return (wasm as any).process_cover_image_with_options(imageData, options)
+}
+
+/**
+ * Render a cover and measure PSNR/SSIM so badly quantized covers can be flagged
+ */
+export async function renderCoverReport(
+  imageData: Uint8Array,
+  options: RenderOptions = {}
+): Promise<CoverReport> {
+  const wasm = await initWasm()
+  return (wasm as any).render_cover_report(imageData, options)
+}
+
 /**
  * Process cover image (resize + convert to BMP) - sync version for backwards compatibility
  */
@@ -13,6 +13,7 @@ import ScanStep from '../components/steps/ScanStep'
 import ProcessReadyStep from '../components/steps/ProcessReadyStep'
 import ProcessingStep from '../components/steps/ProcessingStep'
 import CompleteStep from '../components/steps/CompleteStep'
+import type { CoverReport, QualityMetrics, RenderOptions } from '../lib/wasm'
 
 // WASM types
 type WasmModule = typeof PicoWasm
@@ -45,10 +46,11 @@ export default function App() {
   const [rootDir, setRootDir] = useState<FileSystemDirectoryHandle | null>(null)
   const [romFiles, setRomFiles] = useState<RomFile[]>([])
   const [status, setStatus] = useState<ProcessingStatus>({ total: 0, processed: 0, saved: 0, skipped: 0, errors: 0 })
-  const [dimensions] = useState({ width: 128, height: 96 })
   const [logs, setLogs] = useState<string[]>([])
   const [showLogs, setShowLogs] = useState(false)
   const [includeGba, setIncludeGba] = useState(true)
+  const [renderOptions, setRenderOptions] = useState<RenderOptions>({})
+  const [reviewCovers, setReviewCovers] = useState<Array<{ name: string; metrics: QualityMetrics }>>([])
 
   useEffect(() => {
     document.documentElement.classList.add('dark')
@@ -311,6 +313,7 @@ export default function App() {
 
     setProcessing(true)
     setStatus({ total: filesToProcess.length, processed: 0, saved: 0, skipped: 0, errors: 0 })
+    setReviewCovers([])
     addLog('Starting batch processing...', 'info')
 
     const isFallbackMode = !rootDir
@@ -320,6 +323,7 @@ export default function App() {
 
     if (isFallbackMode) {
       zip = new JSZip()
+      ;(wasm as any).set_overrides(null)
       addLog('Preparing ZIP export (no folder access)', 'info')
     } else {
       // Get or create _pico/covers/nds and _pico/covers/gba directories
@@ -336,6 +340,20 @@ export default function App() {
         setProcessing(false)
         return
       }
+
+      // Per-game overrides kept on the card; a card without the file must not
+      // keep the previous card's
+      ;(wasm as any).set_overrides(null)
+      try {
+        const overridesFile = await (await picoDir.getFileHandle('pico-cover-overrides.json')).getFile()
+        const overrides = JSON.parse(await overridesFile.text())
+        ;(wasm as any).set_overrides(overrides)
+        addLog('Loaded per-game overrides from _pico/pico-cover-overrides.json', 'info')
+      } catch (error) {
+        if ((error as Error).name !== 'NotFoundError') {
+          addLog(`Ignoring overrides file: ${(error as Error).message}`, 'error')
+        }
+      }
     }
 
     const maxConcurrent = 4
@@ -369,10 +387,18 @@ export default function App() {
         // Download cover using WASM
         let imageData: Uint8Array | null = null
         try {
-          imageData = await wasm.download_cover(rom.id, rom.type)
+          imageData = await wasm.download_cover(rom.id, rom.type, rom.path)
           addLog(`Downloaded: ${rom.name} (${rom.id}) [${rom.type.toUpperCase()}]`, 'success')
         } catch (error) {
-          addLog(`Failed: ${rom.name} (no cover found)`, 'error')
+          if ((error as { kind?: string }).kind === 'skipped') {
+            addLog(`Skipped: ${rom.name} (override)`, 'info')
+            setStatus(prev => ({ ...prev, processed: prev.processed + 1, skipped: prev.skipped + 1 }))
+            return
+          }
+          const reason = (error as { kind?: string }).kind === 'not-found'
+            ? 'no cover found'
+            : (error as Error).message
+          addLog(`Failed: ${rom.name} (${reason})`, 'error')
           setStatus(prev => ({ ...prev, processed: prev.processed + 1, errors: prev.errors + 1 }))
           return
         }
@@ -383,8 +409,17 @@ export default function App() {
           return
         }
 
-        // Process with WASM
-        const bmpData = await (wasm as any).process_cover_image_async(imageData, dimensions.width, dimensions.height)
+        // Process with WASM, measuring how well the palette kept the art
+        const report: CoverReport = await (wasm as any).render_cover_report(imageData, renderOptions)
+        const bmpData = report.bmp
+        if (report.metrics?.needsReview) {
+          const metrics = report.metrics
+          setReviewCovers(prev => [...prev, { name: rom.name, metrics }])
+          addLog(
+            `Check: ${rom.name} quantized poorly (PSNR ${metrics.psnr.toFixed(1)} dB, SSIM ${metrics.ssim.toFixed(3)})`,
+            'info'
+          )
+        }
 
         if (isFallbackMode && zip) {
           zip.file(`_pico/covers/${targetDir}/${bmpFilename}`, new Uint8Array(bmpData))
@@ -548,6 +583,8 @@ export default function App() {
                 romCount={romFiles.length}
                 romFiles={romFiles.map(rom => ({ name: rom.name, path: rom.path }))}
                 rootDirName={rootDir?.name}
+                renderOptions={renderOptions}
+                onRenderOptionsChange={setRenderOptions}
                 onBack={() => {
                   setCurrentStep('select')
                   setRomFiles([])
@@ -568,12 +605,14 @@ export default function App() {
             {currentStep === 'complete' && (
               <CompleteStep
                 status={status}
+                reviewCovers={reviewCovers}
                 rootDirName={rootDir?.name}
                 onRestart={() => {
                   setCurrentStep('welcome')
                   setRomFiles([])
                   setRootDir(null)
                   setStatus({ total: 0, processed: 0, saved: 0, skipped: 0, errors: 0 })
+                  setReviewCovers([])
                   setLogs([])
                 }}
               />